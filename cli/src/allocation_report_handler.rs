@@ -0,0 +1,65 @@
+use std::path::{Path, PathBuf};
+
+use allocation_report::classification::Classifications;
+use allocation_report::report::{AllocationConf, Limits};
+use shared_contracts::models::instrument::InstrumentMaster;
+use util::report::ReportFormat;
+
+use crate::error::CliError;
+use crate::market_data_import_handler;
+use crate::mbank_trade_report_csv_handler;
+
+/// `market_data` is a list of GPW daily statistics xls files, a single market data store
+/// directory or a normalized market data csv.
+pub fn handle(
+    trade_orders_csv: &Path,
+    market_data: &[PathBuf],
+    classification_csv: Option<&Path>,
+    limits: Limits,
+    top_n: usize,
+    format: ReportFormat,
+    instruments: &InstrumentMaster,
+) -> Result<(), CliError> {
+    let normalized_orders_csv =
+        mbank_trade_report_csv_handler::normalize(trade_orders_csv, instruments)?;
+
+    let market_data_csv = util::file::new_file_with_suffix(trade_orders_csv, "market_data.csv")?;
+    let source = market_data_import_handler::source(market_data, &market_data_csv)?;
+
+    let classifications = match classification_csv {
+        Some(csv) => Classifications::load(csv, instruments)?,
+        None => Classifications::default(),
+    };
+
+    let allocation_report = util::file::new_file_with_suffix(
+        trade_orders_csv,
+        &format!("allocation.{}", format.extension()),
+    )?;
+    let groups_report = util::file::new_file_with_suffix(
+        trade_orders_csv,
+        &format!("allocation_groups.{}", format.extension()),
+    )?;
+
+    let conf = AllocationConf {
+        trade_orders_csv: &normalized_orders_csv,
+        market_data: source,
+        instruments,
+        classifications: &classifications,
+        limits,
+        top_n,
+        output_file: &allocation_report,
+        groups_file: &groups_report,
+        format,
+    };
+    let allocation = allocation_report::report::calculate_and_save(conf)?;
+
+    println!(
+        "Top {} positions {:.2}%, Herfindahl index {:.4}",
+        allocation.top_n, allocation.top_n_pct, allocation.herfindahl
+    );
+    for warning in &allocation.warnings {
+        println!("Warning: {warning}");
+    }
+    println!("Allocation saved to {}", allocation_report.display());
+    Ok(())
+}
@@ -0,0 +1,150 @@
+use std::path::{Path, PathBuf};
+
+use backtest::engine::{BacktestConf, FillModel, Strategy};
+use backtest::market::PriceHistory;
+use backtest::strategies::{SmaCross, TargetDiscount};
+use chrono::NaiveDate;
+use shared_contracts::errors::PortfolioError;
+use shared_contracts::models::instrument::InstrumentMaster;
+use shared_contracts::models::trade_order::OrderStatus;
+use util::money::Commission;
+use util::report::ReportFormat;
+
+use crate::error::CliError;
+use crate::market_data_import_handler;
+
+pub enum StrategyArgs<'a> {
+    SmaCross {
+        universe: &'a [String],
+        fast: usize,
+        slow: usize,
+    },
+    TargetDiscount {
+        upside_csv: &'a Path,
+        discount_pct: f64,
+    },
+}
+
+pub struct BacktestOptions<'a> {
+    pub strategy: StrategyArgs<'a>,
+    pub position_value: f64,
+    pub cash: f64,
+    pub commission: Commission,
+    pub start: Option<NaiveDate>,
+    pub end: Option<NaiveDate>,
+    pub benchmark: &'a str,
+    pub format: ReportFormat,
+}
+
+/// Writes the simulated orders to `orders_csv`, then the profit and benchmark reports
+/// of the filled ones next to it.
+///
+/// `market_data` is a list of GPW daily statistics xls files, a single market data store
+/// directory or a normalized market data csv.
+pub fn handle(
+    orders_csv: &Path,
+    market_data: &[PathBuf],
+    options: BacktestOptions,
+    instruments: &InstrumentMaster,
+) -> Result<(), CliError> {
+    let market_data_csv = util::file::new_file_with_suffix(orders_csv, "market_data.csv")?;
+    let source = market_data_import_handler::source(market_data, &market_data_csv)?;
+    let history = PriceHistory::load(&source, instruments)?;
+
+    let mut strategy: Box<dyn Strategy> = match options.strategy {
+        StrategyArgs::SmaCross {
+            universe,
+            fast,
+            slow,
+        } => {
+            if universe.is_empty() {
+                return Err(PortfolioError::InputError(
+                    "sma-cross needs a --universe of instruments".into(),
+                )
+                .into());
+            }
+            if fast == 0 || fast >= slow {
+                return Err(PortfolioError::InvalidValue(format!(
+                    "fast SMA of {fast} sessions must be shorter than the slow one of {slow}"
+                ))
+                .into());
+            }
+            Box::new(SmaCross {
+                universe: universe
+                    .iter()
+                    .map(|i| instruments.symbol(i).to_string())
+                    .collect(),
+                fast,
+                slow,
+                position_value: options.position_value,
+            })
+        }
+        StrategyArgs::TargetDiscount {
+            upside_csv,
+            discount_pct,
+        } => Box::new(TargetDiscount::new(
+            recommendations::import::load(upside_csv)?,
+            instruments,
+            discount_pct,
+            options.position_value,
+        )),
+    };
+
+    let conf = BacktestConf {
+        cash: options.cash,
+        start: options.start,
+        end: options.end,
+        fill: FillModel {
+            commission: options.commission,
+        },
+    };
+    let backtest = backtest::engine::run(strategy.as_mut(), &history, &conf)?;
+    backtest::engine::save_orders(&backtest.orders, orders_csv)?;
+
+    let filled = backtest
+        .orders
+        .iter()
+        .filter(|o| matches!(o.status, OrderStatus::Filled | OrderStatus::PartiallyFilled))
+        .count();
+    println!(
+        "{} orders, {} filled, saved to {}",
+        backtest.orders.len(),
+        filled,
+        orders_csv.display()
+    );
+    if let Some(last_session) = backtest.last_session {
+        println!(
+            "Value on {}: {:.2} from {:.2} of cash, {:.2} in cash",
+            last_session, backtest.final_value, options.cash, backtest.account.cash
+        );
+    }
+    if filled == 0 {
+        println!("No order filled, reports skipped");
+        return Ok(());
+    }
+
+    let portfolio_report = util::file::new_file_with_suffix(
+        orders_csv,
+        &format!("portfolio.{}", options.format.extension()),
+    )?;
+    average_cost_basis_profit_report::report::calculate_and_save(
+        orders_csv,
+        portfolio_report.as_path(),
+        options.format,
+        instruments,
+    )?;
+
+    let benchmark_report = util::file::new_file_with_suffix(
+        orders_csv,
+        &format!("benchmark.{}", options.format.extension()),
+    )?;
+    benchmark_report::report::calculate_and_save(benchmark_report::report::BenchmarkConf {
+        trade_orders_csv: orders_csv,
+        market_data: source,
+        instruments,
+        benchmark: options.benchmark,
+        output_file: &benchmark_report,
+        format: options.format,
+    })?;
+    Ok(())
+}
@@ -0,0 +1,40 @@
+use std::path::{Path, PathBuf};
+
+use shared_contracts::models::instrument::InstrumentMaster;
+use util::report::ReportFormat;
+
+use crate::error::CliError;
+use crate::market_data_import_handler;
+use crate::mbank_trade_report_csv_handler;
+
+/// `market_data` is a list of GPW daily statistics xls files, a single market data store
+/// directory or a normalized market data csv.
+pub fn handle(
+    trade_orders_csv: &Path,
+    market_data: &[PathBuf],
+    benchmark: &str,
+    format: ReportFormat,
+    instruments: &InstrumentMaster,
+) -> Result<(), CliError> {
+    let normalized_orders_csv =
+        mbank_trade_report_csv_handler::normalize(trade_orders_csv, instruments)?;
+
+    let market_data_csv = util::file::new_file_with_suffix(trade_orders_csv, "market_data.csv")?;
+    let source = market_data_import_handler::source(market_data, &market_data_csv)?;
+
+    let benchmark_report = util::file::new_file_with_suffix(
+        trade_orders_csv,
+        &format!("benchmark.{}", format.extension()),
+    )?;
+
+    let conf = benchmark_report::report::BenchmarkConf {
+        trade_orders_csv: &normalized_orders_csv,
+        market_data: source,
+        instruments,
+        benchmark,
+        output_file: &benchmark_report,
+        format,
+    };
+    benchmark_report::report::calculate_and_save(conf)?;
+    Ok(())
+}
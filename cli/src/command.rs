@@ -1,20 +1,441 @@
+use chrono::NaiveDate;
 use clap::{Parser, Subcommand};
+use recommendations::outcome::DEFAULT_HORIZON_MONTHS;
+use shared_contracts::models::instrument::Market;
+use tax_harvest::what_if::CostMethod;
+use upside::upside::{RankBy, StopLossMethod};
+use util::money::{DEFAULT_COMMISSION_MIN, DEFAULT_COMMISSION_PCT};
+use util::report::ReportFormat;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 pub struct Cli {
     #[command(subcommand)]
     pub commands: Commands,
+    /// Instrument master csv (see `instruments-build`) resolving broker symbols,
+    /// GPW names and ISINs to one instrument symbol.
+    #[arg(long, global = true)]
+    pub instruments: Option<String>,
+    /// Broker commission, % of the order value.
+    #[arg(long, global = true, default_value_t = DEFAULT_COMMISSION_PCT)]
+    pub commission_pct: f64,
+    /// Minimum broker commission of an order.
+    #[arg(long, global = true, default_value_t = DEFAULT_COMMISSION_MIN)]
+    pub commission_min: f64,
 }
 
 #[derive(Subcommand, Debug)]
 pub enum Commands {
     MbankTradeReportCsv {
         trade_orders_file: String,
+        /// Report output format: csv, parquet, xlsx, markdown or html.
+        #[arg(long, default_value_t = ReportFormat::Csv)]
+        format: ReportFormat,
     },
     UpsideCSV {
         investment_amount: f64,
         upside_report: String,
+        /// GPW daily statistics xls, market data store directory or normalized market data csv.
         market_data: String,
+        /// Report output format: csv, parquet, xlsx, markdown or html.
+        #[arg(long, default_value_t = ReportFormat::Csv)]
+        format: ReportFormat,
+        /// Fail when any target has no market data price.
+        #[arg(long)]
+        strict: bool,
+        /// Also write the per instrument consensus of analysts' targets.
+        #[arg(long)]
+        consensus: bool,
+        /// Age in days after which a target counts half in the weighted consensus.
+        #[arg(long, default_value_t = 90.0)]
+        half_life_days: f64,
+        /// Source scores csv of track-record, weights sources in the consensus
+        /// and sets target probabilities.
+        #[arg(long, requires = "consensus")]
+        source_scores: Option<String>,
+        /// Stop-loss of targets without one: atr or recent-low.
+        #[arg(long, value_enum, default_value_t = StopLossArg::Atr)]
+        stop_loss: StopLossArg,
+        /// ATRs below the price for the atr stop-loss.
+        #[arg(long, default_value_t = 2.0)]
+        atr_multiple: f64,
+        /// Sessions of the recent-low stop-loss.
+        #[arg(long, default_value_t = 20)]
+        low_sessions: usize,
+        /// Report order: expected-value or risk-reward, best first.
+        #[arg(long, value_enum, default_value_t = RankByArg::ExpectedValue)]
+        rank_by: RankByArg,
+        /// Total capital to split across the candidates into a buy list.
+        #[arg(long)]
+        capital: Option<f64>,
+        /// Largest position of the buy list, commission included. Defaults to the capital.
+        #[arg(long, requires = "capital")]
+        max_position: Option<f64>,
+        /// Smallest position of the buy list.
+        #[arg(long, default_value_t = 0.0, requires = "capital")]
+        min_position: f64,
+        /// mBank eMakler trades export, adds the upside of held positions from their average cost.
+        #[arg(long)]
+        trades: Option<String>,
+        /// Trim a held position when the remaining upside to the target is below this %.
+        #[arg(long, default_value_t = 5.0, requires = "trades")]
+        trim_upside_pct: f64,
+        /// Trim a held position when reward to risk is below this.
+        #[arg(long, default_value_t = 1.0, requires = "trades")]
+        min_risk_reward: f64,
+        /// Also write a Monte Carlo simulation of the candidates from resampled daily returns.
+        #[arg(long)]
+        simulate: bool,
+        /// Simulated price paths per candidate.
+        #[arg(long, default_value_t = 10000, requires = "simulate")]
+        paths: usize,
+        /// Latest sessions whose daily returns are resampled.
+        #[arg(long, default_value_t = 504, requires = "simulate")]
+        lookback_sessions: usize,
+        /// Seed of the simulation, the same seed repeats the same run.
+        #[arg(long, default_value_t = 42, requires = "simulate")]
+        seed: u64,
+        /// Dividend forecast csv (instrument;amount;ex_date), adds the dividends to the net profit.
+        #[arg(long)]
+        dividends: Option<String>,
+        /// Validity in months of targets without a horizon_months column value.
+        #[arg(long, default_value_t = DEFAULT_HORIZON_MONTHS)]
+        horizon_months: u32,
+        /// Also write the expired, hit and stopped recommendations with their outcome.
+        #[arg(long)]
+        expired: bool,
     },
+    /// Scores recommendation sources by checking past upside targets against price history.
+    TrackRecord {
+        /// Market data store directory or normalized market data csv.
+        market_data: String,
+        /// Upside csv files with past recommendations.
+        #[arg(required = true, num_args = 1..)]
+        upside_csv: Vec<String>,
+        /// Months after a recommendation without a horizon_months column value
+        /// in which the target has to be hit.
+        #[arg(long, default_value_t = DEFAULT_HORIZON_MONTHS)]
+        horizon_months: u32,
+        /// Report output format: csv, parquet, xlsx, markdown or html.
+        #[arg(long, default_value_t = ReportFormat::Csv)]
+        format: ReportFormat,
+    },
+    /// Imports broker research recommendation exports (csv or html) into an upside csv.
+    ImportRecommendations {
+        /// Upside csv to append to, created when missing.
+        upside_csv: String,
+        /// Recommendation list exports.
+        #[arg(required = true, num_args = 1..)]
+        exports: Vec<String>,
+        /// created_by of exports without a broker column, e.g. bossa or mbank.
+        #[arg(long)]
+        source: Option<String>,
+    },
+    /// Compares the portfolio time-weighted return with a GPW index.
+    BenchmarkReport {
+        trade_orders_file: String,
+        /// GPW daily statistics files (shares and indices) covering the trade period,
+        /// a market data store directory or a normalized market data csv.
+        #[arg(required = true, num_args = 1..)]
+        market_data: Vec<String>,
+        /// Benchmark index: WIG, WIG20, mWIG40 or sWIG80.
+        #[arg(long, default_value = "WIG20")]
+        benchmark: String,
+        /// Report output format: csv, parquet, xlsx, markdown or html.
+        #[arg(long, default_value_t = ReportFormat::Csv)]
+        format: ReportFormat,
+    },
+    /// Weights of the held positions by instrument, sector, industry, country and asset class.
+    AllocationReport {
+        trade_orders_file: String,
+        /// GPW daily statistics files, a market data store directory
+        /// or a normalized market data csv.
+        #[arg(required = true, num_args = 1..)]
+        market_data: Vec<String>,
+        /// Classification csv: instrument;sector;industry;country;asset_class.
+        #[arg(long)]
+        classification: Option<String>,
+        /// Number of largest positions in the concentration.
+        #[arg(long, default_value_t = 5)]
+        top_n: usize,
+        /// Warn about a position above this weight, in %.
+        #[arg(long, default_value_t = 20.0)]
+        max_position_pct: f64,
+        /// Warn about a sector above this weight, in %.
+        #[arg(long, default_value_t = 40.0)]
+        max_sector_pct: f64,
+        /// Report output format: csv, parquet, xlsx, markdown or html.
+        #[arg(long, default_value_t = ReportFormat::Csv)]
+        format: ReportFormat,
+    },
+    /// Correlation matrix, beta to an index and volatility of the held positions.
+    RiskReport {
+        trade_orders_file: String,
+        /// GPW daily statistics files (shares and indices), a market data store directory
+        /// or a normalized market data csv.
+        #[arg(required = true, num_args = 1..)]
+        market_data: Vec<String>,
+        /// Index the betas are calculated to.
+        #[arg(long, default_value = "WIG20")]
+        benchmark: String,
+        /// Number of latest sessions of daily returns used.
+        #[arg(long, default_value_t = 250)]
+        lookback_sessions: usize,
+        /// Pairs correlated at or above it are highlighted.
+        #[arg(long, default_value_t = 0.8)]
+        correlation_threshold: f64,
+        /// Report output format: csv, parquet, xlsx, markdown or html.
+        #[arg(long, default_value_t = ReportFormat::Csv)]
+        format: ReportFormat,
+    },
+    /// Sells of positions at a loss offsetting the realized gains of a tax year.
+    TaxHarvest {
+        trade_orders_file: String,
+        /// GPW daily statistics files, a market data store directory
+        /// or a normalized market data csv.
+        #[arg(required = true, num_args = 1..)]
+        market_data: Vec<String>,
+        /// Tax year of the settled sells, by default the one a sell placed today settles in.
+        #[arg(long)]
+        year: Option<i32>,
+        /// Tax on the realized gains to bring the year down to.
+        #[arg(long, default_value_t = 0.0)]
+        target_tax: f64,
+        /// Report output format: csv, parquet, xlsx, markdown or html.
+        #[arg(long, default_value_t = ReportFormat::Csv)]
+        format: ReportFormat,
+    },
+    /// Shows the realized gain and tax of a sell before placing it, no report is written.
+    WhatIfSell {
+        trade_orders_file: String,
+        instrument: String,
+        quantity: i64,
+        /// Sell price, the latest close of the market data by default.
+        #[arg(long)]
+        price: Option<f64>,
+        /// Market data store directory or normalized market data csv.
+        #[arg(long, required_unless_present = "price")]
+        market_data: Option<String>,
+        /// How the cost of the sold shares is matched.
+        #[arg(long, value_enum, default_value_t = CostMethodArg::Fifo)]
+        cost_method: CostMethodArg,
+    },
+    /// Plans the orders bringing the portfolio back to its target weights.
+    Rebalance {
+        trade_orders_file: String,
+        /// GPW daily statistics files, a market data store directory
+        /// or a normalized market data csv.
+        #[arg(required = true, num_args = 1..)]
+        market_data: Vec<String>,
+        /// Target weights csv: target;weight_pct, a target is an instrument or an asset class.
+        #[arg(long)]
+        targets: String,
+        /// Asset classes csv: instrument;asset_class.
+        #[arg(long)]
+        asset_classes: Option<String>,
+        /// Cash added to the portfolio before rebalancing.
+        #[arg(long, default_value_t = 0.0)]
+        cash: f64,
+        /// Positions within this many percentage points of their target are left alone.
+        #[arg(long, default_value_t = 5.0)]
+        tolerance_pct: f64,
+        /// Report output format: csv, parquet, xlsx, markdown or html.
+        #[arg(long, default_value_t = ReportFormat::Csv)]
+        format: ReportFormat,
+    },
+    /// Runs a trading strategy over past market data, writes its orders as a normalized
+    /// trade orders csv and the profit and benchmark reports of the filled ones.
+    Backtest {
+        /// Output csv of the simulated orders.
+        orders_file: String,
+        /// GPW daily statistics files, a market data store directory
+        /// or a normalized market data csv.
+        #[arg(required = true, num_args = 1..)]
+        market_data: Vec<String>,
+        #[arg(long, value_enum, default_value_t = StrategyArg::SmaCross)]
+        strategy: StrategyArg,
+        /// Instruments traded by sma-cross, required by it.
+        #[arg(long, num_args = 1..)]
+        universe: Vec<String>,
+        /// Sessions of the fast moving average of sma-cross.
+        #[arg(long, default_value_t = 20)]
+        fast: usize,
+        /// Sessions of the slow moving average of sma-cross.
+        #[arg(long, default_value_t = 50)]
+        slow: usize,
+        /// Upside csv with the analyst targets of target-discount.
+        #[arg(long, required_if_eq("strategy", "target-discount"))]
+        upside_csv: Option<String>,
+        /// target-discount buys when the close is this many percent below the target.
+        #[arg(long, default_value_t = 20.0)]
+        discount_pct: f64,
+        /// Value of one buy.
+        #[arg(long, default_value_t = 10000.0)]
+        position_value: f64,
+        /// Starting cash.
+        #[arg(long, default_value_t = 100000.0)]
+        cash: f64,
+        /// First session, YYYY-MM-DD, the start of the market data by default.
+        #[arg(long)]
+        start: Option<NaiveDate>,
+        /// Last session, YYYY-MM-DD, the end of the market data by default.
+        #[arg(long)]
+        end: Option<NaiveDate>,
+        /// Benchmark index: WIG, WIG20, mWIG40 or sWIG80.
+        #[arg(long, default_value = "WIG20")]
+        benchmark: String,
+        /// Report output format: csv, parquet, xlsx, markdown or html.
+        #[arg(long, default_value_t = ReportFormat::Csv)]
+        format: ReportFormat,
+    },
+    /// Appends GPW daily statistics to the local market data store.
+    MarketDataImport {
+        store: String,
+        /// GPW daily statistics xls or normalized market data csv files.
+        #[arg(required = true, num_args = 1..)]
+        market_data: Vec<String>,
+        /// Refuse to import a file with validation errors.
+        #[arg(long)]
+        strict: bool,
+    },
+    /// Merges a directory of GPW daily statistics (xls, xlsx, csv) into one deduplicated series.
+    GpwIngest {
+        dir: String,
+        /// Normalized market data csv to write.
+        output: String,
+        /// Also append the merged series to this market data store.
+        #[arg(long)]
+        store: Option<String>,
+        /// Refuse to write the series when validation finds errors.
+        #[arg(long)]
+        strict: bool,
+    },
+    /// Converts Stooq daily quotes (single instrument files or bulk archive directories).
+    StooqImport {
+        /// Normalized market data csv to write.
+        output: String,
+        #[arg(required = true, num_args = 1..)]
+        files: Vec<String>,
+        /// `ticker;instrument` csv mapping Stooq tickers to trade instrument names.
+        #[arg(long)]
+        tickers: Option<String>,
+        /// Also append the quotes to this market data store.
+        #[arg(long)]
+        store: Option<String>,
+    },
+    /// Prints the latest technical indicators (SMA, EMA, RSI, MACD, ATR, Bollinger bands,
+    /// average volume) of the watchlist instruments.
+    Indicators {
+        /// Normalized market data csv or market data store directory.
+        market_data: String,
+        /// Instruments to show, all instruments when empty.
+        watchlist: Vec<String>,
+        /// Also save the indicators as a report.
+        #[arg(long)]
+        output: Option<String>,
+        /// Report output format: csv, parquet, xlsx, markdown or html.
+        #[arg(long, default_value_t = ReportFormat::Csv)]
+        format: ReportFormat,
+    },
+    /// Checks market data for bad prices, duplicate rows, missing sessions and price jumps.
+    ValidateMarketData {
+        /// GPW daily statistics (xls, xlsx, csv) or normalized market data csv files,
+        /// validated together as one series.
+        #[arg(required = true, num_args = 1..)]
+        market_data: Vec<String>,
+        /// Findings report to write, findings are printed when not given.
+        #[arg(long)]
+        output: Option<String>,
+        /// Findings report format: csv, parquet, xlsx, markdown or html.
+        #[arg(long, default_value_t = ReportFormat::Csv)]
+        format: ReportFormat,
+        /// Largest accepted close-to-close change, 0.5 = 50%.
+        #[arg(long, default_value_t = 0.5)]
+        max_daily_change: f64,
+        /// Exit with an error when there are error findings.
+        #[arg(long)]
+        strict: bool,
+    },
+    /// Builds the instrument master from GPW daily statistics and user overrides.
+    InstrumentsBuild {
+        /// Instrument master csv to write.
+        output: String,
+        /// GPW daily statistics or normalized market data files.
+        #[arg(required = true, num_args = 1..)]
+        market_data: Vec<String>,
+        /// Market of the instruments in the files: main-market or new-connect.
+        #[arg(long, value_enum, default_value_t = MarketArg::MainMarket)]
+        market: MarketArg,
+        /// Instruments csv applied on top, e.g. broker symbols, full names, NewConnect listings.
+        #[arg(long)]
+        overrides: Option<String>,
+    },
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum MarketArg {
+    MainMarket,
+    NewConnect,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum StopLossArg {
+    Atr,
+    RecentLow,
+}
+
+impl From<StopLossArg> for StopLossMethod {
+    fn from(stop_loss: StopLossArg) -> Self {
+        match stop_loss {
+            StopLossArg::Atr => StopLossMethod::Atr,
+            StopLossArg::RecentLow => StopLossMethod::RecentLow,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StrategyArg {
+    /// Fast moving average crossing the slow one.
+    SmaCross,
+    /// Buy below the analyst target, sell at it.
+    TargetDiscount,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum CostMethodArg {
+    Fifo,
+    AverageCost,
+}
+
+impl From<CostMethodArg> for CostMethod {
+    fn from(method: CostMethodArg) -> Self {
+        match method {
+            CostMethodArg::Fifo => CostMethod::Fifo,
+            CostMethodArg::AverageCost => CostMethod::AverageCost,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum RankByArg {
+    ExpectedValue,
+    RiskReward,
+}
+
+impl From<RankByArg> for RankBy {
+    fn from(rank_by: RankByArg) -> Self {
+        match rank_by {
+            RankByArg::ExpectedValue => RankBy::ExpectedValue,
+            RankByArg::RiskReward => RankBy::RiskReward,
+        }
+    }
+}
+
+impl From<MarketArg> for Market {
+    fn from(market: MarketArg) -> Self {
+        match market {
+            MarketArg::MainMarket => Market::MainMarket,
+            MarketArg::NewConnect => Market::NewConnect,
+        }
+    }
 }
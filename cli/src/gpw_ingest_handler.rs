@@ -0,0 +1,69 @@
+use std::path::Path;
+
+use gpw::validation::{self, ValidationConf};
+use market_store::store::MarketDataStore;
+use util::report::ReportFormat;
+
+use crate::error::CliError;
+
+/// Merges a directory of GPW daily statistics into one csv and optionally appends it to the store.
+/// Validation findings are written next to the output as `<output>_findings.csv`,
+/// also when strict mode then stops the ingest.
+pub fn handle(
+    dir: &Path,
+    output: &Path,
+    store_dir: Option<&Path>,
+    strict: bool,
+) -> Result<(), CliError> {
+    let conf = ValidationConf {
+        strict,
+        ..Default::default()
+    };
+    let summary = gpw::ingest::ingest_dir(dir, output, &conf)?;
+
+    for file in &summary.files {
+        match &file.error {
+            None => println!("{}: {} rows", file.file.display(), file.rows),
+            Some(e) => println!("{}: skipped, {e}", file.file.display()),
+        }
+    }
+    println!(
+        "Ingested {} of {} files: {} sessions ({} - {}), {} instruments, {} rows",
+        summary.ingested_files(),
+        summary.files.len(),
+        summary.sessions,
+        summary
+            .first_session
+            .map_or("-".to_string(), |d| d.to_string()),
+        summary
+            .last_session
+            .map_or("-".to_string(), |d| d.to_string()),
+        summary.instruments,
+        summary.rows
+    );
+
+    if !summary.findings.is_empty() {
+        let findings_csv = util::file::new_file_with_suffix(output, "findings.csv")?;
+        validation::save_findings(&summary.findings, &findings_csv, ReportFormat::Csv)?;
+        println!(
+            "Validation: {} errors, {} warnings, see {}",
+            validation::errors(&summary.findings),
+            validation::warnings(&summary.findings),
+            findings_csv.display()
+        );
+    }
+
+    conf.enforce(&summary.findings)?;
+
+    if let Some(store_dir) = store_dir {
+        let store = MarketDataStore::open(store_dir)?;
+        let appended = store.append_csv(output)?;
+        println!(
+            "Appended {} sessions, {} rows to {}",
+            appended.sessions,
+            appended.rows,
+            store.root().display()
+        );
+    }
+    Ok(())
+}
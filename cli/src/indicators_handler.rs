@@ -0,0 +1,41 @@
+use std::path::Path;
+
+use indicators::indicators::IndicatorsConf;
+use market_store::source::MarketDataSource;
+use util::report::{Report, ReportFormat};
+
+use crate::error::CliError;
+
+/// Prints the latest indicator values of `watchlist` (every instrument when empty)
+/// and optionally saves them as a report.
+pub fn handle(
+    market_data: &Path,
+    watchlist: &[String],
+    output: Option<&Path>,
+    format: ReportFormat,
+) -> Result<(), CliError> {
+    let history = MarketDataSource::from_path(market_data).history()?;
+    let with_indicators =
+        indicators::indicators::with_indicators(history, &IndicatorsConf::default());
+    let latest = indicators::indicators::latest(with_indicators, watchlist)?;
+
+    let rows = indicators::indicators::latest_values(&latest)?;
+    for name in watchlist {
+        if !rows.iter().any(|row| &row.instrument == name) {
+            println!("No market data for {name}");
+        }
+    }
+    for row in &rows {
+        println!("{} ({})", row.instrument, row.date);
+        for (name, value) in &row.values {
+            println!("  {name:<16} {value}");
+        }
+    }
+
+    if let Some(output) = output {
+        let mut report = Report::new("Technical Indicators", latest);
+        util::report::save(&mut report, output, format)?;
+        println!("Saved indicators to {}", output.display());
+    }
+    Ok(())
+}
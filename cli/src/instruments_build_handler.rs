@@ -0,0 +1,29 @@
+use std::path::{Path, PathBuf};
+
+use shared_contracts::models::instrument::{InstrumentMaster, Market};
+
+use crate::error::CliError;
+
+/// Builds the instrument master from market data files and applies the user overrides.
+pub fn handle(
+    output: &Path,
+    market_data_files: &[PathBuf],
+    market: Market,
+    overrides_csv: Option<&Path>,
+) -> Result<(), CliError> {
+    let mut instruments = gpw::instruments::from_files(market_data_files, market)?;
+    let from_market_data = instruments.len();
+
+    if let Some(overrides_csv) = overrides_csv {
+        instruments.merge(InstrumentMaster::load(overrides_csv)?);
+    }
+    instruments.save(output)?;
+
+    println!(
+        "Saved {} instruments ({} from market data) to {}",
+        instruments.len(),
+        from_market_data,
+        output.display()
+    );
+    Ok(())
+}
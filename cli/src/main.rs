@@ -1,24 +1,73 @@
+mod allocation_report_handler;
+mod backtest_handler;
+mod benchmark_report_handler;
 mod command;
 mod error;
+mod gpw_ingest_handler;
+mod indicators_handler;
+mod instruments_build_handler;
+mod market_data_import_handler;
 mod mbank_trade_report_csv_handler;
 mod mbank_upside_csv_handler;
-use crate::command::{Cli, Commands};
+mod rebalance_handler;
+mod recommendations_import_handler;
+mod risk_report_handler;
+mod stooq_import_handler;
+mod tax_harvest_handler;
+mod track_record_handler;
+mod validate_market_data_handler;
+mod what_if_sell_handler;
+use crate::command::{Cli, Commands, StrategyArg};
 use clap::Parser;
-use std::path::Path;
+use shared_contracts::models::instrument::InstrumentMaster;
+use std::path::{Path, PathBuf};
+use upside::dividends::DividendConf;
+use upside::upside::StopLossConf;
+use util::money::Commission;
 
 fn main() -> Result<(), error::CliError> {
     let cli = Cli::parse();
+    let instruments = match &cli.instruments {
+        Some(csv) => InstrumentMaster::load(Path::new(csv))?,
+        None => InstrumentMaster::default(),
+    };
+    let commission = Commission::from_pct(cli.commission_pct, cli.commission_min);
 
     match &cli.commands {
-        Commands::MbankTradeReportCsv { trade_orders_file } => {
+        Commands::MbankTradeReportCsv {
+            trade_orders_file,
+            format,
+        } => {
             let trade_orders_path: &Path = Path::new(trade_orders_file);
 
-            mbank_trade_report_csv_handler::handle(trade_orders_path)?;
+            mbank_trade_report_csv_handler::handle(trade_orders_path, *format, &instruments)?;
         }
         Commands::UpsideCSV {
             investment_amount,
             upside_report,
             market_data,
+            format,
+            strict,
+            consensus,
+            half_life_days,
+            source_scores,
+            stop_loss,
+            atr_multiple,
+            low_sessions,
+            rank_by,
+            capital,
+            max_position,
+            min_position,
+            trades,
+            trim_upside_pct,
+            min_risk_reward,
+            simulate,
+            paths,
+            lookback_sessions,
+            seed,
+            dividends,
+            horizon_months,
+            expired,
         } => {
             let upside_report_path = Path::new(upside_report);
             let maket_data_path = Path::new(market_data);
@@ -27,6 +76,330 @@ fn main() -> Result<(), error::CliError> {
                 *investment_amount,
                 upside_report_path,
                 maket_data_path,
+                *format,
+                &instruments,
+                mbank_upside_csv_handler::UpsideOptions {
+                    strict: *strict,
+                    commission,
+                    consensus_half_life_days: consensus.then_some(*half_life_days),
+                    source_scores: source_scores.as_deref().map(Path::new),
+                    stop_loss: StopLossConf {
+                        method: (*stop_loss).into(),
+                        atr_multiple: *atr_multiple,
+                        low_sessions: *low_sessions,
+                        ..Default::default()
+                    },
+                    rank_by: (*rank_by).into(),
+                    allocation: capital.map(|capital| mbank_upside_csv_handler::AllocationArgs {
+                        capital,
+                        max_position: max_position.unwrap_or(capital),
+                        min_position: *min_position,
+                    }),
+                    held: trades
+                        .as_deref()
+                        .map(|trades| mbank_upside_csv_handler::HeldArgs {
+                            trades_csv: Path::new(trades),
+                            trim_upside_pct: *trim_upside_pct,
+                            min_risk_reward: *min_risk_reward,
+                        }),
+                    simulation: simulate.then_some(mbank_upside_csv_handler::SimulationArgs {
+                        paths: *paths,
+                        lookback_sessions: *lookback_sessions,
+                        seed: *seed,
+                    }),
+                    dividends: dividends.as_deref().map(|forecast| DividendConf {
+                        forecast_csv: Path::new(forecast),
+                    }),
+                    horizon_months: *horizon_months,
+                    expired: *expired,
+                },
+            )?;
+        }
+        Commands::TrackRecord {
+            market_data,
+            upside_csv,
+            horizon_months,
+            format,
+        } => {
+            let upside_paths: Vec<PathBuf> = upside_csv.iter().map(PathBuf::from).collect();
+            track_record_handler::handle(
+                Path::new(market_data),
+                &upside_paths,
+                *horizon_months,
+                *format,
+                &instruments,
+            )?;
+        }
+        Commands::ImportRecommendations {
+            upside_csv,
+            exports,
+            source,
+        } => {
+            let export_paths: Vec<PathBuf> = exports.iter().map(PathBuf::from).collect();
+            recommendations_import_handler::handle(
+                Path::new(upside_csv),
+                &export_paths,
+                source.as_deref(),
+            )?;
+        }
+        Commands::BenchmarkReport {
+            trade_orders_file,
+            market_data,
+            benchmark,
+            format,
+        } => {
+            let trade_orders_path = Path::new(trade_orders_file);
+            let market_data_paths: Vec<PathBuf> = market_data.iter().map(PathBuf::from).collect();
+
+            benchmark_report_handler::handle(
+                trade_orders_path,
+                &market_data_paths,
+                benchmark,
+                *format,
+                &instruments,
+            )?;
+        }
+        Commands::AllocationReport {
+            trade_orders_file,
+            market_data,
+            classification,
+            top_n,
+            max_position_pct,
+            max_sector_pct,
+            format,
+        } => {
+            let market_data_paths: Vec<PathBuf> = market_data.iter().map(PathBuf::from).collect();
+
+            allocation_report_handler::handle(
+                Path::new(trade_orders_file),
+                &market_data_paths,
+                classification.as_deref().map(Path::new),
+                allocation_report::report::Limits {
+                    max_position_pct: *max_position_pct,
+                    max_sector_pct: *max_sector_pct,
+                },
+                *top_n,
+                *format,
+                &instruments,
+            )?;
+        }
+        Commands::RiskReport {
+            trade_orders_file,
+            market_data,
+            benchmark,
+            lookback_sessions,
+            correlation_threshold,
+            format,
+        } => {
+            let market_data_paths: Vec<PathBuf> = market_data.iter().map(PathBuf::from).collect();
+
+            risk_report_handler::handle(
+                Path::new(trade_orders_file),
+                &market_data_paths,
+                risk_report_handler::RiskOptions {
+                    benchmark,
+                    lookback_sessions: *lookback_sessions,
+                    correlation_threshold: *correlation_threshold,
+                    format: *format,
+                },
+                &instruments,
+            )?;
+        }
+        Commands::TaxHarvest {
+            trade_orders_file,
+            market_data,
+            year,
+            target_tax,
+            format,
+        } => {
+            let market_data_paths: Vec<PathBuf> = market_data.iter().map(PathBuf::from).collect();
+
+            tax_harvest_handler::handle(
+                Path::new(trade_orders_file),
+                &market_data_paths,
+                *year,
+                *target_tax,
+                commission,
+                *format,
+                &instruments,
+            )?;
+        }
+        Commands::WhatIfSell {
+            trade_orders_file,
+            instrument,
+            quantity,
+            price,
+            market_data,
+            cost_method,
+        } => {
+            let options = what_if_sell_handler::WhatIfOptions {
+                price: *price,
+                market_data: market_data.as_deref().map(Path::new),
+                method: (*cost_method).into(),
+                commission,
+            };
+
+            what_if_sell_handler::handle(
+                Path::new(trade_orders_file),
+                instrument,
+                *quantity,
+                options,
+                &instruments,
+            )?;
+        }
+        Commands::Rebalance {
+            trade_orders_file,
+            market_data,
+            targets,
+            asset_classes,
+            cash,
+            tolerance_pct,
+            format,
+        } => {
+            let market_data_paths: Vec<PathBuf> = market_data.iter().map(PathBuf::from).collect();
+
+            rebalance_handler::handle(
+                Path::new(trade_orders_file),
+                &market_data_paths,
+                Path::new(targets),
+                rebalance_handler::RebalanceOptions {
+                    asset_classes_csv: asset_classes.as_deref().map(Path::new),
+                    cash: *cash,
+                    tolerance_pct: *tolerance_pct,
+                    commission,
+                    format: *format,
+                },
+                &instruments,
+            )?;
+        }
+        Commands::Backtest {
+            orders_file,
+            market_data,
+            strategy,
+            universe,
+            fast,
+            slow,
+            upside_csv,
+            discount_pct,
+            position_value,
+            cash,
+            start,
+            end,
+            benchmark,
+            format,
+        } => {
+            let market_data_paths: Vec<PathBuf> = market_data.iter().map(PathBuf::from).collect();
+            let strategy = match (strategy, upside_csv) {
+                (StrategyArg::TargetDiscount, Some(upside_csv)) => {
+                    backtest_handler::StrategyArgs::TargetDiscount {
+                        upside_csv: Path::new(upside_csv),
+                        discount_pct: *discount_pct,
+                    }
+                }
+                _ => backtest_handler::StrategyArgs::SmaCross {
+                    universe,
+                    fast: *fast,
+                    slow: *slow,
+                },
+            };
+
+            backtest_handler::handle(
+                Path::new(orders_file),
+                &market_data_paths,
+                backtest_handler::BacktestOptions {
+                    strategy,
+                    position_value: *position_value,
+                    cash: *cash,
+                    commission,
+                    start: *start,
+                    end: *end,
+                    benchmark,
+                    format: *format,
+                },
+                &instruments,
+            )?;
+        }
+        Commands::MarketDataImport {
+            store,
+            market_data,
+            strict,
+        } => {
+            let store_path = Path::new(store);
+            let market_data_paths: Vec<PathBuf> = market_data.iter().map(PathBuf::from).collect();
+
+            market_data_import_handler::handle(store_path, &market_data_paths, *strict)?;
+        }
+        Commands::GpwIngest {
+            dir,
+            output,
+            store,
+            strict,
+        } => {
+            gpw_ingest_handler::handle(
+                Path::new(dir),
+                Path::new(output),
+                store.as_deref().map(Path::new),
+                *strict,
+            )?;
+        }
+        Commands::StooqImport {
+            output,
+            files,
+            tickers,
+            store,
+        } => {
+            let input_paths: Vec<PathBuf> = files.iter().map(PathBuf::from).collect();
+
+            stooq_import_handler::handle(
+                Path::new(output),
+                &input_paths,
+                tickers.as_deref().map(Path::new),
+                store.as_deref().map(Path::new),
+            )?;
+        }
+        Commands::Indicators {
+            market_data,
+            watchlist,
+            output,
+            format,
+        } => {
+            indicators_handler::handle(
+                Path::new(market_data),
+                watchlist,
+                output.as_deref().map(Path::new),
+                *format,
+            )?;
+        }
+        Commands::ValidateMarketData {
+            market_data,
+            output,
+            format,
+            max_daily_change,
+            strict,
+        } => {
+            let market_data_paths: Vec<PathBuf> = market_data.iter().map(PathBuf::from).collect();
+
+            validate_market_data_handler::handle(
+                &market_data_paths,
+                output.as_deref().map(Path::new),
+                *format,
+                *max_daily_change,
+                *strict,
+            )?;
+        }
+        Commands::InstrumentsBuild {
+            output,
+            market_data,
+            market,
+            overrides,
+        } => {
+            let market_data_paths: Vec<PathBuf> = market_data.iter().map(PathBuf::from).collect();
+
+            instruments_build_handler::handle(
+                Path::new(output),
+                &market_data_paths,
+                (*market).into(),
+                overrides.as_deref().map(Path::new),
             )?;
         }
     }
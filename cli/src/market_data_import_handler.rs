@@ -0,0 +1,75 @@
+use std::path::{Path, PathBuf};
+
+use gpw::validation::{self, ValidationConf};
+use market_store::source::MarketDataSource;
+use market_store::store::{AppendSummary, MarketDataStore};
+
+use crate::error::CliError;
+
+/// Appends GPW daily statistics (xls) or normalized market data csv files to the store.
+/// Every file is validated first, in strict mode a file with errors stops the import.
+pub fn handle(
+    store_dir: &Path,
+    market_data_files: &[PathBuf],
+    strict: bool,
+) -> Result<(), CliError> {
+    let store = MarketDataStore::open(store_dir)?;
+    let conf = ValidationConf {
+        strict,
+        ..Default::default()
+    };
+
+    let mut total = AppendSummary::default();
+    for file in market_data_files {
+        let findings = validation::validate(&validation::load(file)?, &conf);
+        for finding in &findings {
+            println!("{}: {finding}", file.display());
+        }
+        conf.enforce(&findings)?;
+
+        let summary = if is_csv(file) {
+            store.append_csv(file)?
+        } else {
+            let market_data_csv = util::file::new_file_with_suffix(file, "normalized.csv")?;
+            gpw::market_data::convert_xls_to_csv(file, market_data_csv.as_path())?;
+            store.append_csv(market_data_csv.as_path())?
+        };
+        println!(
+            "{}: {} sessions, {} rows",
+            file.display(),
+            summary.sessions,
+            summary.rows
+        );
+        total.sessions += summary.sessions;
+        total.rows += summary.rows;
+    }
+    println!(
+        "Imported {} files into {}: {} sessions, {} rows",
+        market_data_files.len(),
+        store.root().display(),
+        total.sessions,
+        total.rows
+    );
+    Ok(())
+}
+
+/// Market data of a report: a single store directory or normalized csv is read as is,
+/// GPW daily statistics xls files are converted into `market_data_csv` first.
+pub fn source<'a>(
+    market_data: &'a [PathBuf],
+    market_data_csv: &'a Path,
+) -> Result<MarketDataSource<'a>, CliError> {
+    match market_data {
+        [path] if path.is_dir() || is_csv(path) => Ok(MarketDataSource::from_path(path)),
+        files => {
+            gpw::market_data::convert_xls_files_to_csv(files, market_data_csv)?;
+            Ok(MarketDataSource::Csv(market_data_csv))
+        }
+    }
+}
+
+fn is_csv(file: &Path) -> bool {
+    file.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("csv"))
+}
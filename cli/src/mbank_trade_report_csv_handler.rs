@@ -1,21 +1,37 @@
 use crate::error::CliError;
-use std::path::Path;
+use shared_contracts::models::instrument::InstrumentMaster;
+use std::path::{Path, PathBuf};
+use util::report::ReportFormat;
 
-pub fn handle(csv: &Path) -> Result<(), CliError> {
-    let normalized_orders_csv = util::file::new_file_with_suffix(csv, "normalized.csv")?;
-
-    normalized_orders_csv
-        .exists()
-        .then(|| std::fs::remove_file(&normalized_orders_csv));
-
-    mbank_emakler_csv::loader::normalize(csv, normalized_orders_csv.as_path())?;
+pub fn handle(
+    csv: &Path,
+    format: ReportFormat,
+    instruments: &InstrumentMaster,
+) -> Result<(), CliError> {
+    let normalized_orders_csv = normalize(csv, instruments)?;
 
-    let portfolio_csv = util::file::new_file_with_suffix(csv, "portfolio.csv")?;
+    let portfolio_report =
+        util::file::new_file_with_suffix(csv, &format!("portfolio.{}", format.extension()))?;
 
     average_cost_basis_profit_report::report::calculate_and_save(
         normalized_orders_csv.as_path(),
-        portfolio_csv.as_path(),
+        portfolio_report.as_path(),
+        format,
+        instruments,
     )?;
 
     Ok(())
 }
+
+/// Converts the mBank eMakler export into the normalized trade orders csv.
+pub fn normalize(csv: &Path, instruments: &InstrumentMaster) -> Result<PathBuf, CliError> {
+    let normalized_orders_csv = util::file::new_file_with_suffix(csv, "normalized.csv")?;
+
+    normalized_orders_csv
+        .exists()
+        .then(|| std::fs::remove_file(&normalized_orders_csv));
+
+    mbank_emakler_csv::loader::normalize(csv, normalized_orders_csv.as_path(), instruments)?;
+
+    Ok(normalized_orders_csv)
+}
@@ -1,25 +1,184 @@
+use std::collections::HashMap;
 use std::path::Path;
 
+use holdings::positions::Holdings;
+use shared_contracts::models::instrument::InstrumentMaster;
+use upside::allocation::AllocationConf;
+use upside::dividends::DividendConf;
+use upside::held::HeldConf;
+use upside::simulation::SimulationConf;
+use upside::upside::{PriceStatus, RankBy, StopLossConf};
+use util::money::Commission;
+use util::report::ReportFormat;
+
 use crate::error::CliError;
+use crate::{market_data_import_handler, mbank_trade_report_csv_handler};
+
+pub struct UpsideOptions<'a> {
+    pub strict: bool,
+    pub commission: Commission,
+    /// Half-life in days of the analysts' consensus written next to the report,
+    /// no consensus when `None`.
+    pub consensus_half_life_days: Option<f64>,
+    /// Source scores csv of a track record run.
+    pub source_scores: Option<&'a Path>,
+    pub stop_loss: StopLossConf,
+    pub rank_by: RankBy,
+    pub allocation: Option<AllocationArgs>,
+    pub held: Option<HeldArgs<'a>>,
+    pub simulation: Option<SimulationArgs>,
+    pub dividends: Option<DividendConf<'a>>,
+    /// Validity of targets without their own horizon.
+    pub horizon_months: u32,
+    /// Write the recommendations no longer active.
+    pub expired: bool,
+}
+
+/// Held positions rebuilt from an mBank eMakler trades export.
+pub struct HeldArgs<'a> {
+    pub trades_csv: &'a Path,
+    pub trim_upside_pct: f64,
+    pub min_risk_reward: f64,
+}
+
+/// Monte Carlo paths of the candidates.
+pub struct SimulationArgs {
+    pub paths: usize,
+    pub lookback_sessions: usize,
+    pub seed: u64,
+}
+
+/// Budget split into a buy list.
+pub struct AllocationArgs {
+    pub capital: f64,
+    pub max_position: f64,
+    pub min_position: f64,
+}
 
+/// `market_data` is a GPW daily statistics xls, a market data store directory or a normalized
+/// market data csv.
 pub fn handle(
     investment_amount: f64,
     upside_csv: &Path,
-    market_data_xls: &Path,
+    market_data: &Path,
+    format: ReportFormat,
+    instruments: &InstrumentMaster,
+    options: UpsideOptions,
 ) -> Result<(), CliError> {
-    let market_data_csv = util::file::new_file_with_suffix(market_data_xls, "normalized.csv")?;
-    gpw::market_data::convert_xls_to_csv(market_data_xls, market_data_csv.as_path())?;
+    let source_weights = match options.source_scores {
+        Some(scores) => track_record::track_record::load_weights(scores)?,
+        None => HashMap::new(),
+    };
+    let holdings = match &options.held {
+        Some(held) => {
+            let normalized_orders_csv =
+                mbank_trade_report_csv_handler::normalize(held.trades_csv, instruments)?;
+            Holdings::from_trades(&holdings::trades::load_filled(&normalized_orders_csv)?)?
+        }
+        None => Holdings::default(),
+    };
+    let market_data_csv = util::file::new_file_with_suffix(market_data, "normalized.csv")?;
+    let market_data = [market_data.to_path_buf()];
+    let source = market_data_import_handler::source(&market_data, &market_data_csv)?;
 
-    let upside_report_csv = util::file::new_file_with_suffix(upside_csv, "report.csv")?;
+    let upside_report =
+        util::file::new_file_with_suffix(upside_csv, &format!("report.{}", format.extension()))?;
+    let buy_list_report =
+        util::file::new_file_with_suffix(upside_csv, &format!("buy_list.{}", format.extension()))?;
+    let held_report =
+        util::file::new_file_with_suffix(upside_csv, &format!("held.{}", format.extension()))?;
+    let simulation_report = util::file::new_file_with_suffix(
+        upside_csv,
+        &format!("simulation.{}", format.extension()),
+    )?;
+    let expired_report =
+        util::file::new_file_with_suffix(upside_csv, &format!("expired.{}", format.extension()))?;
+    let consensus_report =
+        util::file::new_file_with_suffix(upside_csv, &format!("consensus.{}", format.extension()))?;
 
     let conf = upside::upside::UpsideConf {
         upside_csv,
-        output_file: &upside_report_csv,
-        market_data_csv: &market_data_csv,
+        output_file: &upside_report,
+        consensus_file: options
+            .consensus_half_life_days
+            .map(|_| consensus_report.as_path()),
+        market_data: source,
+        instruments,
         investment_amount,
-        commission_percent: 0.039_f64,
-        commission_min: 5_f64,
+        commission: options.commission,
+        format,
+        strict: options.strict,
+        half_life_days: options.consensus_half_life_days.unwrap_or(90.0),
+        source_weights: &source_weights,
+        stop_loss: options.stop_loss,
+        rank_by: options.rank_by,
+        allocation: options.allocation.as_ref().map(|a| AllocationConf {
+            capital: a.capital,
+            max_position: a.max_position,
+            min_position: a.min_position,
+            output_file: &buy_list_report,
+        }),
+        held: options.held.as_ref().map(|h| HeldConf {
+            holdings: &holdings,
+            trim_upside_pct: h.trim_upside_pct,
+            min_risk_reward: h.min_risk_reward,
+            output_file: &held_report,
+        }),
+        simulation: options.simulation.as_ref().map(|s| SimulationConf {
+            paths: s.paths,
+            lookback_sessions: s.lookback_sessions,
+            seed: s.seed,
+            output_file: &simulation_report,
+        }),
+        dividends: options.dividends,
+        default_horizon_months: options.horizon_months,
+        expired_file: options.expired.then_some(expired_report.as_path()),
     };
-    upside::upside::calculate(conf)?;
+    let outcome = upside::upside::calculate(conf)?;
+    for target in outcome.flagged {
+        match (target.status, &target.suggestion) {
+            (PriceStatus::Unmatched, Some(suggestion)) => println!(
+                "{}: no market data, did you mean {suggestion}?",
+                target.instrument
+            ),
+            (PriceStatus::Unmatched, None) => println!("{}: no market data", target.instrument),
+            _ => println!(
+                "{}: stale price from {}",
+                target.instrument,
+                target.price_date.map_or("-".to_string(), |d| d.to_string())
+            ),
+        }
+    }
+    if outcome.expired > 0 {
+        println!(
+            "{} expired, hit or stopped recommendations left out of the report",
+            outcome.expired
+        );
+    }
+    if options.expired {
+        println!(
+            "Expired recommendations saved to {}",
+            expired_report.display()
+        );
+    }
+    if options.held.is_some() {
+        println!("Held positions upside saved to {}", held_report.display());
+    }
+    if options.simulation.is_some() {
+        println!("Simulation saved to {}", simulation_report.display());
+    }
+    if options.allocation.is_some() {
+        println!("Buy list ({}):", buy_list_report.display());
+        for position in &outcome.buy_list {
+            println!(
+                "  {:<12} {:>8} x {:<10} cost {:>10.2}, expected {:>9.2}",
+                position.instrument,
+                position.quantity,
+                position.price,
+                position.cost,
+                position.expected_value
+            );
+        }
+    }
     Ok(())
 }
@@ -0,0 +1,86 @@
+use std::path::{Path, PathBuf};
+
+use rebalance::rebalance::{Plan, RebalanceConf};
+use rebalance::targets::{AssetClasses, TargetWeights};
+use shared_contracts::models::instrument::InstrumentMaster;
+use util::money::Commission;
+use util::report::ReportFormat;
+
+use crate::error::CliError;
+use crate::market_data_import_handler;
+use crate::mbank_trade_report_csv_handler;
+
+pub struct RebalanceOptions<'a> {
+    pub asset_classes_csv: Option<&'a Path>,
+    pub cash: f64,
+    pub tolerance_pct: f64,
+    pub commission: Commission,
+    pub format: ReportFormat,
+}
+
+/// `market_data` is a list of GPW daily statistics xls files, a single market data store
+/// directory or a normalized market data csv.
+pub fn handle(
+    trade_orders_csv: &Path,
+    market_data: &[PathBuf],
+    targets_csv: &Path,
+    options: RebalanceOptions,
+    instruments: &InstrumentMaster,
+) -> Result<(), CliError> {
+    let normalized_orders_csv =
+        mbank_trade_report_csv_handler::normalize(trade_orders_csv, instruments)?;
+
+    let market_data_csv = util::file::new_file_with_suffix(trade_orders_csv, "market_data.csv")?;
+    let source = market_data_import_handler::source(market_data, &market_data_csv)?;
+
+    let targets = TargetWeights::load(targets_csv)?;
+    let asset_classes = match options.asset_classes_csv {
+        Some(csv) => AssetClasses::load(csv, instruments)?,
+        None => AssetClasses::default(),
+    };
+
+    let orders_file = util::file::new_file_with_suffix(targets_csv, "orders.csv")?;
+    let rebalance_report = util::file::new_file_with_suffix(
+        targets_csv,
+        &format!("rebalance.{}", options.format.extension()),
+    )?;
+
+    let conf = RebalanceConf {
+        trade_orders_csv: &normalized_orders_csv,
+        market_data: source,
+        instruments,
+        targets: &targets,
+        asset_classes: &asset_classes,
+        plan: Plan {
+            cash_addition: options.cash,
+            tolerance_pct: options.tolerance_pct,
+            commission: options.commission,
+        },
+        orders_file: &orders_file,
+        output_file: &rebalance_report,
+        format: options.format,
+    };
+    let rebalance = rebalance::rebalance::calculate_and_save(conf)?;
+
+    if rebalance.buy_only {
+        println!("Buy-only rebalance");
+    }
+    println!("Orders ({}):", orders_file.display());
+    for order in rebalance.orders() {
+        println!(
+            "  {:<4} {:<12} {:>8} x {:<10} value {:>10.2}, tax {:>8.2}",
+            order
+                .order_side
+                .as_ref()
+                .map_or(String::new(), |s| s.to_string()),
+            order.instrument,
+            order.order_quantity,
+            order.price,
+            order.order_value(),
+            order.estimated_tax
+        );
+    }
+    println!("Cash left {:.2}", rebalance.cash_left);
+    println!("Rebalance saved to {}", rebalance_report.display());
+    Ok(())
+}
@@ -0,0 +1,28 @@
+use std::path::{Path, PathBuf};
+
+use crate::error::CliError;
+
+/// Appends broker research recommendations to an upside csv, skipping ones imported before.
+pub fn handle(
+    upside_csv: &Path,
+    exports: &[PathBuf],
+    source: Option<&str>,
+) -> Result<(), CliError> {
+    let summary = recommendations::import::import(exports, source, upside_csv)?;
+    println!(
+        "Imported {} recommendations from {} files into {}, {} already imported",
+        summary.imported,
+        summary.files,
+        upside_csv.display(),
+        summary.duplicates
+    );
+    for rejected in &summary.rejected {
+        println!(
+            "{} row {}: rejected, {}",
+            rejected.file.display(),
+            rejected.row,
+            rejected.reason
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,68 @@
+use std::path::{Path, PathBuf};
+
+use shared_contracts::models::instrument::InstrumentMaster;
+use util::report::ReportFormat;
+
+use crate::error::CliError;
+use crate::market_data_import_handler;
+use crate::mbank_trade_report_csv_handler;
+
+pub struct RiskOptions<'a> {
+    pub benchmark: &'a str,
+    pub lookback_sessions: usize,
+    pub correlation_threshold: f64,
+    pub format: ReportFormat,
+}
+
+/// `market_data` is a list of GPW daily statistics xls files, a single market data store
+/// directory or a normalized market data csv.
+pub fn handle(
+    trade_orders_csv: &Path,
+    market_data: &[PathBuf],
+    options: RiskOptions,
+    instruments: &InstrumentMaster,
+) -> Result<(), CliError> {
+    let normalized_orders_csv =
+        mbank_trade_report_csv_handler::normalize(trade_orders_csv, instruments)?;
+
+    let market_data_csv = util::file::new_file_with_suffix(trade_orders_csv, "market_data.csv")?;
+    let source = market_data_import_handler::source(market_data, &market_data_csv)?;
+
+    let extension = options.format.extension();
+    let risk_report =
+        util::file::new_file_with_suffix(trade_orders_csv, &format!("risk.{extension}"))?;
+    let pairs_report = util::file::new_file_with_suffix(
+        trade_orders_csv,
+        &format!("correlated_pairs.{extension}"),
+    )?;
+
+    let conf = risk_report::report::RiskConf {
+        trade_orders_csv: &normalized_orders_csv,
+        market_data: source,
+        instruments,
+        benchmark: options.benchmark,
+        lookback_sessions: options.lookback_sessions,
+        correlation_threshold: options.correlation_threshold,
+        output_file: &risk_report,
+        pairs_file: &pairs_report,
+        format: options.format,
+    };
+    let risk = risk_report::report::calculate_and_save(conf)?;
+
+    println!(
+        "{} sessions {} - {}: portfolio volatility {:.2}%, beta {:.2}",
+        risk.sessions,
+        risk.period_start,
+        risk.period_end,
+        risk.portfolio_volatility * 100_f64,
+        risk.portfolio_beta
+    );
+    for pair in risk.pairs.iter().filter(|p| p.highly_correlated) {
+        println!(
+            "Highly correlated: {} and {} {:.2}",
+            pair.first, pair.second, pair.correlation
+        );
+    }
+    println!("Risk report saved to {}", risk_report.display());
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use std::path::{Path, PathBuf};
+
+use market_store::store::MarketDataStore;
+use stooq::tickers::TickerMap;
+
+use crate::error::CliError;
+
+/// Converts Stooq daily quotes into a normalized market data csv and optionally appends it to the store.
+pub fn handle(
+    output: &Path,
+    inputs: &[PathBuf],
+    tickers_csv: Option<&Path>,
+    store_dir: Option<&Path>,
+) -> Result<(), CliError> {
+    let tickers = match tickers_csv {
+        Some(csv) => TickerMap::load(csv)?,
+        None => TickerMap::default(),
+    };
+
+    let summary = stooq::quotes::convert_to_csv(inputs, &tickers, output)?;
+    println!(
+        "Imported {} files, {} rows into {}",
+        summary.files,
+        summary.rows,
+        output.display()
+    );
+    if !summary.unmapped_tickers.is_empty() {
+        println!(
+            "Tickers without instrument mapping: {}",
+            summary
+                .unmapped_tickers
+                .iter()
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+
+    if let Some(store_dir) = store_dir {
+        let store = MarketDataStore::open(store_dir)?;
+        let appended = store.append_csv(output)?;
+        println!(
+            "Appended {} sessions, {} rows to {}",
+            appended.sessions,
+            appended.rows,
+            store.root().display()
+        );
+    }
+    Ok(())
+}
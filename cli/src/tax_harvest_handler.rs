@@ -0,0 +1,70 @@
+use std::path::{Path, PathBuf};
+
+use shared_contracts::models::instrument::InstrumentMaster;
+use tax_harvest::harvest::HarvestConf;
+use util::money::Commission;
+use util::report::ReportFormat;
+
+use crate::error::CliError;
+use crate::market_data_import_handler;
+use crate::mbank_trade_report_csv_handler;
+
+/// `market_data` is a list of GPW daily statistics xls files, a single market data store
+/// directory or a normalized market data csv.
+pub fn handle(
+    trade_orders_csv: &Path,
+    market_data: &[PathBuf],
+    year: Option<i32>,
+    target_tax: f64,
+    commission: Commission,
+    format: ReportFormat,
+    instruments: &InstrumentMaster,
+) -> Result<(), CliError> {
+    let normalized_orders_csv =
+        mbank_trade_report_csv_handler::normalize(trade_orders_csv, instruments)?;
+
+    let market_data_csv = util::file::new_file_with_suffix(trade_orders_csv, "market_data.csv")?;
+    let source = market_data_import_handler::source(market_data, &market_data_csv)?;
+
+    let harvest_report = util::file::new_file_with_suffix(
+        trade_orders_csv,
+        &format!("tax_harvest.{}", format.extension()),
+    )?;
+
+    let conf = HarvestConf {
+        trade_orders_csv: &normalized_orders_csv,
+        market_data: source,
+        instruments,
+        year,
+        target_tax,
+        commission,
+        output_file: &harvest_report,
+        format,
+    };
+    let harvest = tax_harvest::harvest::calculate_and_save(conf)?;
+
+    println!(
+        "{}: realized tax base {:.2}, tax {:.2} (average cost base of the profit report {:.2})",
+        harvest.year,
+        harvest.realized_base,
+        harvest.realized_tax(),
+        harvest.average_cost_base
+    );
+    for position in harvest.positions.iter() {
+        let Some(sale) = position.sale.as_ref() else {
+            continue;
+        };
+        println!(
+            "  Sell {:<12} {:>8} x {:<10} tax saved {:>9.2}, commission {:>7.2}, left {}",
+            position.instrument,
+            sale.quantity,
+            position.price,
+            sale.tax_saved,
+            sale.commission,
+            position.quantity_left()
+        );
+    }
+    println!("Tax after the sells {:.2}", harvest.tax_after());
+    println!("Tax-loss harvesting saved to {}", harvest_report.display());
+    Ok(())
+}
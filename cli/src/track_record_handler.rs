@@ -0,0 +1,49 @@
+use std::path::{Path, PathBuf};
+
+use market_store::source::MarketDataSource;
+use shared_contracts::models::instrument::InstrumentMaster;
+use track_record::track_record::TrackRecordConf;
+use util::report::ReportFormat;
+
+use crate::error::CliError;
+
+/// Writes `<first upside csv>_track_record` and the `_source_scores.csv` used by
+/// `upside-csv --source-scores`, and prints the score of every source.
+pub fn handle(
+    market_data: &Path,
+    upside_csv: &[PathBuf],
+    default_horizon_months: u32,
+    format: ReportFormat,
+    instruments: &InstrumentMaster,
+) -> Result<(), CliError> {
+    let first = upside_csv
+        .first()
+        .map(PathBuf::as_path)
+        .unwrap_or(market_data);
+    let output_file =
+        util::file::new_file_with_suffix(first, &format!("track_record.{}", format.extension()))?;
+    let scores_file = util::file::new_file_with_suffix(first, "source_scores.csv")?;
+
+    let conf = TrackRecordConf {
+        upside_csv,
+        market_data: MarketDataSource::from_path(market_data),
+        instruments,
+        default_horizon_months,
+        output_file: &output_file,
+        scores_file: &scores_file,
+        format,
+    };
+    for score in track_record::track_record::calculate_and_save(conf)? {
+        println!(
+            "{}: {}/{} targets hit ({} recommendations), bias {}%, weight {}",
+            score.created_by,
+            score.hits,
+            score.evaluated,
+            score.recommendations,
+            score.bias_pct.map_or("-".to_string(), |b| b.to_string()),
+            score.weight
+        );
+    }
+    println!("Saved source scores to {}", scores_file.display());
+    Ok(())
+}
@@ -0,0 +1,48 @@
+use std::path::{Path, PathBuf};
+
+use gpw::validation::{self, ValidationConf};
+use util::report::ReportFormat;
+
+use crate::error::CliError;
+
+/// Validates `market_data_files` as one series and prints or saves the findings.
+pub fn handle(
+    market_data_files: &[PathBuf],
+    output: Option<&Path>,
+    format: ReportFormat,
+    max_daily_change: f64,
+    strict: bool,
+) -> Result<(), CliError> {
+    let mut records = Vec::new();
+    for file in market_data_files {
+        records.extend(validation::load(file)?);
+    }
+
+    let conf = ValidationConf {
+        max_daily_change,
+        strict,
+        ..Default::default()
+    };
+    let findings = validation::validate(&records, &conf);
+
+    match output {
+        Some(output) => {
+            validation::save_findings(&findings, output, format)?;
+            println!("Saved findings to {}", output.display());
+        }
+        None => {
+            for finding in &findings {
+                println!("{finding}");
+            }
+        }
+    }
+    println!(
+        "Validated {} rows: {} errors, {} warnings",
+        records.len(),
+        validation::errors(&findings),
+        validation::warnings(&findings)
+    );
+
+    conf.enforce(&findings)?;
+    Ok(())
+}
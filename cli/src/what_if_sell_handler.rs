@@ -0,0 +1,62 @@
+use std::path::Path;
+
+use market_store::source::MarketDataSource;
+use shared_contracts::models::instrument::InstrumentMaster;
+use tax_harvest::what_if::{CostMethod, WhatIfConf};
+use util::money::Commission;
+
+use crate::error::CliError;
+
+pub struct WhatIfOptions<'a> {
+    /// Sell price, the latest close of `market_data` when `None`.
+    pub price: Option<f64>,
+    pub market_data: Option<&'a Path>,
+    pub method: CostMethod,
+    pub commission: Commission,
+}
+
+/// Reads the mBank eMakler export and the market data in place, no file is written.
+pub fn handle(
+    trade_orders_csv: &Path,
+    instrument: &str,
+    quantity: i64,
+    options: WhatIfOptions,
+    instruments: &InstrumentMaster,
+) -> Result<(), CliError> {
+    let trade_orders = mbank_emakler_csv::loader::load(trade_orders_csv, instruments)?;
+
+    let conf = WhatIfConf {
+        trade_orders,
+        market_data: options.market_data.map(MarketDataSource::from_path),
+        instruments,
+        instrument,
+        quantity,
+        price: options.price,
+        method: options.method,
+        commission: options.commission,
+    };
+    let impact = tax_harvest::what_if::what_if(conf)?;
+
+    println!(
+        "Sell {} {} x {:.2}: proceeds {:.2}, commission {:.2}",
+        impact.instrument, impact.quantity, impact.price, impact.proceeds, impact.commission
+    );
+    println!(
+        "Cost {:.2}, realized gain {:.2}, tax due {:.2}",
+        impact.cost,
+        impact.realized_gain(),
+        impact.tax_due()
+    );
+    println!(
+        "{}: realized base {:.2} -> {:.2}, tax {:.2}",
+        impact.year,
+        impact.year_base,
+        impact.year_base + impact.realized_gain(),
+        impact.year_tax_after()
+    );
+    println!(
+        "Left {} shares at an average cost of {:.4}",
+        impact.quantity_left, impact.average_cost_left
+    );
+    Ok(())
+}
@@ -0,0 +1,3 @@
+pub mod lots;
+pub mod positions;
+pub mod trades;
@@ -0,0 +1,183 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use chrono::{DateTime, Utc};
+use shared_contracts::errors::PortfolioError;
+use shared_contracts::models::trade_order::{OrderSide, TradeOrder};
+
+/// Shares of one buy order still held.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lot {
+    pub acquired: DateTime<Utc>,
+    pub quantity: i64,
+    pub price: f64,
+    /// Buy commission of the held quantity.
+    pub commission: f64,
+}
+
+impl Lot {
+    /// Cost of `quantity` shares of the lot, its commission share included.
+    pub fn cost(&self, quantity: i64) -> f64 {
+        if self.quantity == 0 {
+            return 0_f64;
+        }
+        (self.price + self.commission / self.quantity as f64) * quantity as f64
+    }
+}
+
+/// Sell matched to the oldest lots.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RealizedSale {
+    pub instrument: String,
+    pub sold: DateTime<Utc>,
+    pub quantity: i64,
+    /// Sale value less the sell commission.
+    pub proceeds: f64,
+    /// Cost of the matched lots with their buy commissions.
+    pub cost: f64,
+}
+
+impl RealizedSale {
+    pub fn gain(&self) -> f64 {
+        self.proceeds - self.cost
+    }
+}
+
+/// Purchase lots matched first in, first out, the order the Polish tax rules
+/// require for shares bought at different times.
+#[derive(Debug, Clone, Default)]
+pub struct Lots {
+    open: BTreeMap<String, VecDeque<Lot>>,
+    realized: Vec<RealizedSale>,
+}
+
+impl Lots {
+    pub fn from_trades(trades: &[TradeOrder]) -> Result<Self, PortfolioError> {
+        let mut lots = Lots::default();
+        for trade in trades {
+            lots.apply(trade)?;
+        }
+        Ok(lots)
+    }
+
+    pub fn apply(&mut self, order: &TradeOrder) -> Result<(), PortfolioError> {
+        let price = order.price.ok_or_else(|| {
+            PortfolioError::InvalidValue(format!(
+                "price of {} order from {}",
+                order.instrument, order.submission_time
+            ))
+        })?;
+        let lots = self.open.entry(order.instrument.clone()).or_default();
+
+        match order.order_side {
+            OrderSide::Buy => lots.push_back(Lot {
+                acquired: order.submission_time,
+                quantity: order.filled_quantity,
+                price,
+                commission: order.commission,
+            }),
+            OrderSide::Sell => {
+                let held: i64 = lots.iter().map(|l| l.quantity).sum();
+                if order.filled_quantity > held {
+                    return Err(PortfolioError::InvalidValue(format!(
+                        "sell of {} {} on {} exceeds held quantity {held}",
+                        order.filled_quantity, order.instrument, order.submission_time
+                    )));
+                }
+                let mut remaining = order.filled_quantity;
+                let mut cost = 0_f64;
+                while remaining > 0 {
+                    let Some(lot) = lots.front_mut() else {
+                        break;
+                    };
+                    let matched = remaining.min(lot.quantity);
+                    let matched_cost = lot.cost(matched);
+                    cost += matched_cost;
+                    lot.commission -= matched_cost - lot.price * matched as f64;
+                    lot.quantity -= matched;
+                    remaining -= matched;
+                    if lot.quantity == 0 {
+                        lots.pop_front();
+                    }
+                }
+                self.realized.push(RealizedSale {
+                    instrument: order.instrument.clone(),
+                    sold: order.submission_time,
+                    quantity: order.filled_quantity,
+                    proceeds: price * order.filled_quantity as f64 - order.commission,
+                    cost,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Held lots of the instrument, oldest first.
+    pub fn open_lots(&self, instrument: &str) -> impl Iterator<Item = &Lot> {
+        self.open.get(instrument).into_iter().flatten()
+    }
+
+    /// Instruments with held lots, ordered by instrument.
+    pub fn instruments(&self) -> impl Iterator<Item = &str> {
+        self.open
+            .iter()
+            .filter(|(_, lots)| !lots.is_empty())
+            .map(|(instrument, _)| instrument.as_str())
+    }
+
+    pub fn quantity(&self, instrument: &str) -> i64 {
+        self.open_lots(instrument).map(|l| l.quantity).sum()
+    }
+
+    /// Cost of selling `quantity` shares now: the oldest lots first.
+    /// `None` when fewer shares are held.
+    pub fn fifo_cost(&self, instrument: &str, quantity: i64) -> Option<f64> {
+        let mut remaining = quantity;
+        let mut cost = 0_f64;
+        for lot in self.open_lots(instrument) {
+            if remaining == 0 {
+                break;
+            }
+            let matched = remaining.min(lot.quantity);
+            cost += lot.cost(matched);
+            remaining -= matched;
+        }
+        (remaining == 0).then_some(cost)
+    }
+
+    pub fn realized(&self) -> &[RealizedSale] {
+        &self.realized
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_trades_sell_across_lots_return_oldest_lots_cost() {
+        let trades = [
+            TradeOrder {
+                commission: 5.0,
+                ..TradeOrder::filled("ORANGEPL", OrderSide::Buy, 100, 10.0, "2025-03-03")
+            },
+            TradeOrder {
+                commission: 10.0,
+                ..TradeOrder::filled("ORANGEPL", OrderSide::Buy, 100, 20.0, "2025-03-04")
+            },
+            TradeOrder {
+                commission: 15.0,
+                ..TradeOrder::filled("ORANGEPL", OrderSide::Sell, 150, 30.0, "2025-03-05")
+            },
+        ];
+
+        let lots = Lots::from_trades(&trades).unwrap();
+
+        let sale = &lots.realized()[0];
+        // 100 x 10 + 5 and 50 x 20 + half of 10
+        assert!((sale.cost - 2010.0).abs() < 1e-9);
+        assert!((sale.gain() - (4500.0 - 15.0 - 2010.0)).abs() < 1e-9);
+        assert_eq!(lots.quantity("ORANGEPL"), 50);
+        assert!((lots.fifo_cost("ORANGEPL", 50).unwrap() - 1005.0).abs() < 1e-9);
+        assert_eq!(lots.fifo_cost("ORANGEPL", 51), None);
+    }
+}
@@ -0,0 +1,104 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use shared_contracts::errors::PortfolioError;
+use shared_contracts::models::instrument::InstrumentMaster;
+use shared_contracts::models::trade_order::{OrderSide, TradeOrder};
+
+/// Open position valued with the average cost method,
+/// the same one the profit report uses for the tax base.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Position {
+    pub instrument: String,
+    pub quantity: i64,
+    /// Purchase value of the held quantity including buy commissions.
+    pub cost_basis: f64,
+}
+
+impl Position {
+    pub fn average_cost(&self) -> f64 {
+        if self.quantity == 0 {
+            0_f64
+        } else {
+            self.cost_basis / self.quantity as f64
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Holdings {
+    positions: BTreeMap<String, Position>,
+}
+
+impl Holdings {
+    pub fn from_trades(trades: &[TradeOrder]) -> Result<Self, PortfolioError> {
+        let mut holdings = Holdings::default();
+        for trade in trades {
+            holdings.apply(trade)?;
+        }
+        Ok(holdings)
+    }
+
+    /// Holdings of the normalized trade orders under GPW symbols.
+    pub fn load(
+        normalized_orders_csv: &Path,
+        instruments: &InstrumentMaster,
+    ) -> Result<Self, PortfolioError> {
+        Self::from_trades(&crate::trades::load_resolved(
+            normalized_orders_csv,
+            instruments,
+        )?)
+    }
+
+    pub fn apply(&mut self, order: &TradeOrder) -> Result<(), PortfolioError> {
+        let price = order.price.ok_or_else(|| {
+            PortfolioError::InvalidValue(format!(
+                "price of {} order from {}",
+                order.instrument, order.submission_time
+            ))
+        })?;
+
+        let position = self
+            .positions
+            .entry(order.instrument.clone())
+            .or_insert_with(|| Position {
+                instrument: order.instrument.clone(),
+                ..Default::default()
+            });
+
+        match order.order_side {
+            OrderSide::Buy => {
+                position.quantity += order.filled_quantity;
+                position.cost_basis += price * order.filled_quantity as f64 + order.commission;
+            }
+            OrderSide::Sell => {
+                if order.filled_quantity > position.quantity {
+                    return Err(PortfolioError::InvalidValue(format!(
+                        "sell of {} {} on {} exceeds held quantity {}",
+                        order.filled_quantity,
+                        order.instrument,
+                        order.submission_time,
+                        position.quantity
+                    )));
+                }
+                let average_cost = position.average_cost();
+                position.quantity -= order.filled_quantity;
+                position.cost_basis = if position.quantity == 0 {
+                    0_f64
+                } else {
+                    position.cost_basis - average_cost * order.filled_quantity as f64
+                };
+            }
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, instrument: &str) -> Option<&Position> {
+        self.positions.get(instrument).filter(|p| p.quantity > 0)
+    }
+
+    /// Positions with a non-zero quantity, ordered by instrument.
+    pub fn open_positions(&self) -> impl Iterator<Item = &Position> {
+        self.positions.values().filter(|p| p.quantity > 0)
+    }
+}
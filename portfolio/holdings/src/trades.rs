@@ -0,0 +1,44 @@
+use std::path::Path;
+
+use shared_contracts::errors::PortfolioError;
+use shared_contracts::models::instrument::InstrumentMaster;
+use shared_contracts::models::trade_order::{OrderStatus, TradeOrder};
+
+/// Reads normalized trade orders and keeps only executed ones,
+/// sorted by submission time.
+pub fn load_filled(normalized_orders_csv: &Path) -> Result<Vec<TradeOrder>, PortfolioError> {
+    let mut rdr = util::csv::default_reader(normalized_orders_csv)?;
+
+    let mut orders = Vec::new();
+    for result in rdr.deserialize() {
+        let order: TradeOrder = result?;
+        orders.push(order);
+    }
+    Ok(filled(orders))
+}
+
+/// Executed trade orders sorted by submission time.
+pub fn filled(mut orders: Vec<TradeOrder>) -> Vec<TradeOrder> {
+    orders.retain(is_executed);
+    orders.sort_by_key(|o| o.submission_time);
+    orders
+}
+
+/// Executed trade orders with instrument names resolved to the GPW symbols of the market data.
+pub fn load_resolved(
+    normalized_orders_csv: &Path,
+    instruments: &InstrumentMaster,
+) -> Result<Vec<TradeOrder>, PortfolioError> {
+    let mut trades = load_filled(normalized_orders_csv)?;
+    for trade in trades.iter_mut() {
+        trade.instrument = instruments.symbol(&trade.instrument).to_string();
+    }
+    Ok(trades)
+}
+
+fn is_executed(order: &TradeOrder) -> bool {
+    matches!(
+        order.status,
+        OrderStatus::Filled | OrderStatus::PartiallyFilled
+    ) && order.filled_quantity > 0
+}
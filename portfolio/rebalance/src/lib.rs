@@ -0,0 +1,2 @@
+pub mod rebalance;
+pub mod targets;
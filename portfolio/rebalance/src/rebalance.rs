@@ -0,0 +1,398 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use holdings::positions::Holdings;
+use market_store::source::MarketDataSource;
+use polars::prelude::*;
+use shared_contracts::errors::PortfolioError;
+use shared_contracts::models::instrument::InstrumentMaster;
+use shared_contracts::models::trade_order::{
+    InstrumentType, OrderSide, OrderStatus, OrderType, TradeOrder,
+};
+use util::math::round;
+use util::money::{Commission, TAX_RATE};
+use util::report::{Report, ReportFormat};
+
+use crate::targets::{AssetClasses, TargetWeights};
+
+pub struct RebalanceConf<'a> {
+    pub trade_orders_csv: &'a Path,
+    pub market_data: MarketDataSource<'a>,
+    /// Resolves trade, target and market data names to one symbol.
+    pub instruments: &'a InstrumentMaster,
+    pub targets: &'a TargetWeights,
+    pub asset_classes: &'a AssetClasses,
+    pub plan: Plan,
+    /// Orders in the normalized trade orders csv shape.
+    pub orders_file: &'a Path,
+    pub output_file: &'a Path,
+    pub format: ReportFormat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Plan {
+    /// Cash paid in before rebalancing.
+    pub cash_addition: f64,
+    /// Positions within this many percentage points of their target are not traded.
+    pub tolerance_pct: f64,
+    pub commission: Commission,
+}
+
+/// One instrument before and after the rebalance.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Allocation {
+    pub instrument: String,
+    pub price: f64,
+    pub quantity: i64,
+    pub average_cost: f64,
+    pub target_pct: f64,
+    pub order_side: Option<OrderSide>,
+    pub order_quantity: i64,
+    pub commission: f64,
+    /// 19% of the gain of a sell over the average cost, 0 for a loss.
+    pub estimated_tax: f64,
+}
+
+impl Allocation {
+    pub fn value(&self) -> f64 {
+        round(self.quantity as f64 * self.price, 2)
+    }
+
+    pub fn quantity_after(&self) -> i64 {
+        match self.order_side {
+            Some(OrderSide::Buy) => self.quantity + self.order_quantity,
+            Some(OrderSide::Sell) => self.quantity - self.order_quantity,
+            None => self.quantity,
+        }
+    }
+
+    pub fn order_value(&self) -> f64 {
+        round(self.order_quantity as f64 * self.price, 2)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rebalance {
+    pub allocations: Vec<Allocation>,
+    /// No position is above its band, only buys are needed.
+    pub buy_only: bool,
+    pub cash_addition: f64,
+    pub cash_left: f64,
+}
+
+impl Rebalance {
+    pub fn total_value(&self) -> f64 {
+        round(
+            self.allocations.iter().map(Allocation::value).sum::<f64>() + self.cash_addition,
+            2,
+        )
+    }
+
+    pub fn orders(&self) -> impl Iterator<Item = &Allocation> {
+        self.allocations.iter().filter(|a| a.order_side.is_some())
+    }
+}
+
+pub fn calculate_and_save(conf: RebalanceConf) -> Result<Rebalance, PortfolioError> {
+    let holdings = Holdings::load(conf.trade_orders_csv, conf.instruments)?;
+    let prices = conf.market_data.latest_closes(conf.instruments)?;
+
+    let mut values = BTreeMap::new();
+    for position in holdings.open_positions() {
+        let price = prices.get(&position.instrument).ok_or_else(|| {
+            PortfolioError::InputError(format!(
+                "no market data price for held {}",
+                position.instrument
+            ))
+        })?;
+        values.insert(
+            position.instrument.clone(),
+            position.quantity as f64 * price,
+        );
+    }
+    let weights = conf
+        .targets
+        .instrument_weights(conf.asset_classes, conf.instruments, &values);
+
+    let rebalance = rebalance(&holdings, &prices, &weights, &conf.plan)?;
+
+    let submission_time = Utc::now();
+    let mut wtr = util::csv::default_writer(conf.orders_file)?;
+    for order in trade_orders(&rebalance, submission_time) {
+        wtr.serialize(order)?;
+    }
+    wtr.flush()?;
+
+    let mut report = Report::new("Rebalance", allocations_data_frame(&rebalance)?)
+        .with_summary(summary_data_frame(&rebalance)?);
+    util::report::save(&mut report, conf.output_file, conf.format)?;
+    Ok(rebalance)
+}
+
+/// Orders moving every position outside its tolerance band back into it.
+///
+/// Sells are planned only when a position is above its band, a buy-only rebalance
+/// cannot reduce it. Sells trim the position to the upper band edge, realizing no more
+/// gain than needed, a position with a zero target is sold whole. Their net proceeds
+/// fund the buys.
+/// Buys go to the target as well; when the cash is short every buy gets the same
+/// share of its shortfall. Quantities are whole shares after commissions.
+pub fn rebalance(
+    holdings: &Holdings,
+    prices: &BTreeMap<String, f64>,
+    weights: &BTreeMap<String, f64>,
+    plan: &Plan,
+) -> Result<Rebalance, PortfolioError> {
+    let mut allocations = Vec::new();
+    for (instrument, target_pct) in weights {
+        let position = holdings.get(instrument);
+        let price = prices.get(instrument).copied().ok_or_else(|| {
+            PortfolioError::InputError(format!("no market data price for {instrument}"))
+        })?;
+        allocations.push(Allocation {
+            instrument: instrument.clone(),
+            price,
+            quantity: position.map_or(0, |p| p.quantity),
+            average_cost: position.map_or(0_f64, |p| p.average_cost()),
+            target_pct: *target_pct,
+            order_side: None,
+            order_quantity: 0,
+            commission: 0_f64,
+            estimated_tax: 0_f64,
+        });
+    }
+
+    let total = allocations.iter().map(Allocation::value).sum::<f64>() + plan.cash_addition;
+    let band = plan.tolerance_pct / 100_f64 * total;
+    let target_value = |a: &Allocation| a.target_pct / 100_f64 * total;
+    let mut cash = plan.cash_addition;
+
+    let overweight: Vec<usize> = (0..allocations.len())
+        .filter(|i| allocations[*i].value() > target_value(&allocations[*i]) + band)
+        .collect();
+    for &i in &overweight {
+        let allocation = &mut allocations[i];
+        let excess = allocation.value() - (allocation.target_pct / 100_f64 * total + band);
+        let quantity = if allocation.target_pct == 0_f64 {
+            allocation.quantity
+        } else {
+            ((excess / allocation.price).ceil() as i64).min(allocation.quantity)
+        };
+        if quantity == 0 {
+            continue;
+        }
+        let sale_value = round(quantity as f64 * allocation.price, 2);
+        let commission = plan.commission.amount(sale_value);
+        let gain = sale_value - commission - allocation.average_cost * quantity as f64;
+        allocation.order_side = Some(OrderSide::Sell);
+        allocation.order_quantity = quantity;
+        allocation.commission = commission;
+        allocation.estimated_tax = round(gain.max(0_f64) * TAX_RATE, 2);
+        cash += sale_value - commission;
+    }
+
+    let shortfalls: Vec<(usize, f64)> = allocations
+        .iter()
+        .enumerate()
+        .filter(|(_, a)| a.value() < target_value(a) - band)
+        .map(|(i, a)| (i, target_value(a) - a.value()))
+        .collect();
+    let needed: f64 = shortfalls.iter().map(|(_, s)| s).sum();
+    let scale = if needed > cash { cash / needed } else { 1_f64 };
+    for (i, shortfall) in shortfalls {
+        let allocation = &mut allocations[i];
+        let quantity = max_quantity(allocation.price, shortfall * scale, plan);
+        if quantity == 0 {
+            continue;
+        }
+        let purchase_value = round(quantity as f64 * allocation.price, 2);
+        let commission = plan.commission.amount(purchase_value);
+        allocation.order_side = Some(OrderSide::Buy);
+        allocation.order_quantity = quantity;
+        allocation.commission = commission;
+        cash -= purchase_value + commission;
+    }
+
+    Ok(Rebalance {
+        allocations,
+        buy_only: overweight.is_empty(),
+        cash_addition: plan.cash_addition,
+        cash_left: round(cash, 2),
+    })
+}
+
+/// Pending limit orders at the latest close, the shape of normalized trade orders.
+pub fn trade_orders(rebalance: &Rebalance, submission_time: DateTime<Utc>) -> Vec<TradeOrder> {
+    rebalance
+        .orders()
+        .map(|a| TradeOrder {
+            instrument: a.instrument.clone(),
+            instrument_type: InstrumentType::Stock,
+            order_type: OrderType::Limit,
+            order_side: a.order_side.clone().unwrap_or(OrderSide::Buy),
+            quantity: a.order_quantity,
+            filled_quantity: 0,
+            price: Some(a.price),
+            commission: a.commission,
+            status: OrderStatus::Pending,
+            submission_time,
+            currency: "PLN".to_string(),
+            exchange: "GPW".to_string(),
+        })
+        .collect()
+}
+
+fn allocations_data_frame(rebalance: &Rebalance) -> Result<DataFrame, PortfolioError> {
+    let allocations = &rebalance.allocations;
+    let total = rebalance.total_value();
+    let total_after = allocations
+        .iter()
+        .map(|a| a.quantity_after() as f64 * a.price)
+        .sum::<f64>()
+        + rebalance.cash_left;
+    let pct = |value: f64, total: f64| {
+        if total > 0_f64 {
+            round(value / total * 100_f64, 2)
+        } else {
+            0_f64
+        }
+    };
+
+    let df = df!(
+        "instrument" => allocations.iter().map(|a| a.instrument.as_str()).collect::<Vec<_>>(),
+        "price" => allocations.iter().map(|a| a.price).collect::<Vec<_>>(),
+        "quantity" => allocations.iter().map(|a| a.quantity).collect::<Vec<_>>(),
+        "value" => allocations.iter().map(Allocation::value).collect::<Vec<_>>(),
+        "weight_pct" => allocations.iter().map(|a| pct(a.value(), total)).collect::<Vec<_>>(),
+        "target_pct" => allocations.iter().map(|a| round(a.target_pct, 2)).collect::<Vec<_>>(),
+        "order_side" => allocations.iter().map(|a| a.order_side.as_ref().map(|s| s.to_string())).collect::<Vec<_>>(),
+        "order_quantity" => allocations.iter().map(|a| a.order_quantity).collect::<Vec<_>>(),
+        "order_value" => allocations.iter().map(Allocation::order_value).collect::<Vec<_>>(),
+        "commission" => allocations.iter().map(|a| a.commission).collect::<Vec<_>>(),
+        "estimated_tax" => allocations.iter().map(|a| a.estimated_tax).collect::<Vec<_>>(),
+        "quantity_after" => allocations.iter().map(Allocation::quantity_after).collect::<Vec<_>>(),
+        "weight_after_pct" => allocations.iter().map(|a| pct(a.quantity_after() as f64 * a.price, total_after)).collect::<Vec<_>>(),
+    )?;
+    Ok(df)
+}
+
+fn summary_data_frame(rebalance: &Rebalance) -> Result<DataFrame, PortfolioError> {
+    let orders: Vec<&Allocation> = rebalance.orders().collect();
+    let df = df!(
+        "total_value" => [rebalance.total_value()],
+        "cash_addition" => [rebalance.cash_addition],
+        "cash_left" => [rebalance.cash_left],
+        "buy_only" => [rebalance.buy_only],
+        "orders" => [orders.len() as u64],
+        "commissions" => [round(orders.iter().map(|a| a.commission).sum(), 2)],
+        "estimated_tax" => [round(orders.iter().map(|a| a.estimated_tax).sum(), 2)],
+    )?;
+    Ok(df)
+}
+
+/// Most shares whose purchase value and buy commission fit in `budget`.
+fn max_quantity(price: f64, budget: f64, plan: &Plan) -> i64 {
+    if price <= 0_f64 {
+        return 0;
+    }
+    let by_percent = budget / (price * (1_f64 + plan.commission.percent));
+    let by_minimum = (budget - plan.commission.min) / price;
+    by_percent.min(by_minimum).max(0_f64).floor() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rebalance_cash_covers_underweight_return_buy_only_orders() {
+        let holdings = Holdings::from_trades(&[
+            TradeOrder::filled("ORANGEPL", OrderSide::Buy, 500, 10.0, "2025-03-03"),
+            TradeOrder::filled("PKOBP", OrderSide::Buy, 50, 60.0, "2025-03-03"),
+        ])
+        .unwrap();
+        let prices = BTreeMap::from([("ORANGEPL".to_string(), 10.0), ("PKOBP".to_string(), 60.0)]);
+        let weights = BTreeMap::from([("ORANGEPL".to_string(), 50.0), ("PKOBP".to_string(), 50.0)]);
+        let plan = _plan(2000.0);
+
+        let rebalance = rebalance(&holdings, &prices, &weights, &plan).unwrap();
+
+        assert!(rebalance.buy_only);
+        let orders: Vec<(&str, Option<OrderSide>, i64)> = rebalance
+            .allocations
+            .iter()
+            .map(|a| {
+                (
+                    a.instrument.as_str(),
+                    a.order_side.clone(),
+                    a.order_quantity,
+                )
+            })
+            .collect();
+        // 10000 total: ORANGEPL 5000 is on target, PKOBP 3000 gets 1995 after the minimum fee.
+        assert_eq!(
+            orders,
+            vec![("ORANGEPL", None, 0), ("PKOBP", Some(OrderSide::Buy), 33)]
+        );
+    }
+
+    #[test]
+    fn rebalance_overweight_position_return_sell_funding_buy_with_tax() {
+        let holdings = Holdings::from_trades(&[
+            TradeOrder::filled("ORANGEPL", OrderSide::Buy, 800, 5.0, "2025-03-03"),
+            TradeOrder::filled("PKOBP", OrderSide::Buy, 20, 60.0, "2025-03-03"),
+        ])
+        .unwrap();
+        let prices = BTreeMap::from([("ORANGEPL".to_string(), 10.0), ("PKOBP".to_string(), 60.0)]);
+        let weights = BTreeMap::from([("ORANGEPL".to_string(), 50.0), ("PKOBP".to_string(), 50.0)]);
+        let plan = _plan(0.0);
+
+        let rebalance = rebalance(&holdings, &prices, &weights, &plan).unwrap();
+
+        assert!(!rebalance.buy_only);
+        let orangepl = &rebalance.allocations[0];
+        let pkobp = &rebalance.allocations[1];
+        // 9200 total, 4600 +/- 460 each: sell 294 ORANGEPL bought at 5.00.
+        assert_eq!(orangepl.order_side, Some(OrderSide::Sell));
+        assert_eq!(orangepl.order_quantity, 294);
+        assert_eq!(
+            orangepl.estimated_tax,
+            round((2940.0 - 5.0 - 1470.0) * 0.19, 2)
+        );
+        assert_eq!(pkobp.order_side, Some(OrderSide::Buy));
+        assert_eq!(pkobp.order_quantity, 48);
+        assert!(rebalance.cash_left >= 0.0);
+    }
+
+    #[test]
+    fn rebalance_overweight_position_trims_to_band_edge() {
+        let holdings = Holdings::from_trades(&[
+            TradeOrder::filled("ORANGEPL", OrderSide::Buy, 700, 10.0, "2025-03-03"),
+            TradeOrder::filled("PKOBP", OrderSide::Buy, 50, 60.0, "2025-03-03"),
+        ])
+        .unwrap();
+        let prices = BTreeMap::from([("ORANGEPL".to_string(), 10.0), ("PKOBP".to_string(), 60.0)]);
+        let weights = BTreeMap::from([("ORANGEPL".to_string(), 50.0), ("PKOBP".to_string(), 50.0)]);
+        let plan = _plan(0.0);
+
+        let rebalance = rebalance(&holdings, &prices, &weights, &plan).unwrap();
+
+        // 10000 total: ORANGEPL 7000 is trimmed to 5500, the top of the 5000 +/- 500 band.
+        let orangepl = &rebalance.allocations[0];
+        assert_eq!(orangepl.order_quantity, 150);
+        assert_eq!(orangepl.quantity_after(), 550);
+        assert_eq!(orangepl.estimated_tax, 0.0);
+    }
+
+    fn _plan(cash_addition: f64) -> Plan {
+        Plan {
+            cash_addition,
+            tolerance_pct: 5.0,
+            commission: Commission {
+                percent: 0.0,
+                min: 5.0,
+            },
+        }
+    }
+}
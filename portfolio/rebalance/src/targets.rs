@@ -0,0 +1,125 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::Deserialize;
+use shared_contracts::errors::PortfolioError;
+use shared_contracts::models::instrument::InstrumentMaster;
+
+/// Instrument -> asset class, read from an `instrument;asset_class` csv.
+#[derive(Debug, Clone, Default)]
+pub struct AssetClasses {
+    classes: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AssetClassRow {
+    instrument: String,
+    asset_class: String,
+}
+
+impl AssetClasses {
+    /// Instrument names are resolved to market data symbols.
+    pub fn load(csv: &Path, instruments: &InstrumentMaster) -> Result<Self, PortfolioError> {
+        let mut rdr = util::csv::default_reader(csv)?;
+        let mut classes = BTreeMap::new();
+        for result in rdr.deserialize() {
+            let row: AssetClassRow = result?;
+            classes.insert(
+                instruments.symbol(row.instrument.trim()).to_string(),
+                row.asset_class.trim().to_string(),
+            );
+        }
+        Ok(AssetClasses { classes })
+    }
+
+    pub fn class(&self, instrument: &str) -> Option<&str> {
+        self.classes.get(instrument).map(String::as_str)
+    }
+
+    pub fn members<'a>(&'a self, class: &'a str) -> impl Iterator<Item = &'a str> {
+        self.classes
+            .iter()
+            .filter(move |(_, c)| c.as_str() == class)
+            .map(|(instrument, _)| instrument.as_str())
+    }
+
+    pub fn is_class(&self, name: &str) -> bool {
+        self.classes.values().any(|c| c == name)
+    }
+}
+
+/// Target weights csv, `target;weight_pct`. A target is an asset class of the
+/// asset classes csv or an instrument. What is not allocated stays in cash.
+#[derive(Debug, Clone, Default)]
+pub struct TargetWeights {
+    targets: Vec<(String, f64)>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TargetRow {
+    target: String,
+    weight_pct: f64,
+}
+
+impl TargetWeights {
+    pub fn load(csv: &Path) -> Result<Self, PortfolioError> {
+        let mut rdr = util::csv::default_reader(csv)?;
+        let mut targets = Vec::new();
+        for result in rdr.deserialize() {
+            let row: TargetRow = result?;
+            targets.push((row.target.trim().to_string(), row.weight_pct));
+        }
+        TargetWeights::new(targets)
+    }
+
+    pub fn new(targets: Vec<(String, f64)>) -> Result<Self, PortfolioError> {
+        if let Some((target, weight)) = targets.iter().find(|(_, w)| *w < 0_f64) {
+            return Err(PortfolioError::InvalidValue(format!(
+                "negative target weight {weight} of {target}"
+            )));
+        }
+        let total: f64 = targets.iter().map(|(_, w)| w).sum();
+        if total > 100_f64 + 1e-9 {
+            return Err(PortfolioError::InvalidValue(format!(
+                "target weights add up to {total}%, more than 100%"
+            )));
+        }
+        Ok(TargetWeights { targets })
+    }
+
+    /// Target % of every instrument. An asset class weight is split across its members
+    /// in proportion to their current `values`, equally when none of them is held.
+    /// Held instruments without a target get 0.
+    pub fn instrument_weights(
+        &self,
+        classes: &AssetClasses,
+        instruments: &InstrumentMaster,
+        values: &BTreeMap<String, f64>,
+    ) -> BTreeMap<String, f64> {
+        let mut weights: BTreeMap<String, f64> =
+            values.keys().map(|i| (i.clone(), 0_f64)).collect();
+
+        for (target, weight) in &self.targets {
+            if !classes.is_class(target) {
+                *weights
+                    .entry(instruments.symbol(target).to_string())
+                    .or_default() += weight;
+                continue;
+            }
+            let members: Vec<&str> = classes.members(target).collect();
+            let held: f64 = members
+                .iter()
+                .map(|m| values.get(*m).copied().unwrap_or_default())
+                .sum();
+            for member in &members {
+                let share = if held > 0_f64 {
+                    values.get(*member).copied().unwrap_or_default() / held
+                } else {
+                    1_f64 / members.len() as f64
+                };
+                *weights.entry(member.to_string()).or_default() += weight * share;
+            }
+        }
+        weights
+    }
+}
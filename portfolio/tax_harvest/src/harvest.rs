@@ -0,0 +1,383 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use chrono::{DateTime, Datelike, Utc};
+use holdings::lots::Lots;
+use holdings::positions::Holdings;
+use market_store::source::MarketDataSource;
+use polars::prelude::*;
+use shared_contracts::errors::PortfolioError;
+use shared_contracts::models::instrument::InstrumentMaster;
+use shared_contracts::models::trade_order::{OrderSide, TradeOrder};
+use trading_calendar::calendar::TradingCalendar;
+use util::math::round;
+use util::money::{Commission, TAX_RATE};
+use util::report::{Report, ReportFormat};
+
+pub struct HarvestConf<'a> {
+    pub trade_orders_csv: &'a Path,
+    pub market_data: MarketDataSource<'a>,
+    /// Resolves trade and market data names to one symbol.
+    pub instruments: &'a InstrumentMaster,
+    /// Tax year of the realized sales, the one a sell placed today settles in when `None`.
+    pub year: Option<i32>,
+    /// Tax on the realized gains of the year the sells aim at.
+    pub target_tax: f64,
+    pub commission: Commission,
+    pub output_file: &'a Path,
+    pub format: ReportFormat,
+}
+
+/// Suggested sell of the oldest lots of a position.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sale {
+    pub quantity: i64,
+    /// Sale value less the commission.
+    pub proceeds: f64,
+    pub commission: f64,
+    /// Cost of the sold lots.
+    pub cost: f64,
+    pub tax_saved: f64,
+}
+
+impl Sale {
+    pub fn loss(&self) -> f64 {
+        self.cost - self.proceeds
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeldPosition {
+    pub instrument: String,
+    pub quantity: i64,
+    pub price: f64,
+    /// Cost of all held lots.
+    pub cost: f64,
+    pub sale: Option<Sale>,
+}
+
+impl HeldPosition {
+    pub fn value(&self) -> f64 {
+        self.quantity as f64 * self.price
+    }
+
+    pub fn quantity_left(&self) -> i64 {
+        self.quantity - self.sale.as_ref().map_or(0, |s| s.quantity)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Harvest {
+    pub year: i32,
+    /// Realized gains less realized losses of the year, lots matched first in, first out.
+    pub realized_base: f64,
+    /// Realized base of the year with the average cost of the profit report.
+    pub average_cost_base: f64,
+    pub target_tax: f64,
+    /// Ordered by instrument.
+    pub positions: Vec<HeldPosition>,
+}
+
+impl Harvest {
+    pub fn realized_tax(&self) -> f64 {
+        tax(self.realized_base)
+    }
+
+    pub fn base_after(&self) -> f64 {
+        self.realized_base - self.sales().map(Sale::loss).sum::<f64>()
+    }
+
+    pub fn tax_after(&self) -> f64 {
+        tax(self.base_after())
+    }
+
+    pub fn sales(&self) -> impl Iterator<Item = &Sale> {
+        self.positions.iter().filter_map(|p| p.sale.as_ref())
+    }
+}
+
+pub fn calculate_and_save(conf: HarvestConf) -> Result<Harvest, PortfolioError> {
+    let trades = holdings::trades::load_resolved(conf.trade_orders_csv, conf.instruments)?;
+    let prices = conf.market_data.latest_closes(conf.instruments)?;
+    let calendar = TradingCalendar::gpw();
+    let year = conf.year.unwrap_or_else(|| tax_year(&calendar, Utc::now()));
+
+    let harvest = harvest(
+        &trades,
+        &prices,
+        year,
+        conf.target_tax,
+        &conf.commission,
+        &calendar,
+    )?;
+
+    let mut report = Report::new(
+        &format!("Tax-loss harvesting {year}"),
+        positions_data_frame(&harvest)?,
+    )
+    .with_summary(summary_data_frame(&harvest)?);
+    util::report::save(&mut report, conf.output_file, conf.format)?;
+    Ok(harvest)
+}
+
+/// Sells of positions at a loss bringing the tax on the realized gains of `year`
+/// down to `target_tax`. A sell counts in the year it settles in (T+2), a sell of
+/// the last December sessions belongs to the next year.
+///
+/// Sales are matched to the oldest lots first, so a position bought at different
+/// prices may need more shares sold than its latest lots suggest. Positions with the
+/// largest attainable loss are used first; each sells the fewest shares covering the
+/// rest of the gain, or the quantity with the largest loss when it cannot cover it.
+pub fn harvest(
+    trades: &[TradeOrder],
+    prices: &BTreeMap<String, f64>,
+    year: i32,
+    target_tax: f64,
+    commission: &Commission,
+    calendar: &TradingCalendar,
+) -> Result<Harvest, PortfolioError> {
+    let lots = &Lots::from_trades(trades)?;
+    let realized_base = fifo_base(lots, year, calendar);
+    let average_cost_base = average_cost_base(trades, year, calendar)?;
+
+    let mut positions = Vec::new();
+    for instrument in lots.instruments() {
+        let price = prices.get(instrument).copied().ok_or_else(|| {
+            PortfolioError::InputError(format!("no market data price for held {instrument}"))
+        })?;
+        let quantity = lots.quantity(instrument);
+        positions.push(HeldPosition {
+            instrument: instrument.to_string(),
+            quantity,
+            price,
+            cost: lots.fifo_cost(instrument, quantity).unwrap_or_default(),
+            sale: None,
+        });
+    }
+
+    let mut candidates: Vec<(usize, f64)> = positions
+        .iter()
+        .enumerate()
+        .filter_map(|(i, p)| sale(lots, p, f64::INFINITY, commission).map(|(_, loss)| (i, loss)))
+        .collect();
+    candidates.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let target_base = target_tax.max(0_f64) / TAX_RATE;
+    let mut base = realized_base;
+    for (i, _) in candidates {
+        let needed = base - target_base;
+        if needed <= 0_f64 {
+            break;
+        }
+        let position = &mut positions[i];
+        let Some((quantity, _)) = sale(lots, position, needed, commission) else {
+            continue;
+        };
+        let value = position.price * quantity as f64;
+        let sale_commission = commission.amount(value);
+        let cost = lots
+            .fifo_cost(&position.instrument, quantity)
+            .unwrap_or_default();
+        let proceeds = value - sale_commission;
+        let base_after = base - (cost - proceeds);
+        position.sale = Some(Sale {
+            quantity,
+            proceeds,
+            commission: sale_commission,
+            cost,
+            tax_saved: round(tax(base) - tax(base_after), 2),
+        });
+        base = base_after;
+    }
+
+    Ok(Harvest {
+        year,
+        realized_base,
+        average_cost_base,
+        target_tax,
+        positions,
+    })
+}
+
+/// Tax year of a trade: the year of its T+2 settlement.
+pub fn tax_year(calendar: &TradingCalendar, traded: DateTime<Utc>) -> i32 {
+    calendar.settlement_date(traded.date_naive()).year()
+}
+
+/// Realized base of the sells settled in `year`, lots matched first in, first out.
+pub fn fifo_base(lots: &Lots, year: i32, calendar: &TradingCalendar) -> f64 {
+    let base: f64 = lots
+        .realized()
+        .iter()
+        .filter(|s| tax_year(calendar, s.sold) == year)
+        .map(|s| s.gain())
+        .sum();
+    // An empty float sum is -0.
+    base + 0_f64
+}
+
+/// Realized base of the sells settled in `year` with the average purchase price of the
+/// position, the method of the profit report.
+pub fn average_cost_base(
+    trades: &[TradeOrder],
+    year: i32,
+    calendar: &TradingCalendar,
+) -> Result<f64, PortfolioError> {
+    let mut holdings = Holdings::default();
+    let mut base = 0_f64;
+    for trade in trades {
+        if trade.order_side == OrderSide::Sell && tax_year(calendar, trade.submission_time) == year
+        {
+            let average_cost = holdings
+                .get(&trade.instrument)
+                .map_or(0_f64, |p| p.average_cost());
+            let quantity = trade.filled_quantity as f64;
+            base += trade.price.unwrap_or_default() * quantity
+                - trade.commission
+                - average_cost * quantity;
+        }
+        holdings.apply(trade)?;
+    }
+    Ok(base)
+}
+
+/// Fewest shares sold at a loss of at least `needed`, otherwise the quantity with
+/// the largest loss. `None` when no quantity sells at a loss.
+fn sale(
+    lots: &Lots,
+    position: &HeldPosition,
+    needed: f64,
+    commission: &Commission,
+) -> Option<(i64, f64)> {
+    let mut best: Option<(i64, f64)> = None;
+    let mut quantity = 0_i64;
+    let mut cost = 0_f64;
+    for lot in lots.open_lots(&position.instrument) {
+        let unit_cost = lot.cost(1);
+        for _ in 0..lot.quantity {
+            quantity += 1;
+            cost += unit_cost;
+            let value = position.price * quantity as f64;
+            let loss = cost - (value - commission.amount(value));
+            if loss >= needed {
+                return Some((quantity, loss));
+            }
+            if loss > best.map_or(0_f64, |(_, l)| l) {
+                best = Some((quantity, loss));
+            }
+        }
+    }
+    best
+}
+
+fn positions_data_frame(harvest: &Harvest) -> Result<DataFrame, PortfolioError> {
+    let positions = &harvest.positions;
+    let sale = |f: fn(&Sale) -> f64| {
+        positions
+            .iter()
+            .map(|p| round(p.sale.as_ref().map_or(0_f64, f), 2))
+            .collect::<Vec<_>>()
+    };
+    let df = df!(
+        "instrument" => positions.iter().map(|p| p.instrument.as_str()).collect::<Vec<_>>(),
+        "quantity" => positions.iter().map(|p| p.quantity).collect::<Vec<_>>(),
+        "price" => positions.iter().map(|p| p.price).collect::<Vec<_>>(),
+        "value" => positions.iter().map(|p| round(p.value(), 2)).collect::<Vec<_>>(),
+        "fifo_cost" => positions.iter().map(|p| round(p.cost, 2)).collect::<Vec<_>>(),
+        "unrealized_gain" => positions.iter().map(|p| round(p.value() - p.cost, 2)).collect::<Vec<_>>(),
+        "sell_quantity" => positions.iter().map(|p| p.sale.as_ref().map_or(0, |s| s.quantity)).collect::<Vec<_>>(),
+        "proceeds" => sale(|s| s.proceeds),
+        "commission" => sale(|s| s.commission),
+        "realized_loss" => sale(Sale::loss),
+        "tax_saved" => sale(|s| s.tax_saved),
+        "quantity_left" => positions.iter().map(HeldPosition::quantity_left).collect::<Vec<_>>(),
+        "value_left" => positions.iter().map(|p| round(p.quantity_left() as f64 * p.price, 2)).collect::<Vec<_>>(),
+    )?;
+    Ok(df)
+}
+
+fn summary_data_frame(harvest: &Harvest) -> Result<DataFrame, PortfolioError> {
+    let df = df!(
+        "year" => [harvest.year],
+        "realized_base" => [round(harvest.realized_base, 2)],
+        "average_cost_base" => [round(harvest.average_cost_base, 2)],
+        "realized_tax" => [harvest.realized_tax()],
+        "target_tax" => [harvest.target_tax],
+        "sells" => [harvest.sales().count() as u64],
+        "commissions" => [round(harvest.sales().map(|s| s.commission).sum(), 2)],
+        "base_after" => [round(harvest.base_after(), 2)],
+        "tax_after" => [harvest.tax_after()],
+        "tax_saved" => [round(harvest.realized_tax() - harvest.tax_after(), 2)],
+    )?;
+    Ok(df)
+}
+
+/// Tax of a realized base, 0 for a loss.
+pub(crate) fn tax(base: f64) -> f64 {
+    util::money::tax(base.max(0_f64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn harvest_gain_in_year_return_fifo_sells_down_to_target() {
+        let trades = [
+            TradeOrder::filled("PKOBP", OrderSide::Buy, 100, 40.0, "2024-05-03"),
+            TradeOrder::filled("PKOBP", OrderSide::Sell, 100, 60.0, "2025-03-03"),
+            // The older lot is at a gain, only the newer one sells at a loss.
+            TradeOrder::filled("ORANGEPL", OrderSide::Buy, 100, 5.0, "2024-06-03"),
+            TradeOrder::filled("ORANGEPL", OrderSide::Buy, 500, 12.0, "2025-04-03"),
+        ];
+        let prices = BTreeMap::from([("ORANGEPL".to_string(), 8.0)]);
+
+        let harvest = _harvest(&trades, &prices, 2025, 0.0);
+
+        assert_eq!(harvest.realized_base, 2000.0);
+        assert_eq!(harvest.average_cost_base, 2000.0);
+        let sale = harvest.positions[0].sale.as_ref().unwrap();
+        // The first 100 shares gain 3, the rest lose 4: offsetting 2000 needs 675 of 600 held.
+        assert_eq!(sale.quantity, 600);
+        assert_eq!(sale.loss(), 1700.0);
+        assert_eq!(sale.tax_saved, 323.0);
+        assert_eq!(harvest.tax_after(), 57.0);
+
+        let harvest = _harvest(&trades, &prices, 2025, 190.0);
+        // 1000 to offset: 100 + (1000 + 300) / 4 = 425 shares.
+        assert_eq!(harvest.positions[0].sale.as_ref().unwrap().quantity, 425);
+        assert_eq!(harvest.tax_after(), 190.0);
+    }
+
+    #[test]
+    fn harvest_sell_settled_in_january_return_counted_in_next_year() {
+        let trades = [
+            TradeOrder::filled("PKOBP", OrderSide::Buy, 100, 40.0, "2024-05-03"),
+            TradeOrder::filled("PKOBP", OrderSide::Sell, 50, 50.0, "2024-12-20"),
+            // Settles on 2025-01-03, after the closed 31 December and 1 January.
+            TradeOrder::filled("PKOBP", OrderSide::Sell, 50, 60.0, "2024-12-30"),
+        ];
+        let prices = BTreeMap::new();
+
+        let harvest_2024 = _harvest(&trades, &prices, 2024, 0.0);
+        let harvest_2025 = _harvest(&trades, &prices, 2025, 0.0);
+
+        assert_eq!(harvest_2024.realized_base, 500.0);
+        assert_eq!(harvest_2025.realized_base, 1000.0);
+        assert_eq!(harvest_2025.average_cost_base, 1000.0);
+    }
+
+    fn _harvest(
+        trades: &[TradeOrder],
+        prices: &BTreeMap<String, f64>,
+        year: i32,
+        target_tax: f64,
+    ) -> Harvest {
+        let commission = Commission {
+            percent: 0.0,
+            min: 0.0,
+        };
+        let calendar = TradingCalendar::gpw();
+        harvest(trades, prices, year, target_tax, &commission, &calendar).unwrap()
+    }
+}
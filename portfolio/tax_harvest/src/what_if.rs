@@ -0,0 +1,254 @@
+use chrono::{DateTime, Utc};
+use holdings::lots::Lots;
+use holdings::positions::Holdings;
+use market_store::source::MarketDataSource;
+use shared_contracts::errors::PortfolioError;
+use shared_contracts::models::instrument::InstrumentMaster;
+use shared_contracts::models::trade_order::{
+    InstrumentType, OrderSide, OrderStatus, OrderType, TradeOrder,
+};
+
+use trading_calendar::calendar::TradingCalendar;
+use util::math::round;
+use util::money::Commission;
+
+use crate::harvest::{average_cost_base, fifo_base, tax, tax_year};
+
+/// How the cost of sold shares is matched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CostMethod {
+    /// Oldest lots first, the Polish tax rules.
+    #[default]
+    Fifo,
+    /// Average purchase price of the position, as in the profit report.
+    AverageCost,
+}
+
+pub struct WhatIfConf<'a> {
+    /// Trade orders of the broker export, instrument names resolved through `instruments`.
+    pub trade_orders: Vec<TradeOrder>,
+    /// Latest close used when `price` is `None`.
+    pub market_data: Option<MarketDataSource<'a>>,
+    /// Resolves trade and market data names to one symbol.
+    pub instruments: &'a InstrumentMaster,
+    pub instrument: &'a str,
+    pub quantity: i64,
+    pub price: Option<f64>,
+    pub method: CostMethod,
+    pub commission: Commission,
+}
+
+/// Effect of a sell on the position and on the tax of the year it settles in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SellImpact {
+    pub instrument: String,
+    pub quantity: i64,
+    pub price: f64,
+    pub commission: f64,
+    /// Sale value less the commission.
+    pub proceeds: f64,
+    /// Cost of the sold shares with their buy commissions.
+    pub cost: f64,
+    /// Tax year of the sell, the year of its T+2 settlement.
+    pub year: i32,
+    /// Realized base of the year before the sell.
+    pub year_base: f64,
+    pub quantity_left: i64,
+    /// Average cost of the shares left, buy commissions included.
+    pub average_cost_left: f64,
+}
+
+impl SellImpact {
+    pub fn realized_gain(&self) -> f64 {
+        self.proceeds - self.cost
+    }
+
+    /// Tax the sell adds to the year, less than 19% of its gain when the year is at a loss.
+    pub fn tax_due(&self) -> f64 {
+        round(self.year_tax_after() - tax(self.year_base), 2)
+    }
+
+    pub fn year_tax_after(&self) -> f64 {
+        tax(self.year_base + self.realized_gain())
+    }
+}
+
+/// Simulates the sell on the executed trade orders, nothing is written.
+pub fn what_if(conf: WhatIfConf) -> Result<SellImpact, PortfolioError> {
+    let trades = holdings::trades::filled(conf.trade_orders);
+    let instrument = conf.instruments.symbol(conf.instrument);
+
+    let price = match (conf.price, conf.market_data) {
+        (Some(price), _) => price,
+        (None, Some(market_data)) => market_data
+            .latest_closes(conf.instruments)?
+            .get(instrument)
+            .copied()
+            .ok_or_else(|| {
+                PortfolioError::InputError(format!("no market data price for {instrument}"))
+            })?,
+        (None, None) => {
+            return Err(PortfolioError::InputError(
+                "a price or market data is required".into(),
+            ));
+        }
+    };
+
+    simulate_sell(
+        &trades,
+        instrument,
+        conf.quantity,
+        price,
+        Utc::now(),
+        conf.method,
+        &conf.commission,
+    )
+}
+
+/// Applies a sell of `quantity` shares at `price` on `sold` to the executed `trades`.
+/// The year base counts the sells settled in the year the sell settles in.
+pub fn simulate_sell(
+    trades: &[TradeOrder],
+    instrument: &str,
+    quantity: i64,
+    price: f64,
+    sold: DateTime<Utc>,
+    method: CostMethod,
+    commission: &Commission,
+) -> Result<SellImpact, PortfolioError> {
+    if quantity <= 0 {
+        return Err(PortfolioError::InvalidValue(format!(
+            "sell quantity {quantity} of {instrument}"
+        )));
+    }
+    let value = price * quantity as f64;
+    let sell_commission = commission.amount(value);
+    let order = TradeOrder {
+        instrument: instrument.to_string(),
+        instrument_type: InstrumentType::Stock,
+        order_type: OrderType::Limit,
+        order_side: OrderSide::Sell,
+        quantity,
+        filled_quantity: quantity,
+        price: Some(price),
+        commission: sell_commission,
+        status: OrderStatus::Filled,
+        submission_time: sold,
+        currency: "PLN".to_string(),
+        exchange: "GPW".to_string(),
+    };
+    let calendar = TradingCalendar::gpw();
+    let year = tax_year(&calendar, sold);
+
+    let (year_base, cost, quantity_left, average_cost_left) = match method {
+        CostMethod::Fifo => {
+            let mut lots = Lots::from_trades(trades)?;
+            let year_base = fifo_base(&lots, year, &calendar);
+            lots.apply(&order)?;
+            let cost = lots.realized().last().map_or(0_f64, |s| s.cost);
+            let left = lots.quantity(instrument);
+            let cost_left = lots.fifo_cost(instrument, left).unwrap_or_default();
+            let average_cost_left = if left > 0 {
+                cost_left / left as f64
+            } else {
+                0_f64
+            };
+            (year_base, cost, left, average_cost_left)
+        }
+        CostMethod::AverageCost => {
+            let year_base = average_cost_base(trades, year, &calendar)?;
+            let mut holdings = Holdings::from_trades(trades)?;
+            let average_cost = holdings.get(instrument).map_or(0_f64, |p| p.average_cost());
+            holdings.apply(&order)?;
+            let position = holdings.get(instrument);
+            (
+                year_base,
+                average_cost * quantity as f64,
+                position.map_or(0, |p| p.quantity),
+                position.map_or(0_f64, |p| p.average_cost()),
+            )
+        }
+    };
+
+    Ok(SellImpact {
+        instrument: instrument.to_string(),
+        quantity,
+        price,
+        commission: sell_commission,
+        proceeds: value - sell_commission,
+        cost,
+        year,
+        year_base,
+        quantity_left,
+        average_cost_left,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn simulate_sell_fifo_and_average_cost_return_gain_tax_and_position_left() {
+        let trades = [
+            TradeOrder::filled("PKOBP", OrderSide::Buy, 10, 40.0, "2025-01-03"),
+            TradeOrder::filled("PKOBP", OrderSide::Sell, 10, 30.0, "2025-02-03"),
+            TradeOrder::filled("ORANGEPL", OrderSide::Buy, 100, 5.0, "2025-03-03"),
+            TradeOrder::filled("ORANGEPL", OrderSide::Buy, 100, 7.0, "2025-04-03"),
+        ];
+        let commission = Commission {
+            percent: 0.0,
+            min: 0.0,
+        };
+        let sold = Utc.with_ymd_and_hms(2025, 6, 2, 9, 0, 0).unwrap();
+
+        let fifo = simulate_sell(
+            &trades,
+            "ORANGEPL",
+            150,
+            8.0,
+            sold,
+            CostMethod::Fifo,
+            &commission,
+        )
+        .unwrap();
+        let average = simulate_sell(
+            &trades,
+            "ORANGEPL",
+            150,
+            8.0,
+            sold,
+            CostMethod::AverageCost,
+            &commission,
+        )
+        .unwrap();
+
+        // 100 x 5 + 50 x 7 against 150 x 6, after the 100 PKOBP loss of the year.
+        assert_eq!(fifo.year_base, -100.0);
+        assert_eq!(fifo.realized_gain(), 350.0);
+        assert_eq!(fifo.tax_due(), 47.5);
+        assert_eq!((fifo.quantity_left, fifo.average_cost_left), (50, 7.0));
+        assert_eq!(average.realized_gain(), 300.0);
+        assert_eq!(average.tax_due(), 38.0);
+        assert_eq!(
+            (average.quantity_left, average.average_cost_left),
+            (50, 6.0)
+        );
+
+        // Settles in January, the PKOBP loss stays in the year before.
+        let december = Utc.with_ymd_and_hms(2025, 12, 30, 9, 0, 0).unwrap();
+        let next_year = simulate_sell(
+            &trades,
+            "ORANGEPL",
+            150,
+            8.0,
+            december,
+            CostMethod::Fifo,
+            &commission,
+        )
+        .unwrap();
+        assert_eq!((next_year.year, next_year.year_base), (2026, 0.0));
+    }
+}
@@ -0,0 +1,325 @@
+use std::path::Path;
+
+use chrono::{NaiveDate, TimeZone, Utc};
+use holdings::positions::Holdings;
+use shared_contracts::errors::PortfolioError;
+use shared_contracts::models::trade_order::{
+    InstrumentType, OrderSide, OrderStatus, OrderType, TradeOrder,
+};
+use util::math::round;
+use util::money::Commission;
+
+use crate::market::{Bar, MarketView, PriceHistory};
+
+/// Order a strategy places after the close, a day order for the next session.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderRequest {
+    pub instrument: String,
+    pub side: OrderSide,
+    pub quantity: i64,
+    /// `None` for a market order.
+    pub limit: Option<f64>,
+}
+
+impl OrderRequest {
+    pub fn market(instrument: &str, side: OrderSide, quantity: i64) -> Self {
+        OrderRequest {
+            instrument: instrument.to_string(),
+            side,
+            quantity,
+            limit: None,
+        }
+    }
+
+    pub fn limit(instrument: &str, side: OrderSide, quantity: i64, limit: f64) -> Self {
+        OrderRequest {
+            limit: Some(limit),
+            ..OrderRequest::market(instrument, side, quantity)
+        }
+    }
+}
+
+/// Cash and positions of the simulated account.
+#[derive(Debug, Clone, Default)]
+pub struct Account {
+    pub cash: f64,
+    pub holdings: Holdings,
+}
+
+impl Account {
+    pub fn quantity(&self, instrument: &str) -> i64 {
+        self.holdings.get(instrument).map_or(0, |p| p.quantity)
+    }
+}
+
+/// Trading rule run once per session.
+pub trait Strategy {
+    /// Orders for the next session, given the market up to the close of `market.date`.
+    fn on_session(&mut self, market: &MarketView, account: &Account) -> Vec<OrderRequest>;
+}
+
+/// Executes orders on the daily OHLC bar of the session.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FillModel {
+    pub commission: Commission,
+}
+
+impl FillModel {
+    /// Market orders fill at the open. A limit buy fills when the low reaches the limit,
+    /// at the open when the session opens below it; a limit sell mirrors it on the high.
+    pub fn fill_price(&self, request: &OrderRequest, bar: &Bar) -> Option<f64> {
+        match (request.limit, &request.side) {
+            (None, _) => Some(bar.open),
+            (Some(limit), OrderSide::Buy) => (bar.low <= limit).then(|| bar.open.min(limit)),
+            (Some(limit), OrderSide::Sell) => (bar.high >= limit).then(|| bar.open.max(limit)),
+        }
+    }
+}
+
+pub struct BacktestConf {
+    pub cash: f64,
+    /// First and last session, the whole history when `None`.
+    pub start: Option<NaiveDate>,
+    pub end: Option<NaiveDate>,
+    pub fill: FillModel,
+}
+
+pub struct Backtest {
+    /// Every order placed, in the normalized trade order format.
+    pub orders: Vec<TradeOrder>,
+    pub account: Account,
+    pub last_session: Option<NaiveDate>,
+    /// Cash with the positions valued at the last close.
+    pub final_value: f64,
+}
+
+/// Runs the strategy over the sessions of the history.
+///
+/// Orders placed after a session are executed on the next one and dated on it. An order
+/// not filled, or for an instrument without a session, expires. A buy larger than the cash
+/// is cut to the affordable quantity, a sell larger than the position is rejected.
+/// Orders placed after the last session stay pending.
+pub fn run(
+    strategy: &mut dyn Strategy,
+    history: &PriceHistory,
+    conf: &BacktestConf,
+) -> Result<Backtest, PortfolioError> {
+    let sessions = history.sessions(conf.start, conf.end);
+    let mut account = Account {
+        cash: conf.cash,
+        ..Default::default()
+    };
+    let mut orders = Vec::new();
+    let mut requests: Vec<OrderRequest> = Vec::new();
+
+    for date in sessions.iter().copied() {
+        for request in requests.drain(..) {
+            let order = execute(&request, date, history, &conf.fill, &mut account)?;
+            orders.push(order);
+        }
+        requests = strategy.on_session(&MarketView::new(date, history), &account);
+    }
+
+    let last_session = sessions.last().copied();
+    if let Some(date) = last_session {
+        for request in requests {
+            orders.push(TradeOrder {
+                status: OrderStatus::Pending,
+                ..trade_order(&request, date)
+            });
+        }
+    }
+
+    let final_value = account.cash
+        + account
+            .holdings
+            .open_positions()
+            .map(|p| {
+                let close = last_session
+                    .and_then(|date| history.close(&p.instrument, date))
+                    .unwrap_or_default();
+                p.quantity as f64 * close
+            })
+            .fold(0_f64, |value, position| value + position);
+
+    Ok(Backtest {
+        orders,
+        account,
+        last_session,
+        final_value: round(final_value, 2),
+    })
+}
+
+/// Writes the orders as a normalized trade orders csv the reports read.
+pub fn save_orders(orders: &[TradeOrder], orders_csv: &Path) -> Result<(), PortfolioError> {
+    let mut wtr = util::csv::default_writer(orders_csv)?;
+    for order in orders {
+        wtr.serialize(order)?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+fn execute(
+    request: &OrderRequest,
+    date: NaiveDate,
+    history: &PriceHistory,
+    fill: &FillModel,
+    account: &mut Account,
+) -> Result<TradeOrder, PortfolioError> {
+    let mut order = trade_order(request, date);
+    if request.quantity <= 0 {
+        order.status = OrderStatus::Rejected;
+        return Ok(order);
+    }
+    let Some(price) = history
+        .bar(&request.instrument, date)
+        .and_then(|bar| fill.fill_price(request, bar))
+    else {
+        order.status = OrderStatus::Expired;
+        return Ok(order);
+    };
+
+    let quantity = match request.side {
+        OrderSide::Buy => affordable(price, request.quantity, account.cash, fill),
+        OrderSide::Sell if request.quantity > account.quantity(&request.instrument) => 0,
+        OrderSide::Sell => request.quantity,
+    };
+    if quantity == 0 {
+        order.status = OrderStatus::Rejected;
+        return Ok(order);
+    }
+
+    let value = price * quantity as f64;
+    order.price = Some(price);
+    order.filled_quantity = quantity;
+    order.commission = fill.commission.amount(value);
+    order.status = if quantity < request.quantity {
+        OrderStatus::PartiallyFilled
+    } else {
+        OrderStatus::Filled
+    };
+    account.holdings.apply(&order)?;
+    account.cash += match request.side {
+        OrderSide::Buy => -value - order.commission,
+        OrderSide::Sell => value - order.commission,
+    };
+    Ok(order)
+}
+
+/// Largest part of `quantity` whose value with the commission fits in the cash.
+fn affordable(price: f64, quantity: i64, cash: f64, fill: &FillModel) -> i64 {
+    let cost = |q: i64| price * q as f64 + fill.commission.amount(price * q as f64);
+    let mut affordable = quantity.min((cash / (price * (1_f64 + fill.commission.percent))) as i64);
+    while affordable > 0 && cost(affordable) > cash {
+        affordable -= 1;
+    }
+    affordable.max(0)
+}
+
+fn trade_order(request: &OrderRequest, date: NaiveDate) -> TradeOrder {
+    TradeOrder {
+        instrument: request.instrument.clone(),
+        instrument_type: InstrumentType::Stock,
+        order_type: match request.limit {
+            Some(_) => OrderType::Limit,
+            None => OrderType::Market,
+        },
+        order_side: request.side.clone(),
+        quantity: request.quantity,
+        filled_quantity: 0,
+        price: request.limit,
+        commission: 0_f64,
+        status: OrderStatus::Pending,
+        submission_time: Utc.from_utc_datetime(&date.and_hms_opt(9, 0, 0).unwrap_or_default()),
+        currency: "PLN".to_string(),
+        exchange: "GPW".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    #[test]
+    fn run_fills_orders_on_next_session_bar_and_tracks_cash() {
+        let history = PriceHistory::new(BTreeMap::from([(
+            "PKOBP".to_string(),
+            BTreeMap::from([
+                (_date(1), _bar(50.0, 51.0, 49.0, 50.0)),
+                (_date(2), _bar(49.0, 50.0, 47.0, 48.0)),
+                (_date(3), _bar(50.0, 56.0, 50.0, 55.0)),
+                (_date(6), _bar(55.0, 58.0, 54.0, 57.0)),
+            ]),
+        )]));
+        let conf = BacktestConf {
+            cash: 1000.0,
+            start: None,
+            end: None,
+            fill: FillModel {
+                commission: Commission {
+                    percent: 0.0,
+                    min: 1.0,
+                },
+            },
+        };
+        let mut strategy = _Script(vec![
+            vec![
+                OrderRequest::market("PKOBP", OrderSide::Sell, 5),
+                OrderRequest::limit("PKOBP", OrderSide::Buy, 30, 48.5),
+            ],
+            vec![OrderRequest::limit("PKOBP", OrderSide::Buy, 10, 45.0)],
+            vec![OrderRequest::limit("PKOBP", OrderSide::Sell, 20, 57.5)],
+            vec![OrderRequest::market("PKOBP", OrderSide::Sell, 20)],
+        ]);
+
+        let backtest = run(&mut strategy, &history, &conf).unwrap();
+
+        let statuses: Vec<(OrderStatus, i64, Option<f64>)> = backtest
+            .orders
+            .iter()
+            .map(|o| (o.status.clone(), o.filled_quantity, o.price))
+            .collect();
+        // The sell of shares not held yet is rejected, 20 x 48.5 + 1 fits in 1000.
+        assert_eq!(
+            statuses,
+            vec![
+                (OrderStatus::Rejected, 0, None),
+                (OrderStatus::PartiallyFilled, 20, Some(48.5)),
+                (OrderStatus::Expired, 0, Some(45.0)),
+                (OrderStatus::Filled, 20, Some(57.5)),
+                (OrderStatus::Pending, 0, None),
+            ]
+        );
+        assert_eq!(backtest.orders[0].submission_time.date_naive(), _date(2));
+        assert_eq!(backtest.account.cash, 1178.0);
+        assert_eq!(backtest.final_value, 1178.0);
+    }
+
+    struct _Script(Vec<Vec<OrderRequest>>);
+
+    impl Strategy for _Script {
+        fn on_session(&mut self, _: &MarketView, _: &Account) -> Vec<OrderRequest> {
+            if self.0.is_empty() {
+                Vec::new()
+            } else {
+                self.0.remove(0)
+            }
+        }
+    }
+
+    fn _bar(open: f64, high: f64, low: f64, close: f64) -> Bar {
+        Bar {
+            open,
+            high,
+            low,
+            close,
+        }
+    }
+
+    fn _date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 1, day).unwrap()
+    }
+}
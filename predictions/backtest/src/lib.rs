@@ -0,0 +1,3 @@
+pub mod engine;
+pub mod market;
+pub mod strategies;
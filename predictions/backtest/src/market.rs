@@ -0,0 +1,149 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use chrono::NaiveDate;
+use market_store::source::MarketDataSource;
+use polars::prelude::*;
+use shared_contracts::errors::PortfolioError;
+use shared_contracts::models::instrument::InstrumentMaster;
+
+/// Daily OHLC of one instrument.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bar {
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+}
+
+/// Sessions with trades of every instrument of the market data.
+#[derive(Debug, Clone, Default)]
+pub struct PriceHistory {
+    bars: BTreeMap<String, BTreeMap<NaiveDate, Bar>>,
+}
+
+impl PriceHistory {
+    /// Instrument names are resolved to symbols, sessions without trades (zero prices) skipped.
+    pub fn load(
+        market_data: &MarketDataSource,
+        instruments: &InstrumentMaster,
+    ) -> Result<Self, PortfolioError> {
+        let df = util::polars::resolve_instruments(market_data.history()?, instruments)
+            .filter(
+                col("opening_price")
+                    .gt(lit(0))
+                    .and(col("max_price").gt(lit(0)))
+                    .and(col("min_price").gt(lit(0)))
+                    .and(col("closing_price").gt(lit(0))),
+            )
+            .select([
+                col("instrument"),
+                col("date"),
+                col("opening_price"),
+                col("max_price"),
+                col("min_price"),
+                col("closing_price"),
+            ])
+            .collect()?;
+
+        let names = df.column("instrument")?.str()?;
+        let dates: Vec<Option<NaiveDate>> = df.column("date")?.date()?.as_date_iter().collect();
+        let opens = df.column("opening_price")?.f64()?;
+        let highs = df.column("max_price")?.f64()?;
+        let lows = df.column("min_price")?.f64()?;
+        let closes = df.column("closing_price")?.f64()?;
+
+        let mut bars: BTreeMap<String, BTreeMap<NaiveDate, Bar>> = BTreeMap::new();
+        for (i, date) in dates.into_iter().enumerate() {
+            let (Some(name), Some(date), Some(open), Some(high), Some(low), Some(close)) = (
+                names.get(i),
+                date,
+                opens.get(i),
+                highs.get(i),
+                lows.get(i),
+                closes.get(i),
+            ) else {
+                continue;
+            };
+            bars.entry(name.to_string()).or_default().insert(
+                date,
+                Bar {
+                    open,
+                    high,
+                    low,
+                    close,
+                },
+            );
+        }
+        Ok(PriceHistory { bars })
+    }
+
+    pub fn new(bars: BTreeMap<String, BTreeMap<NaiveDate, Bar>>) -> Self {
+        PriceHistory { bars }
+    }
+
+    /// Dates with a session of any instrument, within the optional bounds.
+    pub fn sessions(&self, start: Option<NaiveDate>, end: Option<NaiveDate>) -> Vec<NaiveDate> {
+        let dates: BTreeSet<NaiveDate> = self
+            .bars
+            .values()
+            .flat_map(|bars| bars.keys().copied())
+            .filter(|date| start.is_none_or(|start| *date >= start))
+            .filter(|date| end.is_none_or(|end| *date <= end))
+            .collect();
+        dates.into_iter().collect()
+    }
+
+    pub fn bar(&self, instrument: &str, date: NaiveDate) -> Option<&Bar> {
+        self.bars.get(instrument).and_then(|bars| bars.get(&date))
+    }
+
+    /// Latest close on or before `date`.
+    pub fn close(&self, instrument: &str, date: NaiveDate) -> Option<f64> {
+        self.bars
+            .get(instrument)
+            .and_then(|bars| bars.range(..=date).next_back())
+            .map(|(_, bar)| bar.close)
+    }
+}
+
+/// Market data a strategy sees after the close of `date`: nothing later.
+pub struct MarketView<'a> {
+    pub date: NaiveDate,
+    history: &'a PriceHistory,
+}
+
+impl<'a> MarketView<'a> {
+    pub fn new(date: NaiveDate, history: &'a PriceHistory) -> Self {
+        MarketView { date, history }
+    }
+
+    /// Bar of the session, `None` when the instrument did not trade.
+    pub fn bar(&self, instrument: &str) -> Option<&Bar> {
+        self.history.bar(instrument, self.date)
+    }
+
+    /// Instruments traded in the session, ordered by symbol.
+    pub fn instruments(&self) -> impl Iterator<Item = &str> {
+        self.history
+            .bars
+            .iter()
+            .filter(|(_, bars)| bars.contains_key(&self.date))
+            .map(|(instrument, _)| instrument.as_str())
+    }
+
+    /// Closes of the last `sessions` sessions of the instrument up to the view date,
+    /// oldest first, fewer when the history is shorter.
+    pub fn closes(&self, instrument: &str, sessions: usize) -> Vec<f64> {
+        let Some(bars) = self.history.bars.get(instrument) else {
+            return Vec::new();
+        };
+        let mut closes: Vec<f64> = bars
+            .range(..=self.date)
+            .rev()
+            .take(sessions)
+            .map(|(_, bar)| bar.close)
+            .collect();
+        closes.reverse();
+        closes
+    }
+}
@@ -0,0 +1,216 @@
+use std::collections::BTreeMap;
+
+use chrono::{Months, NaiveDate};
+use recommendations::import::UpsideRecord;
+use recommendations::outcome::DEFAULT_HORIZON_MONTHS;
+use shared_contracts::models::instrument::InstrumentMaster;
+use shared_contracts::models::trade_order::OrderSide;
+
+use crate::engine::{Account, OrderRequest, Strategy};
+use crate::market::MarketView;
+
+/// Buys when the fast simple moving average of the close crosses above the slow one,
+/// sells the whole position when it crosses back below.
+pub struct SmaCross {
+    pub universe: Vec<String>,
+    pub fast: usize,
+    pub slow: usize,
+    /// Value of one buy, less when the cash left is smaller.
+    pub position_value: f64,
+}
+
+impl Strategy for SmaCross {
+    fn on_session(&mut self, market: &MarketView, account: &Account) -> Vec<OrderRequest> {
+        let mut cash = account.cash;
+        let mut requests = Vec::new();
+        for instrument in &self.universe {
+            if market.bar(instrument).is_none() {
+                continue;
+            }
+            let closes = market.closes(instrument, self.slow + 1);
+            if closes.len() < self.slow + 1 {
+                continue;
+            }
+            let (previous, today) = (&closes[..self.slow], &closes[1..]);
+            let was_above = sma(previous, self.fast) > sma(previous, self.slow);
+            let is_above = sma(today, self.fast) > sma(today, self.slow);
+            let held = account.quantity(instrument);
+
+            if is_above && !was_above && held == 0 {
+                let close = closes[closes.len() - 1];
+                let quantity = (self.position_value.min(cash) / close) as i64;
+                if quantity > 0 {
+                    cash -= close * quantity as f64;
+                    requests.push(OrderRequest::market(instrument, OrderSide::Buy, quantity));
+                }
+            } else if was_above && !is_above && held > 0 {
+                requests.push(OrderRequest::market(instrument, OrderSide::Sell, held));
+            }
+        }
+        requests
+    }
+}
+
+/// Average of the last `sessions` closes.
+fn sma(closes: &[f64], sessions: usize) -> f64 {
+    let window = &closes[closes.len().saturating_sub(sessions)..];
+    window.iter().fold(0_f64, |sum, close| sum + close) / window.len() as f64
+}
+
+/// Buys when the close is at least `discount_pct` below the analyst target price,
+/// then sells with a limit order at the target.
+///
+/// The latest recommendation published on or before the session is used, until its horizon ends.
+pub struct TargetDiscount {
+    targets: BTreeMap<String, Vec<UpsideRecord>>,
+    pub discount_pct: f64,
+    /// Value of one buy, less when the cash left is smaller.
+    pub position_value: f64,
+}
+
+impl TargetDiscount {
+    /// Recommendation instruments are resolved to symbols.
+    pub fn new(
+        recommendations: Vec<UpsideRecord>,
+        instruments: &InstrumentMaster,
+        discount_pct: f64,
+        position_value: f64,
+    ) -> Self {
+        let mut targets: BTreeMap<String, Vec<UpsideRecord>> = BTreeMap::new();
+        for record in recommendations {
+            targets
+                .entry(instruments.symbol(&record.instrument).to_string())
+                .or_default()
+                .push(record);
+        }
+        for records in targets.values_mut() {
+            records.sort_by_key(|r| r.created_at);
+        }
+        TargetDiscount {
+            targets,
+            discount_pct,
+            position_value,
+        }
+    }
+
+    /// Target price valid on `date`.
+    fn target(&self, instrument: &str, date: NaiveDate) -> Option<f64> {
+        let record = self
+            .targets
+            .get(instrument)?
+            .iter()
+            .rev()
+            .find(|r| r.created_at <= date)?;
+        let months = Months::new(record.horizon_months.unwrap_or(DEFAULT_HORIZON_MONTHS));
+        let ends = record.created_at.checked_add_months(months)?;
+        (date <= ends).then_some(record.upside)
+    }
+}
+
+impl Strategy for TargetDiscount {
+    fn on_session(&mut self, market: &MarketView, account: &Account) -> Vec<OrderRequest> {
+        let mut cash = account.cash;
+        let mut requests = Vec::new();
+        for instrument in self.targets.keys() {
+            let (Some(bar), Some(target)) =
+                (market.bar(instrument), self.target(instrument, market.date))
+            else {
+                continue;
+            };
+            let held = account.quantity(instrument);
+            if held > 0 {
+                requests.push(OrderRequest::limit(
+                    instrument,
+                    OrderSide::Sell,
+                    held,
+                    target,
+                ));
+            } else if bar.close <= target * (1_f64 - self.discount_pct / 100_f64) {
+                let quantity = (self.position_value.min(cash) / bar.close) as i64;
+                if quantity > 0 {
+                    cash -= bar.close * quantity as f64;
+                    requests.push(OrderRequest::market(instrument, OrderSide::Buy, quantity));
+                }
+            }
+        }
+        requests
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use shared_contracts::models::trade_order::OrderStatus;
+    use util::money::Commission;
+
+    use crate::engine::{BacktestConf, FillModel};
+    use crate::market::{Bar, PriceHistory};
+
+    use super::*;
+
+    #[test]
+    fn sma_cross_buys_on_cross_up_and_sells_position_on_cross_down() {
+        let closes = [10.0, 10.0, 10.0, 13.0, 13.0, 7.0, 8.0];
+        let history = PriceHistory::new(BTreeMap::from([(
+            "CDR".to_string(),
+            closes
+                .iter()
+                .enumerate()
+                .map(|(day, close)| {
+                    (
+                        NaiveDate::from_ymd_opt(2025, 3, day as u32 + 3).unwrap(),
+                        Bar {
+                            open: *close,
+                            high: *close,
+                            low: *close,
+                            close: *close,
+                        },
+                    )
+                })
+                .collect(),
+        )]));
+        let mut strategy = SmaCross {
+            universe: vec!["CDR".to_string()],
+            fast: 1,
+            slow: 3,
+            position_value: 1000.0,
+        };
+        let conf = BacktestConf {
+            cash: 500.0,
+            start: None,
+            end: None,
+            fill: FillModel {
+                commission: Commission {
+                    percent: 0.0,
+                    min: 0.0,
+                },
+            },
+        };
+
+        let backtest = crate::engine::run(&mut strategy, &history, &conf).unwrap();
+
+        let orders: Vec<(OrderSide, OrderStatus, i64, Option<f64>, u32)> = backtest
+            .orders
+            .iter()
+            .map(|o| {
+                (
+                    o.order_side.clone(),
+                    o.status.clone(),
+                    o.filled_quantity,
+                    o.price,
+                    chrono::Datelike::day(&o.submission_time),
+                )
+            })
+            .collect();
+        // Cross up at the close of the 6th, 500 buys 38 at the next open; cross down on the 8th.
+        assert_eq!(
+            orders,
+            vec![
+                (OrderSide::Buy, OrderStatus::Filled, 38, Some(13.0), 7),
+                (OrderSide::Sell, OrderStatus::Filled, 38, Some(8.0), 9),
+            ]
+        );
+        assert_eq!(backtest.final_value, 310.0);
+    }
+}
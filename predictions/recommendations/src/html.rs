@@ -0,0 +1,126 @@
+/// Cell texts of every row of every `<table>` in an html page, tags stripped
+/// and entities decoded. Nested tables are read as rows of the outer one.
+pub(crate) fn table_rows(html: &str) -> Vec<Vec<String>> {
+    // ASCII lowercase keeps byte offsets, tags are searched in it and cut from `html`.
+    let lower = html.to_ascii_lowercase();
+    let mut rows = Vec::new();
+
+    let mut position = 0;
+    while let Some(start) = find_tag(&lower, "tr", position) {
+        let end = find_tag(&lower, "tr", start + 3)
+            .into_iter()
+            .chain(lower[start..].find("</table").map(|i| start + i))
+            .min()
+            .unwrap_or(lower.len());
+        let cells = cells(&html[start..end], &lower[start..end]);
+        if !cells.is_empty() {
+            rows.push(cells);
+        }
+        position = end;
+    }
+    rows
+}
+
+fn cells(row: &str, lower: &str) -> Vec<String> {
+    let mut cells = Vec::new();
+    let mut position = 0;
+    loop {
+        let start = match (
+            find_tag(lower, "td", position),
+            find_tag(lower, "th", position),
+        ) {
+            (Some(td), Some(th)) => td.min(th),
+            (Some(cell), None) | (None, Some(cell)) => cell,
+            (None, None) => break,
+        };
+        let Some(content_start) = lower[start..].find('>').map(|i| start + i + 1) else {
+            break;
+        };
+        let content_end = [
+            lower[content_start..].find("</td"),
+            lower[content_start..].find("</th"),
+            find_tag(&lower[content_start..], "td", 0),
+            find_tag(&lower[content_start..], "th", 0),
+        ]
+        .into_iter()
+        .flatten()
+        .min()
+        .map_or(lower.len(), |i| content_start + i);
+
+        cells.push(text(&row[content_start..content_end]));
+        position = content_end;
+    }
+    cells
+}
+
+/// Position of the next `<name>` or `<name ...>` opening tag.
+fn find_tag(lower: &str, name: &str, from: usize) -> Option<usize> {
+    let open = format!("<{name}");
+    let mut position = from;
+    while let Some(i) = lower[position..].find(&open) {
+        let start = position + i;
+        match lower[start + open.len()..].chars().next() {
+            Some(c) if c == '>' || c.is_whitespace() => return Some(start),
+            _ => position = start + open.len(),
+        }
+    }
+    None
+}
+
+/// Text of an html fragment, whitespace collapsed.
+fn text(fragment: &str) -> String {
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in fragment.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => {
+                in_tag = false;
+                text.push(' ');
+            }
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    decode_entities(&text)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest.find(';').filter(|end| *end <= 10).and_then(|end| {
+            let c = match &rest[1..end] {
+                "nbsp" => Some(' '),
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                code => code
+                    .strip_prefix("#x")
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .or_else(|| code.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                    .and_then(char::from_u32),
+            };
+            c.map(|c| (c, end))
+        });
+        match entity {
+            Some((c, end)) => {
+                decoded.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
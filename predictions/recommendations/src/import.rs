@@ -0,0 +1,435 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDate;
+use encoding_rs::WINDOWS_1250;
+use serde::{Deserialize, Serialize};
+use shared_contracts::errors::PortfolioError;
+
+/// Rows searched for the header, exports may start with a title.
+const HEADER_SEARCH_ROWS: usize = 20;
+
+static DATE_FORMATS: &[&str] = &["%Y-%m-%d", "%d.%m.%Y", "%d-%m-%Y", "%d/%m/%Y", "%Y.%m.%d"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecommendationType {
+    Buy,
+    Accumulate,
+    Hold,
+    Reduce,
+    Sell,
+}
+
+impl RecommendationType {
+    /// Polish (kupuj, akumuluj, trzymaj, redukuj, sprzedaj) or English name, any case.
+    pub fn parse(value: &str) -> Option<RecommendationType> {
+        match normalize(value).as_str() {
+            "kupuj" | "buy" | "strong buy" => Some(RecommendationType::Buy),
+            "akumuluj" | "accumulate" | "overweight" | "przewazaj" => {
+                Some(RecommendationType::Accumulate)
+            }
+            "trzymaj" | "neutralnie" | "hold" | "neutral" => Some(RecommendationType::Hold),
+            "redukuj" | "reduce" | "underweight" | "niedowazaj" => Some(RecommendationType::Reduce),
+            "sprzedaj" | "sell" | "strong sell" => Some(RecommendationType::Sell),
+            _ => None,
+        }
+    }
+}
+
+/// One row of the extended upside csv, the upside columns followed by the imported ones.
+/// Upside csv files typed by hand have only the first four or five columns.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UpsideRecord {
+    pub instrument: String,
+    /// Target price.
+    pub upside: f64,
+    pub created_by: String,
+    pub created_at: NaiveDate,
+    #[serde(default)]
+    pub stop_loss: Option<f64>,
+    #[serde(default)]
+    pub recommendation: Option<RecommendationType>,
+    #[serde(default)]
+    pub horizon_months: Option<u32>,
+}
+
+impl UpsideRecord {
+    /// A recommendation is identified by the instrument, the broker and the day.
+    fn key(&self) -> (String, String, NaiveDate) {
+        (
+            normalize(&self.instrument),
+            normalize(&self.created_by),
+            self.created_at,
+        )
+    }
+}
+
+/// Export row that could not be imported.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rejected {
+    pub file: PathBuf,
+    /// Line of the csv, 1-based row of the html tables.
+    pub row: usize,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ImportSummary {
+    pub files: usize,
+    pub imported: usize,
+    /// Recommendations already in the upside csv or repeated in the exports.
+    pub duplicates: usize,
+    pub rejected: Vec<Rejected>,
+}
+
+/// Column positions of one export layout.
+#[derive(Debug, PartialEq)]
+struct Layout {
+    instrument: usize,
+    target: usize,
+    date: usize,
+    recommendation: Option<usize>,
+    broker: Option<usize>,
+    horizon: Option<usize>,
+}
+
+/// Appends the recommendations of broker research exports (csv or html tables) to
+/// `upside_csv`, skipping those imported before. `source` is the `created_by` of
+/// exports without a broker column.
+pub fn import(
+    exports: &[PathBuf],
+    source: Option<&str>,
+    upside_csv: &Path,
+) -> Result<ImportSummary, PortfolioError> {
+    let mut records = if upside_csv.exists() {
+        load(upside_csv)?
+    } else {
+        Vec::new()
+    };
+    let mut keys: HashSet<_> = records.iter().map(UpsideRecord::key).collect();
+
+    let mut summary = ImportSummary {
+        files: exports.len(),
+        ..Default::default()
+    };
+    let mut imported = Vec::new();
+    for export in exports {
+        for record in read_export(export, source, &mut summary.rejected)? {
+            if keys.insert(record.key()) {
+                imported.push(record);
+            } else {
+                summary.duplicates += 1;
+            }
+        }
+    }
+    imported.sort_by(|a, b| {
+        (a.created_at, &a.instrument, &a.created_by).cmp(&(
+            b.created_at,
+            &b.instrument,
+            &b.created_by,
+        ))
+    });
+    summary.imported = imported.len();
+    records.extend(imported);
+
+    let mut wtr = util::csv::default_writer(upside_csv)?;
+    for record in &records {
+        wtr.serialize(record)?;
+    }
+    wtr.flush()?;
+    Ok(summary)
+}
+
+pub fn load(upside_csv: &Path) -> Result<Vec<UpsideRecord>, PortfolioError> {
+    let mut rdr = util::csv::default_reader(upside_csv)?;
+    let mut records = Vec::new();
+    for result in rdr.deserialize() {
+        records.push(result?);
+    }
+    Ok(records)
+}
+
+fn read_export(
+    export: &Path,
+    source: Option<&str>,
+    rejected: &mut Vec<Rejected>,
+) -> Result<Vec<UpsideRecord>, PortfolioError> {
+    let bytes = fs::read(export)?;
+    let content = match String::from_utf8(bytes) {
+        Ok(content) => content,
+        Err(e) => WINDOWS_1250.decode(e.as_bytes()).0.into_owned(),
+    };
+    let is_html = export
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("html") || e.eq_ignore_ascii_case("htm"));
+    let rows = if is_html {
+        crate::html::table_rows(&content)
+            .into_iter()
+            .enumerate()
+            .map(|(idx, row)| (idx + 1, row))
+            .collect()
+    } else {
+        csv_rows(content.trim_start_matches('\u{feff}'))?
+    };
+
+    let (header_idx, columns) = rows
+        .iter()
+        .take(HEADER_SEARCH_ROWS)
+        .enumerate()
+        .find_map(|(idx, (_, row))| layout(row).map(|columns| (idx, columns)))
+        .ok_or_else(|| {
+            PortfolioError::InputError(format!(
+                "No recommendation table in {}, expected issuer, target price and date columns",
+                export.display()
+            ))
+        })?;
+    if columns.broker.is_none() && source.is_none() {
+        return Err(PortfolioError::InputError(format!(
+            "{} has no broker column, the source of its recommendations is required",
+            export.display()
+        )));
+    }
+
+    let mut records = Vec::new();
+    for (line, row) in rows.iter().skip(header_idx + 1) {
+        if row.iter().all(|cell| cell.trim().is_empty()) || layout(row).is_some() {
+            continue;
+        }
+        match record(row, &columns, source) {
+            Ok(record) => records.push(record),
+            Err(reason) => rejected.push(Rejected {
+                file: export.to_path_buf(),
+                row: *line,
+                reason,
+            }),
+        }
+    }
+    Ok(records)
+}
+
+/// Lines and rows of a `;`, tab or `,` separated export, whichever splits the most of the first lines.
+fn csv_rows(content: &str) -> Result<Vec<(usize, Vec<String>)>, PortfolioError> {
+    let sample: Vec<&str> = content.lines().take(HEADER_SEARCH_ROWS).collect();
+    let delimiter = [b';', b'\t', b',']
+        .into_iter()
+        .max_by_key(|d| {
+            sample
+                .iter()
+                .map(|line| line.matches(*d as char).count())
+                .sum::<usize>()
+        })
+        .unwrap_or(b';');
+
+    let mut rdr = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(false)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(content.as_bytes());
+    let mut rows = Vec::new();
+    for result in rdr.records() {
+        let record = result?;
+        let line = record.position().map_or(0, |p| p.line() as usize);
+        rows.push((line, record.iter().map(str::to_string).collect()));
+    }
+    Ok(rows)
+}
+
+fn layout(headers: &[String]) -> Option<Layout> {
+    let headers: Vec<String> = headers.iter().map(|h| normalize(h)).collect();
+    let find = |names: &[&str]| {
+        headers
+            .iter()
+            .position(|h| names.iter().any(|n| h.starts_with(n)))
+    };
+
+    Some(Layout {
+        instrument: find(&[
+            "spolka",
+            "emitent",
+            "walor",
+            "instrument",
+            "issuer",
+            "company",
+        ])?,
+        target: find(&["cena docelowa", "target"])?,
+        date: find(&["data wydania", "data publikacji", "data", "date"])?,
+        recommendation: find(&["rekomendacja", "zalecenie", "recommendation"]),
+        broker: find(&[
+            "instytucja",
+            "biuro",
+            "dom maklerski",
+            "wydajacy",
+            "autor",
+            "broker",
+        ]),
+        horizon: find(&["horyzont", "horizon"]),
+    })
+}
+
+fn record(row: &[String], layout: &Layout, source: Option<&str>) -> Result<UpsideRecord, String> {
+    let cell = |idx: usize| row.get(idx).map(|c| c.trim()).unwrap_or_default();
+    let optional = |idx: Option<usize>| idx.map(cell).filter(|c| !c.is_empty());
+
+    let instrument = cell(layout.instrument);
+    if instrument.is_empty() {
+        return Err("no issuer".to_string());
+    }
+    let upside = parse_price(cell(layout.target))
+        .ok_or_else(|| format!("target price {:?}", cell(layout.target)))?;
+    let created_at =
+        parse_date(cell(layout.date)).ok_or_else(|| format!("date {:?}", cell(layout.date)))?;
+    let created_by = optional(layout.broker)
+        .or(source)
+        .ok_or_else(|| "no broker".to_string())?;
+    let recommendation = match optional(layout.recommendation) {
+        Some(value) => Some(
+            RecommendationType::parse(value)
+                .ok_or_else(|| format!("recommendation type {value:?}"))?,
+        ),
+        None => None,
+    };
+    let horizon_months = match optional(layout.horizon) {
+        Some(value) => Some(parse_horizon(value).ok_or_else(|| format!("horizon {value:?}"))?),
+        None => None,
+    };
+
+    Ok(UpsideRecord {
+        instrument: instrument.to_string(),
+        upside,
+        created_by: created_by.to_lowercase(),
+        created_at,
+        stop_loss: None,
+        recommendation,
+        horizon_months,
+    })
+}
+
+/// `12,50`, `1 234,50 zł`, `1.234,50`, `12.50 PLN`
+fn parse_price(value: &str) -> Option<f64> {
+    let digits: String = value
+        .chars()
+        .filter(|c| c.is_ascii_digit() || *c == ',' || *c == '.' || *c == '-')
+        .collect();
+    let digits = if digits.contains(',') {
+        digits.replace('.', "").replace(',', ".")
+    } else {
+        digits
+    };
+    digits.parse::<f64>().ok().filter(|price| *price > 0_f64)
+}
+
+/// Date of a date or a date with time.
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    let date = value.split_whitespace().next()?;
+    DATE_FORMATS
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(date, format).ok())
+}
+
+/// Months of `12M`, `12 m-cy`, `6 miesięcy`, `1 rok`, `2Y`, `12`.
+fn parse_horizon(value: &str) -> Option<u32> {
+    let value = normalize(value);
+    let unit = value.trim_start_matches(|c: char| c.is_ascii_digit());
+    let number = value[..value.len() - unit.len()].parse::<u32>().ok()?;
+    let unit = unit.trim();
+    if unit.starts_with('r') || unit.starts_with('l') || unit.starts_with('y') {
+        Some(number * 12)
+    } else {
+        Some(number)
+    }
+}
+
+/// Lower case without Polish diacritics and surrounding whitespace.
+fn normalize(value: &str) -> String {
+    value
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'ą' => 'a',
+            'ć' => 'c',
+            'ę' => 'e',
+            'ł' => 'l',
+            'ń' => 'n',
+            'ó' => 'o',
+            'ś' => 's',
+            'ź' | 'ż' => 'z',
+            c => c,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_export_bossa_html_table_return_mapped_records_and_rejected_rows() {
+        let dir = _temp_dir("bossa_html");
+        let export = dir.join("bossa.html");
+        fs::write(
+            &export,
+            "<html><body><h1>Rekomendacje</h1><table class=\"rek\">\
+             <thead><tr><th>Spółka</th><th>Rekomendacja</th><th>Cena docelowa</th>\
+             <th>Data wydania</th><th>Instytucja</th><th>Horyzont</th></tr></thead>\
+             <tbody><tr><td><a href=\"/o/ORANGEPL\">ORANGEPL</a></td><td>Kupuj</td>\
+             <td>10,80&nbsp;zł</td><td>24.04.2025</td><td>DM BOŚ</td><td>12 m-cy</td></tr>\
+             <tr><td>PKOBP</td><td>Trzymaj</td><td>-</td><td>23.04.2025</td>\
+             <td>mBank</td><td>12M</td></tr></tbody></table></body></html>",
+        )
+        .unwrap();
+
+        let mut rejected = Vec::new();
+        let records = read_export(&export, None, &mut rejected).unwrap();
+
+        assert_eq!(
+            records,
+            vec![UpsideRecord {
+                instrument: "ORANGEPL".to_string(),
+                upside: 10.8,
+                created_by: "dm boś".to_string(),
+                created_at: NaiveDate::from_ymd_opt(2025, 4, 24).unwrap(),
+                stop_loss: None,
+                recommendation: Some(RecommendationType::Buy),
+                horizon_months: Some(12),
+            }]
+        );
+        assert_eq!(rejected.len(), 1);
+        assert_eq!(rejected[0].row, 3);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn import_export_imported_twice_return_duplicates_skipped() {
+        let dir = _temp_dir("import_twice");
+        let export = dir.join("mbank.csv");
+        let upside_csv = dir.join("upside.csv");
+        fs::write(
+            &export,
+            "Spółka;Rekomendacja;Cena docelowa;Data\n\
+             ORANGEPL;akumuluj;10,01;2025-04-23\n\
+             PKOBP;redukuj;55,00;2025-04-23\n",
+        )
+        .unwrap();
+
+        let first = import(std::slice::from_ref(&export), Some("mbank"), &upside_csv).unwrap();
+        let second = import(&[export], Some("mbank"), &upside_csv).unwrap();
+
+        assert_eq!((first.imported, first.duplicates), (2, 0));
+        assert_eq!((second.imported, second.duplicates), (0, 2));
+        assert_eq!(load(&upside_csv).unwrap().len(), 2);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn _temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("recommendations_{}_{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+}
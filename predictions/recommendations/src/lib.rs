@@ -0,0 +1,3 @@
+mod html;
+pub mod import;
+pub mod outcome;
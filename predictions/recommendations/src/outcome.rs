@@ -0,0 +1,270 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use chrono::NaiveDate;
+use polars::prelude::*;
+use shared_contracts::errors::PortfolioError;
+
+/// Validity of a recommendation without `horizon_months`, counted from `created_at`.
+pub const DEFAULT_HORIZON_MONTHS: u32 = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecommendationStatus {
+    /// Within its horizon, target and stop-loss not reached; also targets without market data.
+    Active,
+    /// The horizon has passed without reaching the target or the stop-loss.
+    Expired,
+    Hit,
+    /// The stop-loss of the recommendation was reached before the target.
+    Stopped,
+}
+
+impl RecommendationStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RecommendationStatus::Active => "active",
+            RecommendationStatus::Expired => "expired",
+            RecommendationStatus::Hit => "hit",
+            RecommendationStatus::Stopped => "stopped",
+        }
+    }
+}
+
+/// Session range of an instrument.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bar {
+    pub max_price: f64,
+    pub min_price: f64,
+    pub closing_price: f64,
+}
+
+/// Sessions of every instrument, oldest first.
+pub type BarHistory = HashMap<String, BTreeMap<NaiveDate, Bar>>;
+
+/// A recommendation checked against the sessions of its horizon.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Outcome {
+    pub status: RecommendationStatus,
+    /// Close of the first session on or after `created_at`, `None` without one.
+    pub entry_price: Option<f64>,
+    /// Session the target or the stop-loss was reached, the expiry of an expired recommendation.
+    pub date: Option<NaiveDate>,
+    /// The target, the stop-loss, or the last close within the horizon of an expired recommendation.
+    pub price: Option<f64>,
+    /// Worst move against the recommendation until it was hit or stopped, or to the
+    /// latest session of the horizon, in % of the entry price, 0 or negative.
+    pub max_adverse_pct: Option<f64>,
+    /// Last close within the horizon, sessions after the hit included.
+    pub horizon_close: Option<f64>,
+}
+
+/// Checks a recommendation against the sessions after the recommendation session up to
+/// `expiry`. The recommendation session itself is not counted, its range is partly known
+/// when the recommendation is made.
+///
+/// A target above the entry price is hit by a session high, one below it by a session low.
+/// A long recommendation with a `stop_loss` below the entry price is stopped by a session
+/// low at or below it, a session reaching both counts as stopped. Not reached, it is
+/// expired once `as_of`, the latest session of the market data, reaches the expiry.
+/// Without a session up to the expiry there is no entry price, an old recommendation
+/// against recent market data is expired.
+pub fn evaluate(
+    bars: &BTreeMap<NaiveDate, Bar>,
+    created_at: NaiveDate,
+    expiry: NaiveDate,
+    target: f64,
+    stop_loss: Option<f64>,
+    as_of: NaiveDate,
+) -> Outcome {
+    let mut outcome = Outcome {
+        status: RecommendationStatus::Active,
+        entry_price: None,
+        date: None,
+        price: None,
+        max_adverse_pct: None,
+        horizon_close: None,
+    };
+    let Some((&entry_date, entry)) = bars
+        .range(created_at..)
+        .next()
+        .filter(|(date, _)| **date <= expiry)
+    else {
+        // No session within the horizon, the history may start after it ended.
+        if as_of >= expiry {
+            outcome.status = RecommendationStatus::Expired;
+            outcome.date = Some(expiry);
+        }
+        return outcome;
+    };
+    let entry_price = entry.closing_price;
+    let long = target >= entry_price;
+    let stop_loss = stop_loss.filter(|stop| long && *stop < entry_price);
+    outcome.entry_price = Some(entry_price);
+
+    let mut worst = entry_price;
+    for (date, bar) in bars.range(entry_date..=expiry).skip(1) {
+        outcome.horizon_close = Some(bar.closing_price);
+        if outcome.date.is_some() {
+            continue;
+        }
+        worst = if long {
+            worst.min(bar.min_price)
+        } else {
+            worst.max(bar.max_price)
+        };
+        let stopped = stop_loss.is_some_and(|stop| bar.min_price <= stop);
+        let hit = if long {
+            bar.max_price >= target
+        } else {
+            bar.min_price <= target
+        };
+        let (status, price) = match (stopped, hit) {
+            (true, _) => (RecommendationStatus::Stopped, stop_loss),
+            (false, true) => (RecommendationStatus::Hit, Some(target)),
+            _ => continue,
+        };
+        outcome.status = status;
+        outcome.date = Some(*date);
+        outcome.price = price;
+    }
+
+    let change = worst / entry_price - 1_f64;
+    let adverse = if long { change } else { -change };
+    outcome.max_adverse_pct = Some((adverse * 10000_f64).round() / 100_f64);
+    if outcome.status == RecommendationStatus::Active && as_of >= expiry {
+        outcome.status = RecommendationStatus::Expired;
+        outcome.date = Some(expiry);
+        outcome.price = outcome.horizon_close.or(Some(entry_price));
+    }
+    outcome
+}
+
+/// Sessions of the instruments from market data history, sessions without trades
+/// (zero prices) skipped.
+pub fn read_bars(
+    history: LazyFrame,
+    instruments: &HashSet<String>,
+) -> Result<BarHistory, PortfolioError> {
+    let df = history
+        .select([
+            col("instrument"),
+            col("date"),
+            col("max_price").cast(DataType::Float64),
+            col("min_price").cast(DataType::Float64),
+            col("closing_price").cast(DataType::Float64),
+        ])
+        .filter(
+            col("closing_price")
+                .gt(lit(0))
+                .and(col("max_price").gt(lit(0)))
+                .and(col("min_price").gt(lit(0))),
+        )
+        .collect()?;
+
+    let names = df.column("instrument")?.str()?;
+    let dates: Vec<Option<NaiveDate>> = df.column("date")?.date()?.as_date_iter().collect();
+    let max_prices = df.column("max_price")?.f64()?;
+    let min_prices = df.column("min_price")?.f64()?;
+    let closing_prices = df.column("closing_price")?.f64()?;
+
+    let mut bars: BarHistory = HashMap::new();
+    for (i, date) in dates.into_iter().enumerate() {
+        let (Some(name), Some(date), Some(max_price), Some(min_price), Some(closing_price)) = (
+            names.get(i),
+            date,
+            max_prices.get(i),
+            min_prices.get(i),
+            closing_prices.get(i),
+        ) else {
+            continue;
+        };
+        if !instruments.contains(name) {
+            continue;
+        }
+        bars.entry(name.to_string()).or_default().insert(
+            date,
+            Bar {
+                max_price,
+                min_price,
+                closing_price,
+            },
+        );
+    }
+    Ok(bars)
+}
+
+/// Latest session of the history.
+pub fn latest_session(bars: &BarHistory) -> Option<NaiveDate> {
+    bars.values()
+        .filter_map(|b| b.keys().next_back())
+        .max()
+        .copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evaluate_hit_then_lower_close_return_hit_with_horizon_close() {
+        let bars = BTreeMap::from([
+            (_date("2025-01-02"), _bar(10.5, 9.5, 10.0)),
+            (_date("2025-01-03"), _bar(10.0, 9.0, 9.5)),
+            (_date("2025-01-06"), _bar(11.2, 9.8, 11.0)),
+            (_date("2025-01-07"), _bar(11.0, 8.0, 8.5)),
+        ]);
+
+        let hit = evaluate(
+            &bars,
+            _date("2025-01-02"),
+            _date("2025-02-02"),
+            11.0,
+            None,
+            _date("2025-01-07"),
+        );
+        let stopped = evaluate(
+            &bars,
+            _date("2025-01-02"),
+            _date("2025-02-02"),
+            11.0,
+            Some(9.0),
+            _date("2025-01-07"),
+        );
+
+        assert_eq!(hit.status, RecommendationStatus::Hit);
+        assert_eq!(hit.date, Some(_date("2025-01-06")));
+        assert_eq!(hit.max_adverse_pct, Some(-10.0));
+        assert_eq!(hit.horizon_close, Some(8.5));
+        assert_eq!(stopped.status, RecommendationStatus::Stopped);
+        assert_eq!(stopped.price, Some(9.0));
+    }
+
+    #[test]
+    fn evaluate_horizon_before_history_return_expired_without_entry() {
+        let bars = BTreeMap::from([(_date("2025-10-17"), _bar(10.5, 9.5, 10.0))]);
+
+        let outcome = evaluate(
+            &bars,
+            _date("2020-04-24"),
+            _date("2021-04-24"),
+            11.0,
+            None,
+            _date("2025-10-17"),
+        );
+
+        assert_eq!(outcome.status, RecommendationStatus::Expired);
+        assert_eq!(outcome.entry_price, None);
+        assert_eq!(outcome.price, None);
+    }
+
+    fn _bar(max_price: f64, min_price: f64, closing_price: f64) -> Bar {
+        Bar {
+            max_price,
+            min_price,
+            closing_price,
+        }
+    }
+
+    fn _date(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+    }
+}
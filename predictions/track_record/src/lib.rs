@@ -0,0 +1 @@
+pub mod track_record;
@@ -0,0 +1,389 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use chrono::{Months, NaiveDate};
+use market_store::source::MarketDataSource;
+use polars::prelude::*;
+use recommendations::outcome::{BarHistory, RecommendationStatus, latest_session, read_bars};
+use serde::{Deserialize, Serialize};
+use shared_contracts::errors::PortfolioError;
+use shared_contracts::models::instrument::InstrumentMaster;
+use util::math::round;
+use util::report::{Report, ReportFormat};
+
+pub struct TrackRecordConf<'a> {
+    /// Upside csv files (`instrument;upside;created_by;created_at`), any number of years.
+    pub upside_csv: &'a [PathBuf],
+    pub market_data: MarketDataSource<'a>,
+    /// Resolves analyst and broker names to market data symbols.
+    pub instruments: &'a InstrumentMaster,
+    /// Months after the recommendation in which the target has to be hit, for
+    /// recommendations without `horizon_months`.
+    pub default_horizon_months: u32,
+    pub output_file: &'a Path,
+    /// Source scores csv, readable with [`load_weights`].
+    pub scores_file: &'a Path,
+    pub format: ReportFormat,
+}
+
+/// One row of an upside csv.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Recommendation {
+    pub instrument: String,
+    /// Target price.
+    pub upside: f64,
+    pub created_by: String,
+    pub created_at: NaiveDate,
+    #[serde(default)]
+    pub horizon_months: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Outcome {
+    /// The session range reached the target within the horizon.
+    Hit,
+    /// The horizon has passed without reaching the target.
+    Missed,
+    /// Not hit yet, market data ends before the horizon.
+    Open,
+    /// No session on or after the recommendation date.
+    NoPrice,
+}
+
+/// A recommendation checked against realized prices.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Evaluation {
+    pub instrument: String,
+    pub created_by: String,
+    pub created_at: NaiveDate,
+    pub target: f64,
+    /// Close of the first session on or after `created_at`.
+    pub entry_price: Option<f64>,
+    pub outcome: Outcome,
+    pub hit_date: Option<NaiveDate>,
+    pub days_to_hit: Option<i64>,
+    /// Worst move against the recommendation until the hit or the horizon end,
+    /// in % of the entry price, 0 or negative.
+    pub max_adverse_pct: Option<f64>,
+    /// Last close within the horizon.
+    pub horizon_close: Option<f64>,
+    /// How far the target was from the horizon close, in % of the close.
+    /// Set once the horizon has passed; positive when the target was too high.
+    pub target_error_pct: Option<f64>,
+}
+
+/// Accuracy and bias of one recommendation source.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SourceScore {
+    pub created_by: String,
+    pub recommendations: usize,
+    /// Hit and missed recommendations.
+    pub evaluated: usize,
+    pub hits: usize,
+    pub hit_rate_pct: Option<f64>,
+    pub avg_days_to_hit: Option<f64>,
+    pub avg_max_adverse_pct: Option<f64>,
+    /// Average `target_error_pct`, positive for a source with too optimistic targets.
+    pub bias_pct: Option<f64>,
+    pub mean_abs_error_pct: Option<f64>,
+    /// Hit rate with one hit and one miss added, relative to 50%. A source without
+    /// evaluated recommendations weighs 1, a perfect one approaches 2.
+    pub weight: f64,
+}
+
+pub fn calculate_and_save(conf: TrackRecordConf) -> Result<Vec<SourceScore>, PortfolioError> {
+    let mut recommendations = Vec::new();
+    for upside_csv in conf.upside_csv {
+        recommendations.extend(load_recommendations(upside_csv)?);
+    }
+    for recommendation in recommendations.iter_mut() {
+        recommendation.instrument = conf
+            .instruments
+            .symbol(&recommendation.instrument)
+            .to_string();
+    }
+    let symbols: HashSet<String> = recommendations
+        .iter()
+        .map(|r| r.instrument.clone())
+        .collect();
+    let history = read_bars(
+        util::polars::resolve_instruments(conf.market_data.history()?, conf.instruments),
+        &symbols,
+    )?;
+
+    let evaluations = evaluate(&recommendations, &history, conf.default_horizon_months);
+    let scores = score_sources(&evaluations);
+
+    let mut report = Report::new(
+        "Analyst Track Record",
+        evaluations_data_frame(&evaluations)?,
+    )
+    .with_summary(scores_data_frame(&scores)?);
+    util::report::save(&mut report, conf.output_file, conf.format)?;
+    save_scores(&scores, conf.scores_file)?;
+    Ok(scores)
+}
+
+pub fn load_recommendations(upside_csv: &Path) -> Result<Vec<Recommendation>, PortfolioError> {
+    let mut rdr = util::csv::default_reader(upside_csv)?;
+    let mut recommendations = Vec::new();
+    for result in rdr.deserialize() {
+        let recommendation: Recommendation = result?;
+        recommendations.push(recommendation);
+    }
+    Ok(recommendations)
+}
+
+pub fn save_scores(scores: &[SourceScore], scores_csv: &Path) -> Result<(), PortfolioError> {
+    let mut wtr = util::csv::default_writer(scores_csv)?;
+    for score in scores {
+        wtr.serialize(score)?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+/// `created_by` -> weight from a source scores csv.
+pub fn load_weights(scores_csv: &Path) -> Result<HashMap<String, f64>, PortfolioError> {
+    let mut rdr = util::csv::default_reader(scores_csv)?;
+    let mut weights = HashMap::new();
+    for result in rdr.deserialize() {
+        let score: SourceScore = result?;
+        weights.insert(score.created_by, score.weight);
+    }
+    Ok(weights)
+}
+
+/// Checks every recommendation against the sessions of its horizon with
+/// [`recommendations::outcome::evaluate`], `horizon_months` (or `default_horizon_months`)
+/// after it.
+fn evaluate(
+    recommendations: &[Recommendation],
+    history: &BarHistory,
+    default_horizon_months: u32,
+) -> Vec<Evaluation> {
+    let data_end = latest_session(history);
+
+    recommendations
+        .iter()
+        .map(|r| {
+            let mut evaluation = Evaluation {
+                instrument: r.instrument.clone(),
+                created_by: r.created_by.clone(),
+                created_at: r.created_at,
+                target: r.upside,
+                entry_price: None,
+                outcome: Outcome::NoPrice,
+                hit_date: None,
+                days_to_hit: None,
+                max_adverse_pct: None,
+                horizon_close: None,
+                target_error_pct: None,
+            };
+            let months = Months::new(r.horizon_months.unwrap_or(default_horizon_months));
+            let (Some(bars), Some(data_end), Some(horizon_end)) = (
+                history.get(&r.instrument),
+                data_end,
+                r.created_at.checked_add_months(months),
+            ) else {
+                return evaluation;
+            };
+            let outcome = recommendations::outcome::evaluate(
+                bars,
+                r.created_at,
+                horizon_end,
+                r.upside,
+                None,
+                data_end,
+            );
+            // No session within the horizon, also when it ended before the history starts.
+            if outcome.entry_price.is_none() {
+                return evaluation;
+            }
+
+            evaluation.entry_price = outcome.entry_price;
+            evaluation.outcome = match outcome.status {
+                RecommendationStatus::Hit => Outcome::Hit,
+                RecommendationStatus::Expired | RecommendationStatus::Stopped => Outcome::Missed,
+                RecommendationStatus::Active => Outcome::Open,
+            };
+            if evaluation.outcome == Outcome::Hit {
+                evaluation.hit_date = outcome.date;
+                evaluation.days_to_hit = outcome.date.map(|date| (date - r.created_at).num_days());
+            }
+            evaluation.max_adverse_pct = outcome.max_adverse_pct;
+            evaluation.horizon_close = outcome.horizon_close;
+            if data_end >= horizon_end {
+                evaluation.target_error_pct = outcome
+                    .horizon_close
+                    .map(|close| pct(r.upside / close - 1_f64));
+            }
+            evaluation
+        })
+        .collect()
+}
+
+/// Per source statistics, sorted by `created_by`.
+fn score_sources(evaluations: &[Evaluation]) -> Vec<SourceScore> {
+    let mut by_source: BTreeMap<&str, Vec<&Evaluation>> = BTreeMap::new();
+    for evaluation in evaluations {
+        by_source
+            .entry(evaluation.created_by.as_str())
+            .or_default()
+            .push(evaluation);
+    }
+
+    by_source
+        .into_iter()
+        .map(|(created_by, evaluations)| {
+            let hits = evaluations
+                .iter()
+                .filter(|e| e.outcome == Outcome::Hit)
+                .count();
+            let evaluated = hits
+                + evaluations
+                    .iter()
+                    .filter(|e| e.outcome == Outcome::Missed)
+                    .count();
+            let errors: Vec<f64> = evaluations
+                .iter()
+                .filter_map(|e| e.target_error_pct)
+                .collect();
+
+            SourceScore {
+                created_by: created_by.to_string(),
+                recommendations: evaluations.len(),
+                evaluated,
+                hits,
+                hit_rate_pct: (evaluated > 0).then(|| pct(hits as f64 / evaluated as f64)),
+                avg_days_to_hit: average(
+                    evaluations
+                        .iter()
+                        .filter_map(|e| e.days_to_hit)
+                        .map(|d| d as f64),
+                ),
+                avg_max_adverse_pct: average(evaluations.iter().filter_map(|e| e.max_adverse_pct)),
+                bias_pct: average(errors.iter().copied()),
+                mean_abs_error_pct: average(errors.iter().map(|e| e.abs())),
+                weight: round(
+                    2_f64 * (hits as f64 + 1_f64) / (evaluated as f64 + 2_f64),
+                    4,
+                ),
+            }
+        })
+        .collect()
+}
+
+fn evaluations_data_frame(evaluations: &[Evaluation]) -> Result<DataFrame, PortfolioError> {
+    let df = df!(
+        "instrument" => evaluations.iter().map(|e| e.instrument.as_str()).collect::<Vec<_>>(),
+        "created_by" => evaluations.iter().map(|e| e.created_by.as_str()).collect::<Vec<_>>(),
+        "created_at" => evaluations.iter().map(|e| e.created_at).collect::<Vec<_>>(),
+        "target" => evaluations.iter().map(|e| e.target).collect::<Vec<_>>(),
+        "entry_price" => evaluations.iter().map(|e| e.entry_price).collect::<Vec<_>>(),
+        "outcome" => evaluations.iter().map(|e| format!("{:?}", e.outcome)).collect::<Vec<_>>(),
+        "hit_date" => evaluations.iter().map(|e| e.hit_date).collect::<Vec<_>>(),
+        "days_to_hit" => evaluations.iter().map(|e| e.days_to_hit).collect::<Vec<_>>(),
+        "max_adverse_pct" => evaluations.iter().map(|e| e.max_adverse_pct).collect::<Vec<_>>(),
+        "horizon_close" => evaluations.iter().map(|e| e.horizon_close).collect::<Vec<_>>(),
+        "target_error_pct" => evaluations.iter().map(|e| e.target_error_pct).collect::<Vec<_>>(),
+    )?;
+    Ok(df)
+}
+
+fn scores_data_frame(scores: &[SourceScore]) -> Result<DataFrame, PortfolioError> {
+    let df = df!(
+        "created_by" => scores.iter().map(|s| s.created_by.as_str()).collect::<Vec<_>>(),
+        "recommendations" => scores.iter().map(|s| s.recommendations as u64).collect::<Vec<_>>(),
+        "evaluated" => scores.iter().map(|s| s.evaluated as u64).collect::<Vec<_>>(),
+        "hits" => scores.iter().map(|s| s.hits as u64).collect::<Vec<_>>(),
+        "hit_rate_pct" => scores.iter().map(|s| s.hit_rate_pct).collect::<Vec<_>>(),
+        "avg_days_to_hit" => scores.iter().map(|s| s.avg_days_to_hit).collect::<Vec<_>>(),
+        "avg_max_adverse_pct" => scores.iter().map(|s| s.avg_max_adverse_pct).collect::<Vec<_>>(),
+        "bias_pct" => scores.iter().map(|s| s.bias_pct).collect::<Vec<_>>(),
+        "mean_abs_error_pct" => scores.iter().map(|s| s.mean_abs_error_pct).collect::<Vec<_>>(),
+        "weight" => scores.iter().map(|s| s.weight).collect::<Vec<_>>(),
+    )?;
+    Ok(df)
+}
+
+fn average(values: impl Iterator<Item = f64>) -> Option<f64> {
+    let (sum, count) = values.fold((0_f64, 0_usize), |(sum, count), v| (sum + v, count + 1));
+    (count > 0).then(|| round(sum / count as f64, 2))
+}
+
+fn pct(value: f64) -> f64 {
+    round(value * 100_f64, 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use recommendations::outcome::Bar;
+
+    use super::*;
+
+    #[test]
+    fn evaluate_hit_missed_and_open_return_source_scores() {
+        let history: BarHistory = HashMap::from([(
+            "ORANGEPL".to_string(),
+            BTreeMap::from([
+                (_date("2025-01-02"), _bar(10.5, 9.5, 10.0)),
+                (_date("2025-01-03"), _bar(10.0, 9.0, 9.5)),
+                (_date("2025-01-06"), _bar(11.2, 9.8, 11.0)),
+                (_date("2025-01-31"), _bar(11.0, 10.4, 10.5)),
+                (_date("2025-02-03"), _bar(10.6, 10.2, 10.4)),
+            ]),
+        )]);
+        let recommendations = vec![
+            _recommendation(11.0, "mbank", "2025-01-02"),
+            _recommendation(12.0, "mbank", "2025-01-02"),
+            _recommendation(12.0, "konse", "2025-01-06"),
+            _recommendation(12.0, "mbank", "2024-06-03"),
+        ];
+
+        let evaluations = evaluate(&recommendations, &history, 1);
+
+        let outcomes: Vec<Outcome> = evaluations.iter().map(|e| e.outcome).collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                Outcome::Hit,
+                Outcome::Missed,
+                Outcome::Open,
+                Outcome::NoPrice
+            ]
+        );
+        assert_eq!(evaluations[0].days_to_hit, Some(4));
+        assert_eq!(evaluations[0].max_adverse_pct, Some(-10.0));
+        // The horizon ends on 2025-02-02, the last close before it is 10.5: 12 / 10.5 - 1.
+        assert_eq!(evaluations[1].target_error_pct, Some(14.29));
+
+        let scores = score_sources(&evaluations);
+        assert_eq!(scores[0].created_by, "konse");
+        assert_eq!(scores[0].weight, 1.0);
+        assert_eq!(scores[1].hit_rate_pct, Some(50.0));
+        assert_eq!(scores[1].weight, 1.0);
+    }
+
+    fn _bar(max_price: f64, min_price: f64, closing_price: f64) -> Bar {
+        Bar {
+            max_price,
+            min_price,
+            closing_price,
+        }
+    }
+
+    fn _recommendation(upside: f64, created_by: &str, created_at: &str) -> Recommendation {
+        Recommendation {
+            instrument: "ORANGEPL".to_string(),
+            upside,
+            created_by: created_by.to_string(),
+            created_at: _date(created_at),
+            horizon_months: None,
+        }
+    }
+
+    fn _date(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+    }
+}
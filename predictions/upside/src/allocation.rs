@@ -0,0 +1,321 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use chrono::NaiveDate;
+use polars::prelude::*;
+use shared_contracts::errors::PortfolioError;
+use trading_calendar::calendar::TradingCalendar;
+use util::math::round;
+use util::money::{Commission, tax};
+
+use crate::dividends::dividend_net;
+
+/// Budget steps of the allocation search, the budget is split with `capital / BUDGET_STEPS` precision.
+const BUDGET_STEPS: usize = 500;
+
+/// One budget split across the upside candidates.
+#[derive(Debug, Clone, Copy)]
+pub struct AllocationConf<'a> {
+    /// Total capital, buy commissions included.
+    pub capital: f64,
+    /// Largest cost of one position.
+    pub max_position: f64,
+    /// Smallest value of a bought position.
+    pub min_position: f64,
+    pub output_file: &'a Path,
+}
+
+/// Instrument that can be bought.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    pub instrument: String,
+    pub price: f64,
+    pub target: f64,
+    pub stop_loss: f64,
+    /// Probability of reaching the target, otherwise the position is sold at the stop-loss.
+    pub probability: f64,
+    /// GPW sessions after the price date up to the expiry of the target.
+    pub horizon_sessions: usize,
+    /// Forecast dividends per share, 0 without a forecast.
+    pub dividend_per_share: f64,
+}
+
+/// Line of the buy list.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Position {
+    pub instrument: String,
+    pub quantity: u64,
+    pub price: f64,
+    /// Purchase value with the buy commission.
+    pub cost: f64,
+    pub commission: f64,
+    pub target: f64,
+    pub stop_loss: f64,
+    pub probability: f64,
+    /// Net profit when sold at the target, forecast dividends included like in the upside report.
+    pub net_profit: f64,
+    /// Net result when sold at the stop-loss.
+    pub net_profit_stop: f64,
+    pub expected_value: f64,
+}
+
+/// Latest target of every priced instrument of the upside report, targets without
+/// a stop-loss are skipped.
+pub fn candidates(df: &DataFrame) -> Result<Vec<Candidate>, PortfolioError> {
+    let instruments = df.column("instrument")?.str()?;
+    let prices = df.column("actual_price")?.f64()?;
+    let targets = df.column("upside")?.f64()?;
+    let stops = df.column("stop_loss")?.f64()?;
+    let probabilities = df.column("probability")?.f64()?;
+    let dividends = df.column("dividend_per_share")?.f64()?;
+    let created_at: Vec<Option<NaiveDate>> =
+        df.column("created_at")?.date()?.as_date_iter().collect();
+    let price_dates: Vec<Option<NaiveDate>> =
+        df.column("price_date")?.date()?.as_date_iter().collect();
+    let expires_at: Vec<Option<NaiveDate>> =
+        df.column("expires_at")?.date()?.as_date_iter().collect();
+    let calendar = TradingCalendar::gpw();
+
+    let mut latest: BTreeMap<String, (Option<NaiveDate>, Candidate)> = BTreeMap::new();
+    for (i, created_at) in created_at.into_iter().enumerate() {
+        let (Some(instrument), Some(price), Some(target), Some(stop_loss), Some(probability)) = (
+            instruments.get(i),
+            prices.get(i),
+            targets.get(i),
+            stops.get(i),
+            probabilities.get(i),
+        ) else {
+            continue;
+        };
+        if latest
+            .get(instrument)
+            .is_some_and(|(date, _)| *date >= created_at)
+        {
+            continue;
+        }
+        let horizon_sessions = match (price_dates[i], expires_at[i]) {
+            (Some(price_date), Some(expires_at)) if price_date < expires_at => calendar
+                .sessions(calendar.next_session(price_date)..=expires_at)
+                .len(),
+            _ => 0,
+        };
+        latest.insert(
+            instrument.to_string(),
+            (
+                created_at,
+                Candidate {
+                    instrument: instrument.to_string(),
+                    price,
+                    target,
+                    stop_loss,
+                    probability,
+                    horizon_sessions,
+                    dividend_per_share: dividends.get(i).unwrap_or_default(),
+                },
+            ),
+        );
+    }
+    Ok(latest.into_values().map(|(_, c)| c).collect())
+}
+
+/// Chooses integer share quantities maximizing the summed expected value within
+/// the capital. The minimum commission makes small positions cost more per złoty,
+/// so every candidate is priced at each budget step and the best combination is
+/// searched over all of them (knapsack). Positions with no positive expected value
+/// are not bought. Sorted by expected value, best first.
+pub fn allocate(
+    candidates: &[Candidate],
+    allocation: &AllocationConf,
+    commission: Commission,
+) -> Vec<Position> {
+    let step = allocation.capital / BUDGET_STEPS as f64;
+    if step <= 0_f64 {
+        return Vec::new();
+    }
+
+    // Per candidate: budget steps used and the position bought with them.
+    let options: Vec<Vec<(usize, Position)>> = candidates
+        .iter()
+        .map(|candidate| {
+            let mut options: Vec<(usize, Position)> = Vec::new();
+            for steps in 1..=BUDGET_STEPS {
+                let budget = (steps as f64 * step).min(allocation.max_position);
+                let quantity = max_quantity(candidate.price, budget, commission);
+                if quantity == 0
+                    || quantity as f64 * candidate.price < allocation.min_position
+                    || options.last().is_some_and(|(_, p)| p.quantity == quantity)
+                {
+                    continue;
+                }
+                let position = position(candidate, quantity, commission);
+                if position.expected_value > 0_f64 {
+                    options.push((steps, position));
+                }
+            }
+            options
+        })
+        .collect();
+
+    let mut best = vec![0_f64; BUDGET_STEPS + 1];
+    let mut choices = vec![vec![None; BUDGET_STEPS + 1]; candidates.len()];
+    for (i, candidate_options) in options.iter().enumerate() {
+        let previous = best.clone();
+        for budget in 0..=BUDGET_STEPS {
+            for (option, (steps, position)) in candidate_options.iter().enumerate() {
+                if *steps > budget {
+                    break;
+                }
+                let value = previous[budget - steps] + position.expected_value;
+                if value > best[budget] {
+                    best[budget] = value;
+                    choices[i][budget] = Some(option);
+                }
+            }
+        }
+    }
+
+    let mut positions = Vec::new();
+    let mut budget = BUDGET_STEPS;
+    for i in (0..candidates.len()).rev() {
+        if let Some(option) = choices[i][budget] {
+            let (steps, position) = &options[i][option];
+            positions.push(position.clone());
+            budget -= steps;
+        }
+    }
+    positions.sort_by(|a, b| b.expected_value.total_cmp(&a.expected_value));
+    positions
+}
+
+/// Buy list with a capital summary.
+pub fn buy_list(
+    positions: &[Position],
+    capital: f64,
+) -> Result<(DataFrame, DataFrame), PortfolioError> {
+    let invested: f64 = positions.iter().map(|p| p.cost).sum();
+    let df = df!(
+        "instrument" => positions.iter().map(|p| p.instrument.as_str()).collect::<Vec<_>>(),
+        "quantity" => positions.iter().map(|p| p.quantity).collect::<Vec<_>>(),
+        "price" => positions.iter().map(|p| p.price).collect::<Vec<_>>(),
+        "cost" => positions.iter().map(|p| p.cost).collect::<Vec<_>>(),
+        "commission" => positions.iter().map(|p| p.commission).collect::<Vec<_>>(),
+        "capital_pct" => positions.iter().map(|p| round(p.cost / capital * 100_f64, 2)).collect::<Vec<_>>(),
+        "target" => positions.iter().map(|p| p.target).collect::<Vec<_>>(),
+        "stop_loss" => positions.iter().map(|p| p.stop_loss).collect::<Vec<_>>(),
+        "probability" => positions.iter().map(|p| p.probability).collect::<Vec<_>>(),
+        "net_profit" => positions.iter().map(|p| p.net_profit).collect::<Vec<_>>(),
+        "net_profit_stop" => positions.iter().map(|p| p.net_profit_stop).collect::<Vec<_>>(),
+        "expected_value" => positions.iter().map(|p| p.expected_value).collect::<Vec<_>>(),
+    )?;
+    let summary = df!(
+        "capital" => [capital],
+        "invested" => [round(invested, 2)],
+        "cash_left" => [round(capital - invested, 2)],
+        "positions" => [positions.len() as u64],
+        "expected_value_total" => [round(positions.iter().map(|p| p.expected_value).sum(), 2)],
+    )?;
+    Ok((df, summary))
+}
+
+/// Most shares whose purchase value and buy commission fit in `budget`.
+fn max_quantity(price: f64, budget: f64, commission: Commission) -> u64 {
+    if price <= 0_f64 {
+        return 0;
+    }
+    let by_percent = budget / (price * (1_f64 + commission.percent));
+    let by_minimum = (budget - commission.min) / price;
+    by_percent.min(by_minimum).max(0_f64).floor() as u64
+}
+
+fn position(candidate: &Candidate, quantity: u64, commission: Commission) -> Position {
+    let purchase_value = round(quantity as f64 * candidate.price, 2);
+    let cost = purchase_cost(quantity, candidate.price, commission);
+    let at_target = round(
+        net_profit(quantity, candidate.target, cost, commission)
+            + dividend_net(quantity, candidate.dividend_per_share),
+        2,
+    );
+    let at_stop = net_profit(quantity, candidate.stop_loss, cost, commission);
+
+    Position {
+        instrument: candidate.instrument.clone(),
+        quantity,
+        price: candidate.price,
+        cost,
+        commission: commission.amount(purchase_value),
+        target: candidate.target,
+        stop_loss: candidate.stop_loss,
+        probability: candidate.probability,
+        net_profit: at_target,
+        net_profit_stop: at_stop,
+        expected_value: round(
+            candidate.probability * at_target + (1_f64 - candidate.probability) * at_stop,
+            2,
+        ),
+    }
+}
+
+/// Purchase value of `quantity` at `price` with the buy commission.
+pub(crate) fn purchase_cost(quantity: u64, price: f64, commission: Commission) -> f64 {
+    let purchase_value = round(quantity as f64 * price, 2);
+    round(purchase_value + commission.amount(purchase_value), 2)
+}
+
+/// Net result of selling `quantity` at `price`, with the same commission and tax as the upside report.
+pub(crate) fn net_profit(
+    quantity: u64,
+    price: f64,
+    cost_basis: f64,
+    commission: Commission,
+) -> f64 {
+    let sale_value = round(quantity as f64 * price, 2);
+    let net_proceeds = round(sale_value - commission.amount(sale_value), 2);
+    let tax_base = round(net_proceeds - cost_basis, 2);
+    round(tax_base - tax(tax_base), 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allocate_capped_budget_return_best_expected_value_buy_list() {
+        let candidates = vec![
+            _candidate("ORANGEPL", 10.0, 12.0, 9.5),
+            _candidate("PKOBP", 60.0, 63.0, 58.0),
+            _candidate("PEKAO", 150.0, 140.0, 145.0),
+        ];
+        let commission = Commission {
+            percent: 0.0039,
+            min: 5.0,
+        };
+        let allocation = AllocationConf {
+            capital: 10000.0,
+            max_position: 6000.0,
+            min_position: 1000.0,
+            output_file: Path::new("buy_list.csv"),
+        };
+
+        let positions = allocate(&candidates, &allocation, commission);
+
+        let bought: Vec<(&str, u64)> = positions
+            .iter()
+            .map(|p| (p.instrument.as_str(), p.quantity))
+            .collect();
+        assert_eq!(bought, vec![("ORANGEPL", 597), ("PKOBP", 66)]);
+        assert!(positions.iter().map(|p| p.cost).sum::<f64>() <= allocation.capital);
+        assert!(positions.iter().all(|p| p.cost <= allocation.max_position));
+    }
+
+    fn _candidate(instrument: &str, price: f64, target: f64, stop_loss: f64) -> Candidate {
+        Candidate {
+            instrument: instrument.to_string(),
+            price,
+            target,
+            stop_loss,
+            probability: 0.5,
+            horizon_sessions: 250,
+            dividend_per_share: 0.0,
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use chrono::NaiveDate;
+use polars::prelude::*;
+use shared_contracts::errors::PortfolioError;
+
+use crate::upside::{UpsideConf, source_weight, with_expected_profit};
+
+/// Consensus targets with the suffix of their expected profit columns.
+const TARGETS: [(&str, &str); 5] = [
+    ("target_mean", "_mean"),
+    ("target_median", "_median"),
+    ("target_min", "_min"),
+    ("target_max", "_max"),
+    ("target_weighted", "_weighted"),
+];
+
+/// One row per instrument from the targets of all analysts.
+///
+/// Only the latest target of every `created_by` is counted, a revised recommendation
+/// replaces the previous one. `target_weighted` weights targets with
+/// 0.5^(age / `half_life_days`) times the source weight, the age in days is counted to `as_of`.
+/// `dispersion_pct` is the standard deviation of targets as a percent of their mean.
+pub fn consensus(
+    targets: LazyFrame,
+    market_data: LazyFrame,
+    as_of: NaiveDate,
+    conf: &UpsideConf,
+) -> Result<DataFrame, PortfolioError> {
+    let round = 2;
+    let mode = RoundMode::HalfToEven;
+    let as_of_days = (as_of - NaiveDate::default()).num_days();
+    let age_days =
+        (lit(as_of_days) - col("created_at").cast(DataType::Int64)).cast(DataType::Float64);
+    let age_days = when(age_days.clone().gt(lit(0.0)))
+        .then(age_days)
+        .otherwise(lit(0.0));
+
+    let consensus = targets
+        .sort(["created_at"], Default::default())
+        .unique_stable(
+            Some(cols(["instrument", "created_by"])),
+            UniqueKeepStrategy::Last,
+        )
+        .with_column(
+            (lit(0.5_f64).pow(age_days / lit(conf.half_life_days)) * source_weight(conf))
+                .alias("weight"),
+        )
+        .group_by([col("instrument")])
+        .agg([
+            col("created_by").len().alias("analysts"),
+            col("upside").mean().round(round, mode).alias("target_mean"),
+            col("upside")
+                .median()
+                .round(round, mode)
+                .alias("target_median"),
+            col("upside").min().alias("target_min"),
+            col("upside").max().alias("target_max"),
+            col("upside").std(1).round(round, mode).alias("target_std"),
+            ((col("upside").std(1) / col("upside").mean()) * lit(100))
+                .round(round, mode)
+                .alias("dispersion_pct"),
+            ((col("upside") * col("weight")).sum() / col("weight").sum())
+                .round(round, mode)
+                .alias("target_weighted"),
+            col("created_at").max().alias("latest_target_at"),
+        ])
+        .left_join(
+            market_data.select([
+                col("instrument"),
+                col("closing_price").alias("actual_price"),
+                col("date").alias("price_date"),
+            ]),
+            col("instrument"),
+            col("instrument"),
+        )
+        .collect()?;
+
+    let mut columns = vec![
+        col("instrument"),
+        col("analysts"),
+        col("actual_price"),
+        col("price_date"),
+    ];
+    columns.extend(TARGETS.iter().map(|(target, _)| col(*target)));
+    columns.extend([col("target_std"), col("dispersion_pct")]);
+    columns.extend(
+        TARGETS
+            .iter()
+            .map(|(_, suffix)| col(format!("net_profit{suffix}"))),
+    );
+    columns.extend(
+        TARGETS
+            .iter()
+            .map(|(_, suffix)| col(format!("pct_change{suffix}"))),
+    );
+    columns.push(col("latest_target_at"));
+
+    // Collected per target, one plan with every profit chain is too deep to optimize.
+    let mut consensus = consensus;
+    for (target, suffix) in TARGETS {
+        consensus = with_expected_profit(consensus.lazy(), target, suffix, conf).collect()?;
+    }
+
+    let consensus = consensus
+        .lazy()
+        .select(columns)
+        .sort(["instrument"], Default::default())
+        .collect()?;
+    Ok(consensus)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn consensus_revised_and_old_targets_return_latest_per_analyst_weighted() {
+        let targets = df!(
+            "instrument" => ["ORANGEPL", "ORANGEPL", "ORANGEPL", "ORANGEPL"],
+            "upside" => [8.0, 10.0, 12.0, 11.0],
+            "created_by" => ["mbank", "mbank", "konse", "bossa"],
+            "created_at" => [
+                _date("2025-01-10"),
+                _date("2025-10-10"),
+                _date("2025-07-12"),
+                _date("2025-10-10"),
+            ],
+        )
+        .unwrap();
+        let market_data = df!(
+            "instrument" => ["ORANGEPL"],
+            "closing_price" => [10.0],
+            "date" => [_date("2025-10-10")],
+        )
+        .unwrap();
+        let conf = crate::upside::_test_conf();
+
+        let df = consensus(
+            targets.lazy(),
+            market_data.lazy(),
+            _date("2025-10-10"),
+            &conf,
+        )
+        .unwrap();
+
+        let value = |column: &str| df.column(column).unwrap().f64().unwrap().get(0);
+        assert_eq!(
+            df.column("analysts").unwrap().u32().unwrap().get(0),
+            Some(3)
+        );
+        assert_eq!(value("target_mean"), Some(11.0));
+        assert_eq!(value("target_min"), Some(10.0));
+        // konse's target is one half-life old: (10 + 11 + 12 * 0.5) / 2.5
+        assert_eq!(value("target_weighted"), Some(10.8));
+        assert_eq!(value("net_profit_mean"), Some(810.0));
+    }
+
+    fn _date(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+    }
+}
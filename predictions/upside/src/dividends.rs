@@ -0,0 +1,163 @@
+use std::path::Path;
+
+use polars::prelude::*;
+use util::math::round;
+use util::money::{tax, tax_expr};
+
+/// Dividend forecast of the targets' instruments.
+#[derive(Debug, Clone, Copy)]
+pub struct DividendConf<'a> {
+    /// instrument;amount;ex_date, amount per share.
+    pub forecast_csv: &'a Path,
+}
+
+/// Sum of forecast dividends per share of every target's instrument and `expires_at`,
+/// ex-dates after the instrument's latest session up to the expiry of the target.
+pub fn dividends_per_share(
+    forecast: LazyFrame,
+    targets: LazyFrame,
+    market_data: LazyFrame,
+) -> LazyFrame {
+    let ex_date = col("ex_date").cast(DataType::Date);
+
+    targets
+        .select([col("instrument"), col("expires_at")])
+        .unique_stable(None, UniqueKeepStrategy::First)
+        .join(
+            market_data.select([col("instrument"), col("date")]),
+            [col("instrument")],
+            [col("instrument")],
+            JoinArgs::new(JoinType::Inner),
+        )
+        .join(
+            forecast,
+            [col("instrument")],
+            [col("instrument")],
+            JoinArgs::new(JoinType::Inner),
+        )
+        .filter(
+            ex_date
+                .clone()
+                .gt(col("date"))
+                .and(ex_date.lt_eq(col("expires_at"))),
+        )
+        .group_by([col("instrument"), col("expires_at")])
+        .agg([col("amount")
+            .cast(DataType::Float64)
+            .sum()
+            .alias("dividend_per_share")])
+}
+
+/// Adds the dividends of the bought quantity, taxed at 19% apart from the capital gain,
+/// to `net_profit`. `pct_change` stays the price return, `dividend_return_pct` is the
+/// gross dividend as a percent of the cost basis. Without a forecast the dividends are 0.
+pub(crate) fn with_dividends(lf: LazyFrame, dividends: Option<LazyFrame>) -> LazyFrame {
+    let round = 2;
+    let mode = RoundMode::HalfToEven;
+    let lf = match dividends {
+        Some(dividends) => lf.join(
+            dividends,
+            [col("instrument"), col("expires_at")],
+            [col("instrument"), col("expires_at")],
+            JoinArgs::new(JoinType::Left),
+        ),
+        None => lf.with_column(
+            lit(NULL)
+                .cast(DataType::Float64)
+                .alias("dividend_per_share"),
+        ),
+    };
+
+    lf.with_column(col("dividend_per_share").fill_null(lit(0.0)))
+        .with_column(
+            (col("quantity") * col("dividend_per_share"))
+                .round(round, mode)
+                .alias("dividend_gross"),
+        )
+        .with_column(tax_expr(col("dividend_gross")).alias("dividend_tax"))
+        .with_columns([
+            (col("dividend_gross") - col("dividend_tax"))
+                .round(round, mode)
+                .alias("dividend_net"),
+            ((col("dividend_gross") / col("cost_basis")) * lit(100))
+                .round(round, mode)
+                .alias("dividend_return_pct"),
+        ])
+        .with_column(
+            (col("net_profit") + col("dividend_net"))
+                .round(0, RoundMode::HalfToEven)
+                .alias("net_profit"),
+        )
+}
+
+/// Dividends of `quantity` shares after tax, as `with_dividends` adds them to the net profit.
+pub(crate) fn dividend_net(quantity: u64, dividend_per_share: f64) -> f64 {
+    let gross = round(quantity as f64 * dividend_per_share, 2);
+    round(gross - tax(gross), 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    #[test]
+    fn dividends_per_share_ex_dates_in_and_out_of_horizon_return_sum_within() {
+        let forecast = df!(
+            "instrument" => ["ORANGEPL", "ORANGEPL", "ORANGEPL", "PKOBP"],
+            "amount" => [0.35, 0.4, 0.45, 5.5],
+            "ex_date" => [
+                _date("2025-06-20"),
+                _date("2026-06-19"),
+                _date("2027-06-18"),
+                _date("2026-07-01"),
+            ],
+        )
+        .unwrap();
+        let market_data = df!(
+            "instrument" => ["ORANGEPL", "PKOBP"],
+            "date" => [_date("2025-10-17"), _date("2025-10-17")],
+        )
+        .unwrap();
+        let targets = df!(
+            "instrument" => ["ORANGEPL", "ORANGEPL", "PKOBP", "PEKAO"],
+            "expires_at" => [
+                _date("2026-10-01"),
+                _date("2025-12-17"),
+                _date("2026-10-01"),
+                _date("2026-10-01"),
+            ],
+            "quantity" => [1000.0, 1000.0, 100.0, 10.0],
+            "cost_basis" => [10000.0, 10000.0, 6000.0, 1500.0],
+            "net_profit" => [810.0, 810.0, 810.0, 81.0],
+        )
+        .unwrap();
+
+        let dividends =
+            dividends_per_share(forecast.lazy(), targets.clone().lazy(), market_data.lazy());
+        let df = with_dividends(targets.lazy(), Some(dividends))
+            .collect()
+            .unwrap();
+
+        let column = |name: &str| df.column(name).unwrap().f64().unwrap().to_vec();
+        // The second ORANGEPL target expires before the 2026 ex-date.
+        assert_eq!(
+            column("dividend_per_share"),
+            vec![Some(0.4), Some(0.0), Some(5.5), Some(0.0)]
+        );
+        // ORANGEPL: 400 of dividends, 76 of tax.
+        assert_eq!(
+            column("net_profit"),
+            vec![Some(1134.0), Some(810.0), Some(1256.0), Some(81.0)]
+        );
+        assert_eq!(
+            column("dividend_return_pct"),
+            vec![Some(4.0), Some(0.0), Some(9.17), Some(0.0)]
+        );
+    }
+
+    fn _date(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+    }
+}
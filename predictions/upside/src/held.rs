@@ -0,0 +1,245 @@
+use std::path::Path;
+
+use holdings::positions::Holdings;
+use polars::prelude::*;
+use shared_contracts::errors::PortfolioError;
+use util::money::{Commission, tax_expr};
+
+use crate::upside::{StopLossConf, with_stop_loss};
+
+/// Upside of positions already held.
+#[derive(Debug, Clone, Copy)]
+pub struct HeldConf<'a> {
+    /// Open positions rebuilt from trades, instrument names resolved like the targets.
+    pub holdings: &'a Holdings,
+    /// Trim when the remaining upside to the target is below this, in %.
+    pub trim_upside_pct: f64,
+    /// Trim when the reward to the target is less than this times the risk to the stop-loss.
+    pub min_risk_reward: f64,
+    pub output_file: &'a Path,
+}
+
+/// One row per open position with its latest target, priced at the latest close.
+///
+/// The profit at the target is counted from the average cost of the held quantity,
+/// so its tax includes the tax on gains accrued so far (`accrued_tax`).
+/// `remaining_profit` is what holding to the target adds to selling now, after tax.
+/// Positions without a target or a price have no signal.
+pub fn held_upside(
+    targets: LazyFrame,
+    market_data: LazyFrame,
+    held: &HeldConf,
+    stop_loss: &StopLossConf,
+    commission: Commission,
+) -> Result<DataFrame, PortfolioError> {
+    let positions: Vec<_> = held.holdings.open_positions().collect();
+    let positions = df!(
+        "instrument" => positions.iter().map(|p| p.instrument.as_str()).collect::<Vec<_>>(),
+        "quantity" => positions.iter().map(|p| p.quantity).collect::<Vec<_>>(),
+        "cost_basis" => positions.iter().map(|p| p.cost_basis).collect::<Vec<_>>(),
+    )?;
+
+    let latest_targets = targets
+        .sort(["created_at"], Default::default())
+        .unique_stable(Some(cols(["instrument"])), UniqueKeepStrategy::Last)
+        .select([
+            col("instrument"),
+            col("upside"),
+            col("stop_loss"),
+            col("created_by"),
+            col("created_at"),
+        ]);
+
+    let lf = positions
+        .lazy()
+        .left_join(latest_targets, col("instrument"), col("instrument"))
+        .left_join(
+            market_data.select([
+                col("instrument"),
+                col("closing_price").alias("actual_price"),
+                col("date").alias("price_date"),
+                col("atr"),
+                col("recent_low"),
+            ]),
+            col("instrument"),
+            col("instrument"),
+        );
+    let df = with_stop_loss(lf, stop_loss).collect()?;
+
+    let df = with_held_profit(df.lazy(), commission)
+        .with_column(signal(held).alias("signal"))
+        .select([
+            col("instrument"),
+            col("signal"),
+            col("quantity"),
+            col("average_cost"),
+            col("actual_price"),
+            col("upside"),
+            col("remaining_upside_pct"),
+            col("stop_loss"),
+            col("stop_loss_source"),
+            col("risk_reward"),
+            col("market_value"),
+            col("accrued_gain"),
+            col("accrued_tax"),
+            col("net_profit"),
+            col("remaining_profit"),
+            col("price_date"),
+            col("created_by"),
+            col("created_at"),
+        ])
+        .sort(
+            ["remaining_upside_pct"],
+            SortMultipleOptions::default()
+                .with_order_descending(true)
+                .with_nulls_last(true),
+        )
+        .collect()?;
+    Ok(df)
+}
+
+fn with_held_profit(lf: LazyFrame, commission: Commission) -> LazyFrame {
+    let round = 2;
+    let mode = RoundMode::HalfToEven;
+
+    lf.with_columns([
+        (col("cost_basis") / col("quantity"))
+            .round(round, mode)
+            .alias("average_cost"),
+        (col("quantity") * col("actual_price"))
+            .round(round, mode)
+            .alias("market_value"),
+        (col("quantity") * col("upside"))
+            .round(round, mode)
+            .alias("sale_value"),
+        ((col("upside") / col("actual_price") - lit(1)) * lit(100))
+            .round(round, mode)
+            .alias("remaining_upside_pct"),
+        when((col("actual_price") - col("stop_loss")).gt(lit(0)))
+            .then((col("upside") - col("actual_price")) / (col("actual_price") - col("stop_loss")))
+            .otherwise(lit(NULL))
+            .round(round, mode)
+            .alias("risk_reward"),
+    ])
+    .with_columns([
+        (col("market_value") - commission.expr(col("market_value")) - col("cost_basis"))
+            .round(round, mode)
+            .alias("accrued_gain"),
+        (col("sale_value") - commission.expr(col("sale_value")) - col("cost_basis"))
+            .round(round, mode)
+            .alias("tax_base"),
+    ])
+    .with_columns([
+        tax_expr(col("accrued_gain")).alias("accrued_tax"),
+        tax_expr(col("tax_base")).alias("tax_amount"),
+    ])
+    .with_column(
+        (col("tax_base") - col("tax_amount"))
+            .round(0, RoundMode::HalfToEven)
+            .alias("net_profit"),
+    )
+    .with_column(
+        (col("net_profit") - (col("accrued_gain") - col("accrued_tax")))
+            .round(0, RoundMode::HalfToEven)
+            .alias("remaining_profit"),
+    )
+}
+
+/// hold, or trim with the reason: the remaining upside or the risk/reward is too low.
+fn signal(held: &HeldConf) -> Expr {
+    let low_upside = col("remaining_upside_pct").lt(lit(held.trim_upside_pct));
+    let low_risk_reward = col("risk_reward")
+        .lt(lit(held.min_risk_reward))
+        .fill_null(lit(false));
+
+    when(col("remaining_upside_pct").is_null())
+        .then(lit(NULL).cast(DataType::String))
+        .when(low_upside)
+        .then(lit("trim: upside"))
+        .when(low_risk_reward)
+        .then(lit("trim: risk/reward"))
+        .otherwise(lit("hold"))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use shared_contracts::models::trade_order::{OrderSide, TradeOrder};
+
+    use super::*;
+
+    #[test]
+    fn held_upside_gain_accrued_return_profit_from_average_cost_and_signal() {
+        let holdings = Holdings::from_trades(&[
+            TradeOrder::filled("ORANGEPL", OrderSide::Buy, 1000, 8.0, "2025-03-03"),
+            TradeOrder::filled("PKOBP", OrderSide::Buy, 100, 60.0, "2025-03-03"),
+            TradeOrder::filled("PEKAO", OrderSide::Buy, 10, 150.0, "2025-03-03"),
+        ])
+        .unwrap();
+        let targets = df!(
+            "instrument" => ["ORANGEPL", "PKOBP"],
+            "upside" => [12.0, 70.0],
+            "stop_loss" => [None::<f64>, None],
+            "created_by" => ["mbank", "konse"],
+            "created_at" => [_date("2025-10-01"), _date("2025-10-01")],
+        )
+        .unwrap();
+        let market_data = df!(
+            "instrument" => ["ORANGEPL", "PKOBP", "PEKAO"],
+            "closing_price" => [10.0, 69.0, 160.0],
+            "date" => [_date("2025-10-17"), _date("2025-10-17"), _date("2025-10-17")],
+            "atr" => [Some(0.5), Some(2.0), Some(4.0)],
+            "recent_low" => [Some(9.0), Some(60.0), Some(150.0)],
+        )
+        .unwrap();
+        let held = HeldConf {
+            holdings: &holdings,
+            trim_upside_pct: 5.0,
+            min_risk_reward: 1.0,
+            output_file: Path::new("held.csv"),
+        };
+
+        let df = held_upside(
+            targets.lazy(),
+            market_data.lazy(),
+            &held,
+            &StopLossConf::default(),
+            Commission {
+                percent: 0.0,
+                min: 0.0,
+            },
+        )
+        .unwrap();
+
+        let text = |name: &str| {
+            df.column(name)
+                .unwrap()
+                .str()
+                .unwrap()
+                .into_iter()
+                .map(|v| v.map(str::to_string))
+                .collect::<Vec<_>>()
+        };
+        let column = |name: &str| df.column(name).unwrap().f64().unwrap().to_vec();
+        assert_eq!(
+            text("instrument"),
+            vec![
+                Some("ORANGEPL".into()),
+                Some("PKOBP".into()),
+                Some("PEKAO".into())
+            ]
+        );
+        assert_eq!(
+            text("signal"),
+            vec![Some("hold".into()), Some("trim: upside".into()), None]
+        );
+        // 4000 gain from the 8.00 average cost, 2000 of it accrued at 10.00.
+        assert_eq!(column("net_profit")[0], Some(3240.0));
+        assert_eq!(column("accrued_tax")[0], Some(380.0));
+        assert_eq!(column("remaining_profit")[0], Some(1620.0));
+    }
+
+    fn _date(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+    }
+}
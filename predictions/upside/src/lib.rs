@@ -1 +1,7 @@
+pub mod allocation;
+pub mod consensus;
+pub mod dividends;
+pub mod held;
+pub mod simulation;
+pub mod status;
 pub mod upside;
@@ -0,0 +1,280 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use polars::prelude::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use shared_contracts::errors::PortfolioError;
+use util::math::round;
+use util::money::Commission;
+
+use crate::allocation::{Candidate, net_profit, purchase_cost};
+use crate::dividends::dividend_net;
+
+/// Fewer daily returns than this are too little history to resample.
+const MIN_RETURNS: usize = 20;
+
+/// Monte Carlo paths of the upside candidates.
+#[derive(Debug, Clone, Copy)]
+pub struct SimulationConf<'a> {
+    pub paths: usize,
+    /// Latest sessions whose daily returns are resampled.
+    pub lookback_sessions: usize,
+    /// Same seed, same market data and targets give the same report.
+    pub seed: u64,
+    pub output_file: &'a Path,
+}
+
+/// Outcome distribution of one candidate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Simulation {
+    pub instrument: String,
+    pub quantity: u64,
+    pub price: f64,
+    pub target: f64,
+    pub stop_loss: f64,
+    /// Daily returns the paths were drawn from, no statistics below `MIN_RETURNS`.
+    pub returns: usize,
+    /// Share of paths closing at or above the target before closing at or below the stop, in %.
+    pub target_first_pct: Option<f64>,
+    pub stop_first_pct: Option<f64>,
+    pub net_profit_mean: Option<f64>,
+    pub net_profit_median: Option<f64>,
+    pub net_profit_p5: Option<f64>,
+    pub net_profit_p95: Option<f64>,
+    /// Loss not exceeded in 95% of paths, 0 when even the 5th percentile is a profit.
+    pub value_at_risk_5: Option<f64>,
+}
+
+/// Daily close-to-close log returns of the latest `lookback_sessions` sessions per instrument.
+/// Sessions without trades (zero prices) are skipped.
+pub fn daily_returns(
+    history: LazyFrame,
+    lookback_sessions: usize,
+) -> Result<HashMap<String, Vec<f64>>, PortfolioError> {
+    let df = history
+        .filter(col("closing_price").gt(lit(0)))
+        .sort(["instrument", "date"], Default::default())
+        .with_column(
+            (col("closing_price") / col("closing_price").shift(lit(1)))
+                .over([col("instrument")])
+                .alias("return"),
+        )
+        .filter(col("return").is_not_null())
+        .select([col("instrument"), col("return")])
+        .collect()?;
+
+    let mut returns: HashMap<String, Vec<f64>> = HashMap::new();
+    let instruments = df.column("instrument")?.str()?;
+    let values = df.column("return")?.f64()?;
+    for (instrument, value) in instruments.into_iter().zip(values) {
+        if let (Some(instrument), Some(value)) = (instrument, value) {
+            returns
+                .entry(instrument.to_string())
+                .or_default()
+                .push(value.ln());
+        }
+    }
+    for values in returns.values_mut() {
+        let skip = values.len().saturating_sub(lookback_sessions);
+        values.drain(..skip);
+    }
+    Ok(returns)
+}
+
+/// Resamples historical daily returns (bootstrap) into `paths` price paths per
+/// candidate, over the `horizon_sessions` left to the expiry of its target. A path is
+/// sold at the target on the first close reaching it, at the first close at or below
+/// the stop-loss (gaps through the stop are not filled at the stop), otherwise at the
+/// last close, and the net profit is counted for `investment_amount` like in the upside report.
+/// Forecast dividends are added to the paths not stopped out.
+/// Every instrument draws from its own generator seeded from `seed` and its name,
+/// so adding a candidate does not change the others.
+pub fn simulate(
+    candidates: &[Candidate],
+    returns: &HashMap<String, Vec<f64>>,
+    simulation: &SimulationConf,
+    investment_amount: f64,
+    commission: Commission,
+) -> Vec<Simulation> {
+    candidates
+        .iter()
+        .map(|candidate| {
+            let returns = returns
+                .get(&candidate.instrument)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            simulate_candidate(
+                candidate,
+                returns,
+                simulation,
+                investment_amount,
+                commission,
+            )
+        })
+        .collect()
+}
+
+fn simulate_candidate(
+    candidate: &Candidate,
+    returns: &[f64],
+    simulation: &SimulationConf,
+    investment_amount: f64,
+    commission: Commission,
+) -> Simulation {
+    let quantity = (investment_amount / candidate.price).round().max(0_f64) as u64;
+    let mut result = Simulation {
+        instrument: candidate.instrument.clone(),
+        quantity,
+        price: candidate.price,
+        target: candidate.target,
+        stop_loss: candidate.stop_loss,
+        returns: returns.len(),
+        target_first_pct: None,
+        stop_first_pct: None,
+        net_profit_mean: None,
+        net_profit_median: None,
+        net_profit_p5: None,
+        net_profit_p95: None,
+        value_at_risk_5: None,
+    };
+    if returns.len() < MIN_RETURNS || simulation.paths == 0 || quantity == 0 {
+        return result;
+    }
+
+    let cost = purchase_cost(quantity, candidate.price, commission);
+    let dividends = dividend_net(quantity, candidate.dividend_per_share);
+    let mut rng = ChaCha8Rng::seed_from_u64(simulation.seed ^ fnv1a(&candidate.instrument));
+    let mut profits = Vec::with_capacity(simulation.paths);
+    let (mut at_target, mut at_stop) = (0_usize, 0_usize);
+    for _ in 0..simulation.paths {
+        let mut price = candidate.price;
+        let mut exit = None;
+        let mut stopped = false;
+        for _ in 0..candidate.horizon_sessions {
+            price *= returns[rng.random_range(0..returns.len())].exp();
+            if price >= candidate.target {
+                at_target += 1;
+                exit = Some(candidate.target);
+                break;
+            }
+            if price <= candidate.stop_loss {
+                at_stop += 1;
+                stopped = true;
+                exit = Some(price);
+                break;
+            }
+        }
+        let profit = net_profit(quantity, exit.unwrap_or(price), cost, commission);
+        profits.push(if stopped {
+            profit
+        } else {
+            round(profit + dividends, 2)
+        });
+    }
+    profits.sort_by(f64::total_cmp);
+
+    let paths = simulation.paths as f64;
+    let p5 = quantile(&profits, 0.05);
+    result.target_first_pct = Some(round(at_target as f64 / paths * 100_f64, 2));
+    result.stop_first_pct = Some(round(at_stop as f64 / paths * 100_f64, 2));
+    result.net_profit_mean = Some(round(profits.iter().sum::<f64>() / paths, 2));
+    result.net_profit_median = Some(quantile(&profits, 0.5));
+    result.net_profit_p5 = Some(p5);
+    result.net_profit_p95 = Some(quantile(&profits, 0.95));
+    result.value_at_risk_5 = Some(round((-p5).max(0_f64), 2));
+    result
+}
+
+pub fn report(simulations: &[Simulation]) -> Result<DataFrame, PortfolioError> {
+    let df = df!(
+        "instrument" => simulations.iter().map(|s| s.instrument.as_str()).collect::<Vec<_>>(),
+        "quantity" => simulations.iter().map(|s| s.quantity).collect::<Vec<_>>(),
+        "price" => simulations.iter().map(|s| s.price).collect::<Vec<_>>(),
+        "target" => simulations.iter().map(|s| s.target).collect::<Vec<_>>(),
+        "stop_loss" => simulations.iter().map(|s| s.stop_loss).collect::<Vec<_>>(),
+        "target_first_pct" => simulations.iter().map(|s| s.target_first_pct).collect::<Vec<_>>(),
+        "stop_first_pct" => simulations.iter().map(|s| s.stop_first_pct).collect::<Vec<_>>(),
+        "net_profit_mean" => simulations.iter().map(|s| s.net_profit_mean).collect::<Vec<_>>(),
+        "net_profit_median" => simulations.iter().map(|s| s.net_profit_median).collect::<Vec<_>>(),
+        "net_profit_p5" => simulations.iter().map(|s| s.net_profit_p5).collect::<Vec<_>>(),
+        "net_profit_p95" => simulations.iter().map(|s| s.net_profit_p95).collect::<Vec<_>>(),
+        "value_at_risk_5" => simulations.iter().map(|s| s.value_at_risk_5).collect::<Vec<_>>(),
+        "returns" => simulations.iter().map(|s| s.returns as u64).collect::<Vec<_>>(),
+    )?;
+    Ok(df)
+}
+
+/// Nearest-rank quantile of sorted values.
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    let rank = (q * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Stable across builds and platforms, unlike the std hasher.
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn simulate_same_seed_return_same_distribution() {
+        let orangepl = Candidate {
+            instrument: "ORANGEPL".to_string(),
+            price: 10.0,
+            target: 11.0,
+            stop_loss: 9.0,
+            probability: 0.5,
+            horizon_sessions: 2000,
+            dividend_per_share: 0.0,
+        };
+        let candidates = vec![
+            orangepl.clone(),
+            Candidate {
+                instrument: "PKOBP".to_string(),
+                horizon_sessions: 0,
+                dividend_per_share: 0.5,
+                ..orangepl
+            },
+        ];
+        let daily: Vec<f64> = (0..60)
+            .map(|i| if i % 2 == 0 { 0.02 } else { -0.019 })
+            .collect();
+        let returns = HashMap::from([
+            ("ORANGEPL".to_string(), daily.clone()),
+            ("PKOBP".to_string(), daily),
+        ]);
+        let commission = Commission {
+            percent: 0.0,
+            min: 0.0,
+        };
+        let simulation = SimulationConf {
+            paths: 500,
+            lookback_sessions: 250,
+            seed: 42,
+            output_file: Path::new("simulation.csv"),
+        };
+
+        let first = simulate(&candidates, &returns, &simulation, 10000.0, commission);
+        let second = simulate(&candidates, &returns, &simulation, 10000.0, commission);
+
+        assert_eq!(first, second);
+        let result = &first[0];
+        // Every path ends at the target, +810 after tax, or at the first close below the stop.
+        assert_eq!(
+            result.target_first_pct.unwrap() + result.stop_first_pct.unwrap(),
+            100.0
+        );
+        assert_eq!(result.net_profit_p95, Some(810.0));
+        assert_eq!(result.value_at_risk_5, Some(923.12));
+        // The expired horizon leaves no session, every path is sold at the price
+        // with 500 of dividends, 95 of tax.
+        assert_eq!(first[1].target_first_pct, Some(0.0));
+        assert_eq!(first[1].net_profit_p5, Some(405.0));
+    }
+}
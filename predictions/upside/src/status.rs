@@ -0,0 +1,205 @@
+use std::collections::HashSet;
+
+use chrono::{Months, NaiveDate};
+use polars::prelude::*;
+pub use recommendations::outcome::RecommendationStatus;
+use recommendations::outcome::{Outcome, evaluate, latest_session, read_bars};
+use shared_contracts::errors::PortfolioError;
+
+/// Adds `expires_at`, `recommendation_status`, `entry_price`, `outcome_date`,
+/// `outcome_price` and `outcome_pct` to the targets.
+///
+/// A target expires `horizon_months` (or `default_horizon_months` when empty) after
+/// `created_at`, its status comes from [`recommendations::outcome::evaluate`].
+pub fn with_status(
+    targets: DataFrame,
+    history: LazyFrame,
+    default_horizon_months: u32,
+) -> Result<DataFrame, PortfolioError> {
+    let instruments: HashSet<String> = targets
+        .column("instrument")?
+        .str()?
+        .into_iter()
+        .flatten()
+        .map(str::to_string)
+        .collect();
+    let bars = read_bars(history, &instruments)?;
+    let as_of = latest_session(&bars);
+
+    let horizon_months = match targets.column("horizon_months") {
+        Ok(column) => column
+            .cast(&DataType::UInt32)?
+            .u32()?
+            .into_iter()
+            .collect::<Vec<_>>(),
+        Err(PolarsError::ColumnNotFound(_)) => vec![None; targets.height()],
+        Err(e) => return Err(e.into()),
+    };
+    let target_instruments = targets.column("instrument")?.str()?;
+    let upsides = targets.column("upside")?.cast(&DataType::Float64)?;
+    let upsides = upsides.f64()?;
+    let stops = targets.column("stop_loss")?.f64()?;
+    let created_at: Vec<Option<NaiveDate>> = targets
+        .column("created_at")?
+        .date()?
+        .as_date_iter()
+        .collect();
+
+    let mut expires_at = Vec::with_capacity(targets.height());
+    let mut outcomes = Vec::with_capacity(targets.height());
+    for (i, created_at) in created_at.into_iter().enumerate() {
+        let months = horizon_months[i].unwrap_or(default_horizon_months);
+        let expiry = created_at.and_then(|date| date.checked_add_months(Months::new(months)));
+        let history = target_instruments.get(i).and_then(|i| bars.get(i));
+        let outcome = match (history, created_at, expiry, upsides.get(i), as_of) {
+            (Some(history), Some(created_at), Some(expiry), Some(target), Some(as_of)) => {
+                evaluate(history, created_at, expiry, target, stops.get(i), as_of)
+            }
+            _ => Outcome {
+                status: RecommendationStatus::Active,
+                entry_price: None,
+                date: None,
+                price: None,
+                max_adverse_pct: None,
+                horizon_close: None,
+            },
+        };
+        expires_at.push(expiry);
+        outcomes.push(outcome);
+    }
+
+    let mut targets = targets;
+    targets.with_column(Column::new("expires_at".into(), expires_at))?;
+    targets.with_column(Column::new(
+        "recommendation_status".into(),
+        outcomes
+            .iter()
+            .map(|o| o.status.as_str())
+            .collect::<Vec<_>>(),
+    ))?;
+    targets.with_column(Column::new(
+        "entry_price".into(),
+        outcomes.iter().map(|o| o.entry_price).collect::<Vec<_>>(),
+    ))?;
+    targets.with_column(Column::new(
+        "outcome_date".into(),
+        outcomes.iter().map(|o| o.date).collect::<Vec<_>>(),
+    ))?;
+    targets.with_column(Column::new(
+        "outcome_price".into(),
+        outcomes.iter().map(|o| o.price).collect::<Vec<_>>(),
+    ))?;
+    targets.with_column(Column::new(
+        "outcome_pct".into(),
+        outcomes
+            .iter()
+            .map(|o| {
+                o.entry_price
+                    .zip(o.price)
+                    .map(|(entry, price)| ((price / entry - 1_f64) * 10000_f64).round() / 100_f64)
+            })
+            .collect::<Vec<_>>(),
+    ))?;
+    Ok(targets)
+}
+
+/// Recommendations no longer active (expired, hit or stopped) with their outcome.
+pub fn expired_report(targets: &DataFrame) -> Result<DataFrame, PortfolioError> {
+    let df = targets
+        .clone()
+        .lazy()
+        .filter(col("recommendation_status").neq(lit(RecommendationStatus::Active.as_str())))
+        .select([
+            col("instrument"),
+            col("recommendation_status"),
+            col("upside"),
+            col("stop_loss"),
+            col("entry_price"),
+            col("outcome_date"),
+            col("outcome_price"),
+            col("outcome_pct"),
+            col("created_by"),
+            col("created_at"),
+            col("expires_at"),
+        ])
+        .sort(
+            ["created_at", "instrument"],
+            SortMultipleOptions::default().with_order_descending_multi([true, false]),
+        )
+        .collect()?;
+    Ok(df)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn with_status_hit_stopped_expired_and_active_targets_return_status() {
+        let targets = df!(
+            "instrument" => ["ORANGEPL", "PKOBP", "PEKAO", "ORANGEPL"],
+            "upside" => [11.0, 70.0, 170.0, 12.0],
+            "stop_loss" => [None, Some(55.0), None, None],
+            "created_by" => ["mbank", "konse", "bossa", "mbank"],
+            "created_at" => [
+                _date("2025-01-02"),
+                _date("2025-01-02"),
+                _date("2024-01-02"),
+                _date("2025-03-03"),
+            ],
+            "horizon_months" => [None, None, Some(6_u32), None],
+        )
+        .unwrap();
+        let history = df!(
+            "instrument" => ["ORANGEPL", "ORANGEPL", "ORANGEPL", "PKOBP", "PKOBP", "PEKAO", "PEKAO"],
+            "date" => [
+                _date("2025-01-02"),
+                _date("2025-02-03"),
+                _date("2025-03-03"),
+                _date("2025-01-02"),
+                _date("2025-02-03"),
+                _date("2024-01-02"),
+                _date("2025-03-03"),
+            ],
+            "max_price" => [10.2, 11.1, 10.5, 61.0, 62.0, 151.0, 160.0],
+            "min_price" => [9.8, 10.5, 10.0, 59.0, 54.0, 149.0, 150.0],
+            "closing_price" => [10.0, 10.9, 10.2, 60.0, 56.0, 150.0, 155.0],
+        )
+        .unwrap();
+
+        let df = with_status(targets, history.lazy(), 12).unwrap();
+
+        let statuses: Vec<Option<&str>> = df
+            .column("recommendation_status")
+            .unwrap()
+            .str()
+            .unwrap()
+            .into_iter()
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                Some("hit"),
+                Some("stopped"),
+                Some("expired"),
+                Some("active")
+            ]
+        );
+        let expires_at: Vec<Option<NaiveDate>> = df
+            .column("expires_at")
+            .unwrap()
+            .date()
+            .unwrap()
+            .as_date_iter()
+            .collect();
+        assert_eq!(expires_at[2], Some(_date("2024-07-02")));
+        assert_eq!(
+            df.column("outcome_pct").unwrap().f64().unwrap().to_vec(),
+            vec![Some(10.0), Some(-8.33), Some(0.0), None]
+        );
+    }
+
+    fn _date(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+    }
+}
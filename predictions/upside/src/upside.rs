@@ -1,40 +1,440 @@
-use std::fs::File;
+use std::collections::{BTreeSet, HashMap};
 use std::path::Path;
 
+use chrono::{NaiveDate, Utc};
+use market_store::source::MarketDataSource;
 use polars::prelude::*;
 use shared_contracts::errors::PortfolioError;
+use shared_contracts::models::instrument::InstrumentMaster;
+use util::money::{Commission, tax_expr};
+use util::report::{Report, ReportFormat};
+
+use crate::allocation::{AllocationConf, Position};
+use crate::dividends::{DividendConf, dividends_per_share, with_dividends};
+use crate::held::HeldConf;
+use crate::simulation::SimulationConf;
+use crate::status::{RecommendationStatus, expired_report, with_status};
 
 pub struct UpsideConf<'a> {
     pub upside_csv: &'a Path,
     pub output_file: &'a Path,
-    pub market_data_csv: &'a Path,
+    /// Per instrument consensus of all analysts' targets, not written when `None`.
+    pub consensus_file: Option<&'a Path>,
+    pub market_data: MarketDataSource<'a>,
+    /// Resolves analyst and broker names to market data symbols.
+    pub instruments: &'a InstrumentMaster,
     pub investment_amount: f64,
-    pub commission_percent: f64,
-    pub commission_min: f64,
+    pub commission: Commission,
+    pub format: ReportFormat,
+    /// Fail when any target has no market data price.
+    pub strict: bool,
+    /// Age after which a target counts half in the recency-weighted consensus.
+    pub half_life_days: f64,
+    /// `created_by` -> weight from an analyst track record, twice the source's smoothed
+    /// hit rate. Weighs the consensus and sets the target probability. Sources not listed weigh 1.
+    pub source_weights: &'a HashMap<String, f64>,
+    /// Stop-loss of targets without a `stop_loss` column value.
+    pub stop_loss: StopLossConf,
+    pub rank_by: RankBy,
+    /// Splits one budget across the candidates and writes a buy list, when set.
+    pub allocation: Option<AllocationConf<'a>>,
+    /// Upside of positions already held, written when set.
+    pub held: Option<HeldConf<'a>>,
+    /// Monte Carlo outcome distribution of the candidates, written when set.
+    pub simulation: Option<SimulationConf<'a>>,
+    /// Forecast dividends added to the net profit at the target, when set.
+    pub dividends: Option<DividendConf<'a>>,
+    /// Validity of targets without `horizon_months`, counted from `created_at`.
+    pub default_horizon_months: u32,
+    /// Expired, hit and stopped recommendations with their outcome, not written when `None`.
+    /// Only active recommendations are reported otherwise.
+    pub expired_file: Option<&'a Path>,
+}
+
+/// How the stop-loss of a target without one in the upside csv is set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopLossMethod {
+    /// `atr_multiple` ATRs below the price, the recent low while there is too little history.
+    Atr,
+    /// Lowest session low of the last `low_sessions` sessions.
+    RecentLow,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StopLossConf {
+    pub method: StopLossMethod,
+    pub atr_period: usize,
+    pub atr_multiple: f64,
+    pub low_sessions: usize,
+}
+
+impl Default for StopLossConf {
+    fn default() -> Self {
+        StopLossConf {
+            method: StopLossMethod::Atr,
+            atr_period: 14,
+            atr_multiple: 2.0,
+            low_sessions: 20,
+        }
+    }
 }
 
-pub fn calculate(arg: UpsideConf) -> Result<(), PortfolioError> {
-    let upside_df = read_df_from_csv(arg.upside_csv)?;
-    let market_data_df = read_df_from_csv(arg.market_data_csv)?;
+/// Report order, best candidates first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RankBy {
+    #[default]
+    ExpectedValue,
+    RiskReward,
+}
+
+impl RankBy {
+    fn column(&self) -> &'static str {
+        match self {
+            RankBy::ExpectedValue => "expected_value",
+            RankBy::RiskReward => "risk_reward",
+        }
+    }
+}
+
+/// Probability that a target is reached, used for sources without a track record.
+/// A source weight is its hit rate relative to this.
+pub const DEFAULT_TARGET_PROBABILITY: f64 = 0.5;
+
+/// Suggestions below this Jaro-Winkler similarity are not shown.
+const MIN_SIMILARITY: f64 = 0.8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PriceStatus {
+    Ok,
+    /// No market data for the instrument, the target is not priced.
+    Unmatched,
+    /// Priced with a close older than the latest session in market data.
+    Stale,
+}
+
+impl PriceStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            PriceStatus::Ok => "ok",
+            PriceStatus::Unmatched => "unmatched",
+            PriceStatus::Stale => "stale",
+        }
+    }
+}
+
+/// Target without a current price.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlaggedTarget {
+    pub instrument: String,
+    pub status: PriceStatus,
+    pub price_date: Option<NaiveDate>,
+    /// Closest known instrument name of an unmatched target.
+    pub suggestion: Option<String>,
+}
+
+/// What the caller should show besides the saved reports.
+#[derive(Debug, Clone, Default)]
+pub struct UpsideOutcome {
+    /// Unmatched and stale targets.
+    pub flagged: Vec<FlaggedTarget>,
+    /// Empty without an allocation.
+    pub buy_list: Vec<Position>,
+    /// Expired, hit and stopped recommendations left out of the report.
+    pub expired: usize,
+}
+
+pub fn calculate(arg: UpsideConf) -> Result<UpsideOutcome, PortfolioError> {
+    let upside_df = with_stop_loss_column(util::polars::resolve_instruments(
+        read_df_from_csv(arg.upside_csv)?,
+        arg.instruments,
+    ))?;
+    let history = util::polars::resolve_instruments(arg.market_data.history()?, arg.instruments);
+    let market_data_df = latest_with_stop_levels(history.clone(), &arg.stop_loss).collect()?;
+    let known_names = known_names(&market_data_df, arg.instruments)?;
+    let latest_session = latest_session(&market_data_df)?;
+
+    let targets = with_status(
+        upside_df.collect()?,
+        history.clone(),
+        arg.default_horizon_months,
+    )?;
+    let expired = expired_report(&targets)?;
+    if let Some(expired_file) = arg.expired_file {
+        let mut report = Report::new("Expired Recommendations", expired.clone());
+        util::report::save(&mut report, expired_file, arg.format)?;
+    }
+    let upside_df = targets
+        .lazy()
+        .filter(col("recommendation_status").eq(lit(RecommendationStatus::Active.as_str())));
+
+    let dividends = match &arg.dividends {
+        Some(dividends) => Some(
+            dividends_per_share(
+                util::polars::resolve_instruments(
+                    read_df_from_csv(dividends.forecast_csv)?,
+                    arg.instruments,
+                ),
+                upside_df.clone(),
+                market_data_df.clone().lazy(),
+            )
+            .collect()?
+            .lazy(),
+        ),
+        None => None,
+    };
+
+    let mut df = _calculate(
+        upside_df.clone(),
+        market_data_df.clone().lazy(),
+        dividends,
+        &arg,
+    )?
+    .collect()?;
+    let flagged = flag_targets(&mut df, &known_names, latest_session)?;
 
-    let df = _calculate(upside_df, market_data_df, &arg)?.collect()?;
-    let mut selected_col = df.select([
+    if arg.strict {
+        let unmatched: Vec<&str> = flagged
+            .iter()
+            .filter(|t| t.status == PriceStatus::Unmatched)
+            .map(|t| t.instrument.as_str())
+            .collect();
+        if !unmatched.is_empty() {
+            return Err(PortfolioError::InputError(format!(
+                "no market data price for targets: {}",
+                unmatched.join(", ")
+            )));
+        }
+    }
+
+    let selected_col = df.select([
         "instrument",
+        "expected_value",
+        "risk_reward",
         "net_profit",
         "pct_change",
+        "dividend_return_pct",
+        "dividend_per_share",
+        "dividend_net",
         "upside",
         "actual_price",
+        "stop_loss",
+        "stop_loss_source",
+        "net_profit_stop",
+        "break_even_price",
+        "probability",
+        "price_date",
+        "status",
+        "suggestion",
         "quantity",
         "created_by",
         "created_at",
     ])?;
 
-    let output = arg.output_file;
-    let mut write_to_file = File::create(output)?;
+    let mut report = Report::new("Upside Report", selected_col);
+    util::report::save(&mut report, arg.output_file, arg.format)?;
+
+    if let Some(consensus_file) = arg.consensus_file {
+        let as_of = latest_session.unwrap_or_else(|| Utc::now().date_naive());
+        let consensus = crate::consensus::consensus(
+            upside_df.clone(),
+            market_data_df.clone().lazy(),
+            as_of,
+            &arg,
+        )?;
+        let mut report = Report::new("Upside Consensus", consensus);
+        util::report::save(&mut report, consensus_file, arg.format)?;
+    }
+
+    if let Some(held) = &arg.held {
+        let held_upside = crate::held::held_upside(
+            upside_df.clone(),
+            market_data_df.clone().lazy(),
+            held,
+            &arg.stop_loss,
+            arg.commission,
+        )?;
+        let mut report = Report::new("Held Positions Upside", held_upside);
+        util::report::save(&mut report, held.output_file, arg.format)?;
+    }
+
+    let mut buy_list = Vec::new();
+    if let Some(allocation) = &arg.allocation {
+        let candidates = crate::allocation::candidates(&df)?;
+        buy_list = crate::allocation::allocate(&candidates, allocation, arg.commission);
+        let (positions, summary) = crate::allocation::buy_list(&buy_list, allocation.capital)?;
+        let mut report = Report::new("Buy List", positions).with_summary(summary);
+        util::report::save(&mut report, allocation.output_file, arg.format)?;
+    }
+
+    if let Some(simulation) = &arg.simulation {
+        let candidates = crate::allocation::candidates(&df)?;
+        let returns = crate::simulation::daily_returns(history, simulation.lookback_sessions)?;
+        let simulations = crate::simulation::simulate(
+            &candidates,
+            &returns,
+            simulation,
+            arg.investment_amount,
+            arg.commission,
+        );
+        let mut report = Report::new(
+            "Upside Simulation",
+            crate::simulation::report(&simulations)?,
+        );
+        util::report::save(&mut report, simulation.output_file, arg.format)?;
+    }
+
+    Ok(UpsideOutcome {
+        flagged,
+        buy_list,
+        expired: expired.height(),
+    })
+}
+
+fn latest_session(market_data: &DataFrame) -> Result<Option<NaiveDate>, PortfolioError> {
+    let latest = market_data
+        .column("date")?
+        .date()?
+        .as_date_iter()
+        .flatten()
+        .max();
+    Ok(latest)
+}
+
+/// Market data instruments and every name of the instrument master.
+fn known_names(
+    market_data: &DataFrame,
+    instruments: &InstrumentMaster,
+) -> Result<BTreeSet<String>, PortfolioError> {
+    let mut names: BTreeSet<String> = market_data
+        .column("instrument")?
+        .str()?
+        .into_iter()
+        .flatten()
+        .map(str::to_string)
+        .collect();
+    for instrument in instruments.iter() {
+        names.insert(instrument.symbol.clone());
+        names.extend(instrument.aliases.iter().cloned());
+        names.extend(instrument.full_name.iter().cloned());
+    }
+    Ok(names)
+}
+
+/// Adds `status` and `suggestion` columns and returns the unmatched and stale targets.
+fn flag_targets(
+    df: &mut DataFrame,
+    known_names: &BTreeSet<String>,
+    latest_session: Option<NaiveDate>,
+) -> Result<Vec<FlaggedTarget>, PortfolioError> {
+    let instruments = df.column("instrument")?.str()?.clone();
+    let prices = df.column("actual_price")?.f64()?.clone();
+    let dates: Vec<Option<NaiveDate>> = df.column("price_date")?.date()?.as_date_iter().collect();
+
+    let mut statuses = Vec::with_capacity(df.height());
+    let mut suggestions = Vec::with_capacity(df.height());
+    let mut flagged = Vec::new();
+    for ((instrument, price), price_date) in instruments.iter().zip(prices.iter()).zip(dates) {
+        let instrument = instrument.unwrap_or_default();
+        let status = match (price, price_date) {
+            (None, _) => PriceStatus::Unmatched,
+            (Some(_), Some(date)) if latest_session.is_some_and(|latest| date < latest) => {
+                PriceStatus::Stale
+            }
+            _ => PriceStatus::Ok,
+        };
+        let suggestion = (status == PriceStatus::Unmatched)
+            .then(|| suggest(instrument, known_names))
+            .flatten();
+
+        if status != PriceStatus::Ok {
+            flagged.push(FlaggedTarget {
+                instrument: instrument.to_string(),
+                status,
+                price_date,
+                suggestion: suggestion.clone(),
+            });
+        }
+        statuses.push(status.as_str());
+        suggestions.push(suggestion);
+    }
+
+    df.with_column(Column::new("status".into(), statuses))?;
+    df.with_column(Column::new("suggestion".into(), suggestions))?;
+    Ok(flagged)
+}
 
-    util::polars::default_writer(&mut write_to_file)?.finish(&mut selected_col)?;
+/// Most similar known name, compared case-insensitively.
+fn suggest(instrument: &str, known_names: &BTreeSet<String>) -> Option<String> {
+    let instrument = instrument.trim().to_uppercase();
+    known_names
+        .iter()
+        .map(|name| {
+            (
+                strsim::jaro_winkler(&instrument, &name.to_uppercase()),
+                name,
+            )
+        })
+        .filter(|(similarity, _)| *similarity >= MIN_SIMILARITY)
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, name)| name.clone())
+}
+
+/// Adds an empty `stop_loss` column to upside csv files without one.
+fn with_stop_loss_column(mut upside_df: LazyFrame) -> Result<LazyFrame, PortfolioError> {
+    let stop_loss = if upside_df.collect_schema()?.contains("stop_loss") {
+        col("stop_loss")
+    } else {
+        lit(NULL)
+    };
+    Ok(upside_df.with_column(stop_loss.cast(DataType::Float64).alias("stop_loss")))
+}
 
-    Ok(())
+/// Latest session of every instrument with its `atr` and `recent_low`.
+/// Sessions without trades (zero prices) count neither as lows nor in the ATR,
+/// their true range would be the whole previous close.
+fn latest_with_stop_levels(history: LazyFrame, conf: &StopLossConf) -> LazyFrame {
+    let history = history.sort(["instrument", "date"], Default::default());
+    let latest = col("date").eq(col("date").max().over([col("instrument")]));
+    let atr = history
+        .clone()
+        .filter(col("max_price").gt(lit(0)).and(col("min_price").gt(lit(0))))
+        .with_column(indicators::indicators::atr(conf.atr_period).alias("atr"))
+        .filter(latest.clone())
+        .select([col("instrument"), col("atr")]);
+
+    let low = when(col("min_price").gt(lit(0)))
+        .then(col("min_price"))
+        .otherwise(lit(NULL));
+    history
+        .with_column(
+            low.rolling_min(RollingOptionsFixedWindow {
+                window_size: conf.low_sessions,
+                min_periods: 1,
+                ..Default::default()
+            })
+            .over([col("instrument")])
+            .alias("recent_low"),
+        )
+        .filter(latest)
+        .left_join(atr, col("instrument"), col("instrument"))
+}
+
+/// Weight of the `created_by` source, 1 when not listed in `source_weights`.
+pub(crate) fn source_weight(conf: &UpsideConf) -> Expr {
+    if conf.source_weights.is_empty() {
+        return lit(1_f64);
+    }
+    let (sources, weights): (Vec<&str>, Vec<f64>) = conf
+        .source_weights
+        .iter()
+        .map(|(source, weight)| (source.as_str(), *weight))
+        .unzip();
+    col("created_by").replace_strict(
+        lit(Series::new("source".into(), sources)),
+        lit(Series::new("weight".into(), weights)),
+        Some(lit(1_f64)),
+        Some(DataType::Float64),
+    )
 }
 
 fn read_df_from_csv(csv: &Path) -> Result<LazyFrame, PortfolioError> {
@@ -45,74 +445,317 @@ fn read_df_from_csv(csv: &Path) -> Result<LazyFrame, PortfolioError> {
 fn _calculate(
     upside_df: LazyFrame,
     market_data_df: LazyFrame,
+    dividends: Option<LazyFrame>,
     conf: &UpsideConf,
 ) -> Result<LazyFrame, PortfolioError> {
-    let commission_percent = lit(conf.commission_percent);
-    let commission_min = lit(conf.commission_min);
-    let round = 2;
-    let mode = RoundMode::HalfToEven;
-    let investment_amount = lit(conf.investment_amount);
     let main_df = upside_df
         .lazy()
         .left_join(market_data_df, col("instrument"), col("instrument"))
         .select([
             col("closing_price").alias("actual_price"),
+            col("date").alias("price_date"),
             col("instrument"),
             col("upside"),
+            col("stop_loss"),
+            col("atr"),
+            col("recent_low"),
             col("created_by"),
             col("created_at"),
-        ])
-        .with_columns([(investment_amount / col("actual_price"))
-            .round(0, RoundMode::HalfToEven)
-            .alias("quantity")])
+            col("expires_at"),
+        ]);
+
+    let main_df = with_expected_profit(main_df, "upside", "", conf);
+    let main_df = with_dividends(main_df, dividends);
+    let main_df = with_stop_loss(main_df, &conf.stop_loss);
+    let main_df = with_expected_profit(main_df, "stop_loss", "_stop", conf);
+    let main_df = with_risk_reward(main_df, conf).sort(
+        [
+            conf.rank_by.column(),
+            "instrument",
+            "created_by",
+            "created_at",
+        ],
+        SortMultipleOptions::default()
+            .with_order_descending_multi([true, false, false, false])
+            .with_nulls_last(true),
+    );
+
+    Ok(main_df)
+}
+
+/// Fills `stop_loss` of targets without one from `conf` and tells where it comes
+/// from in `stop_loss_source`: input, atr or recent_low.
+pub(crate) fn with_stop_loss(lf: LazyFrame, conf: &StopLossConf) -> LazyFrame {
+    let atr_stop =
+        (col("actual_price") - lit(conf.atr_multiple) * col("atr")).round(2, RoundMode::HalfToEven);
+    let mut candidates = vec![(col("stop_loss"), "input")];
+    if conf.method == StopLossMethod::Atr {
+        candidates.push((atr_stop, "atr"));
+    }
+    candidates.push((col("recent_low"), "recent_low"));
+
+    let source = candidates.iter().rev().fold(
+        lit(NULL).cast(DataType::String),
+        |otherwise, (stop, name)| {
+            when(stop.clone().is_not_null())
+                .then(lit(*name))
+                .otherwise(otherwise)
+        },
+    );
+    let stops: Vec<Expr> = candidates.into_iter().map(|(stop, _)| stop).collect();
+
+    lf.with_columns([
+        coalesce(&stops).alias("stop_loss"),
+        source.alias("stop_loss_source"),
+    ])
+}
+
+/// Adds `risk_reward`, reward to risk per share, empty when the price is already at or
+/// below the stop-loss, `break_even_price`, the sale price
+/// covering the cost basis and the sell commission, `probability` of the target and
+/// `expected_value`, net profit at the target or net result at the stop-loss weighted
+/// with that probability.
+fn with_risk_reward(lf: LazyFrame, conf: &UpsideConf) -> LazyFrame {
+    let round = 2;
+    let mode = RoundMode::HalfToEven;
+    let risk = col("actual_price") - col("stop_loss");
+    let reward = col("upside") - col("actual_price");
+    // Tax is not due at break-even, only the sell commission, a percentage or the minimum.
+    let by_percent = col("cost_basis") / lit(1_f64 - conf.commission.percent);
+    let by_minimum = col("cost_basis") + lit(conf.commission.min);
+
+    lf.with_columns([
+        when(risk.clone().gt(lit(0)))
+            .then(reward / risk)
+            .otherwise(lit(NULL))
+            .round(round, mode)
+            .alias("risk_reward"),
+        (when(by_percent.clone().gt_eq(by_minimum.clone()))
+            .then(by_percent)
+            .otherwise(by_minimum)
+            / col("quantity"))
+        .round(round, mode)
+        .alias("break_even_price"),
+        (source_weight(conf) * lit(DEFAULT_TARGET_PROBABILITY))
+            .round(round, mode)
+            .alias("probability"),
+    ])
+    .with_column(
+        (col("probability") * col("net_profit")
+            + (lit(1_f64) - col("probability")) * col("net_profit_stop"))
+        .round(0, RoundMode::HalfToEven)
+        .alias("expected_value"),
+    )
+}
+
+/// Adds the expected result of buying for the investment amount at `actual_price`
+/// and selling at the `target` column, after commissions and tax. Every added
+/// column (`quantity`, `net_profit`, `pct_change`, ...) is named with `suffix`.
+pub(crate) fn with_expected_profit(
+    lf: LazyFrame,
+    target: &str,
+    suffix: &str,
+    conf: &UpsideConf,
+) -> LazyFrame {
+    let round = 2;
+    let mode = RoundMode::HalfToEven;
+    let investment_amount = lit(conf.investment_amount);
+    let c = |name: &str| col(format!("{name}{suffix}"));
+    let name = |name: &str| format!("{name}{suffix}");
+
+    lf.with_columns([(investment_amount / col("actual_price"))
+        .round(0, RoundMode::HalfToEven)
+        .alias(name("quantity"))])
         .with_columns([
-            (col("quantity") * col("actual_price"))
+            (c("quantity") * col("actual_price"))
                 .round(round, mode)
-                .alias("purchase_value"),
-            (col("quantity") * col("upside"))
+                .alias(name("purchase_value")),
+            (c("quantity") * col(target))
                 .round(round, mode)
-                .alias("sale_value"),
+                .alias(name("sale_value")),
         ])
         .with_columns([
-            when((col("purchase_value") * commission_percent.clone()).gt(commission_min.clone()))
-                .then(col("purchase_value") * commission_percent.clone())
-                .otherwise(commission_min.clone())
-                .round(round, mode)
-                .alias("buy_commission"),
-            when((col("sale_value") * commission_percent.clone()).gt(commission_min.clone()))
-                .then(col("sale_value") * commission_percent.clone())
-                .otherwise(commission_min.clone())
-                .round(round, mode)
-                .alias("sell_commission"),
+            conf.commission
+                .expr(c("purchase_value"))
+                .alias(name("buy_commission")),
+            conf.commission
+                .expr(c("sale_value"))
+                .alias(name("sell_commission")),
         ])
         .with_columns([
-            (col("purchase_value") + col("buy_commission"))
+            (c("purchase_value") + c("buy_commission"))
                 .round(round, mode)
-                .alias("cost_basis"),
-            (col("sale_value") - col("sell_commission"))
+                .alias(name("cost_basis")),
+            (c("sale_value") - c("sell_commission"))
                 .round(round, mode)
-                .alias("net_proceeds"),
+                .alias(name("net_proceeds")),
         ])
-        .with_columns([(col("net_proceeds") - col("cost_basis"))
-            .round(round, mode)
-            .alias("tax_base")])
-        .with_columns([(col("tax_base") * lit(0.19))
+        .with_columns([(c("net_proceeds") - c("cost_basis"))
             .round(round, mode)
-            .alias("tax_amount")])
+            .alias(name("tax_base"))])
+        .with_columns([tax_expr(c("tax_base")).alias(name("tax_amount"))])
         .with_column(
-            (col("net_proceeds") - col("cost_basis") - col("tax_amount"))
+            (c("net_proceeds") - c("cost_basis") - c("tax_amount"))
                 .round(0, RoundMode::HalfToEven)
-                .alias("net_profit"),
+                .alias(name("net_profit")),
         )
         .with_column(
-            ((col("tax_base") / col("cost_basis")) * lit(100))
+            ((c("tax_base") / c("cost_basis")) * lit(100))
                 .round(round, mode)
-                .alias("pct_change"),
+                .alias(name("pct_change")),
+        )
+}
+
+/// Conf of the module tests: 10000 per target without commission, no optional reports.
+#[cfg(test)]
+pub(crate) fn _test_conf() -> UpsideConf<'static> {
+    use std::sync::LazyLock;
+
+    static INSTRUMENTS: LazyLock<InstrumentMaster> = LazyLock::new(InstrumentMaster::default);
+    static SOURCE_WEIGHTS: LazyLock<HashMap<String, f64>> = LazyLock::new(HashMap::new);
+    UpsideConf {
+        upside_csv: Path::new("upside.csv"),
+        output_file: Path::new("upside_report.csv"),
+        consensus_file: None,
+        market_data: MarketDataSource::Csv(Path::new("market_data.csv")),
+        instruments: &INSTRUMENTS,
+        investment_amount: 10000.0,
+        commission: Commission {
+            percent: 0.0,
+            min: 0.0,
+        },
+        format: ReportFormat::Csv,
+        strict: false,
+        half_life_days: 90.0,
+        source_weights: &SOURCE_WEIGHTS,
+        stop_loss: StopLossConf::default(),
+        rank_by: RankBy::ExpectedValue,
+        allocation: None,
+        held: None,
+        simulation: None,
+        dividends: None,
+        default_horizon_months: 12,
+        expired_file: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn calculate_stop_loss_input_and_atr_return_ranked_by_expected_value() {
+        let upside_df = df!(
+            "instrument" => ["ORANGEPL", "PKOBP"],
+            "upside" => [11.0, 70.0],
+            "stop_loss" => [Some(9.5), None],
+            "created_by" => ["mbank", "konse"],
+            "created_at" => [_date("2025-10-01"), _date("2025-10-01")],
+            "expires_at" => [_date("2026-10-01"), _date("2026-10-01")],
+        )
+        .unwrap();
+        let market_data_df = df!(
+            "instrument" => ["ORANGEPL", "PKOBP"],
+            "closing_price" => [10.0, 60.0],
+            "date" => [_date("2025-10-17"), _date("2025-10-17")],
+            "atr" => [Some(0.4), Some(2.5)],
+            "recent_low" => [Some(9.0), Some(55.0)],
         )
-        .sort(
-            ["instrument", "created_by", "created_at"],
-            Default::default(),
+        .unwrap();
+        let conf = UpsideConf {
+            investment_amount: 6000.0,
+            commission: Commission {
+                percent: 0.0,
+                min: 5.0,
+            },
+            .._test_conf()
+        };
+
+        let df = _calculate(upside_df.lazy(), market_data_df.lazy(), None, &conf)
+            .unwrap()
+            .collect()
+            .unwrap();
+
+        let column = |name: &str| df.column(name).unwrap().f64().unwrap().to_vec();
+        let text = |name: &str| {
+            df.column(name)
+                .unwrap()
+                .str()
+                .unwrap()
+                .into_iter()
+                .map(|v| v.map(str::to_string))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            text("instrument"),
+            vec![Some("PKOBP".into()), Some("ORANGEPL".into())]
+        );
+        assert_eq!(
+            text("stop_loss_source"),
+            vec![Some("atr".into()), Some("input".into())]
         );
+        // PKOBP: 100 shares at 60, profit 802 at the target, loss 413 at the 60 - 2 * 2.5 stop.
+        assert_eq!(column("stop_loss"), vec![Some(55.0), Some(9.5)]);
+        assert_eq!(column("risk_reward"), vec![Some(2.0), Some(2.0)]);
+        assert_eq!(column("break_even_price"), vec![Some(60.1), Some(10.02)]);
+        assert_eq!(column("expected_value"), vec![Some(194.0), Some(114.0)]);
+    }
 
-    Ok(main_df)
+    #[test]
+    fn latest_with_stop_levels_no_trade_session_return_atr_of_traded_sessions() {
+        let history = |no_trade: bool| {
+            let mut df = df!(
+                "instrument" => ["ORANGEPL"; 3],
+                "date" => [_date("2025-10-14"), _date("2025-10-15"), _date("2025-10-17")],
+                "max_price" => [10.2, 10.4, 10.6],
+                "min_price" => [9.8, 9.9, 10.0],
+                "closing_price" => [10.0, 10.2, 10.4],
+            )
+            .unwrap();
+            if no_trade {
+                let session = df!(
+                    "instrument" => ["ORANGEPL"],
+                    "date" => [_date("2025-10-16")],
+                    "max_price" => [0.0],
+                    "min_price" => [0.0],
+                    "closing_price" => [10.2],
+                )
+                .unwrap();
+                df = df.vstack(&session).unwrap();
+            }
+            df.lazy()
+        };
+        let conf = StopLossConf {
+            atr_period: 2,
+            ..Default::default()
+        };
+        let atr = |history: LazyFrame| {
+            let df = latest_with_stop_levels(history, &conf).collect().unwrap();
+            df.column("atr").unwrap().f64().unwrap().get(0)
+        };
+
+        let traded = atr(history(false));
+
+        assert!(traded.is_some_and(|atr| atr < 1.0));
+        assert_eq!(atr(history(true)), traded);
+    }
+
+    #[test]
+    fn suggest_typo_return_closest_known_name() {
+        let known_names = BTreeSet::from([
+            "ORANGEPL".to_string(),
+            "PKOBP".to_string(),
+            "PEKAO".to_string(),
+        ]);
+
+        assert_eq!(
+            suggest("orangpl", &known_names),
+            Some("ORANGEPL".to_string())
+        );
+        assert_eq!(suggest("XTB", &known_names), None);
+    }
+
+    fn _date(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+    }
 }
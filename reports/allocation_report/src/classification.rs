@@ -0,0 +1,110 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::Deserialize;
+use shared_contracts::errors::PortfolioError;
+use shared_contracts::models::instrument::InstrumentMaster;
+
+/// Group of instruments missing from the classification file or without a value.
+pub const UNCLASSIFIED: &str = "unclassified";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dimension {
+    Sector,
+    Industry,
+    Country,
+    AssetClass,
+}
+
+impl Dimension {
+    pub const ALL: [Dimension; 4] = [
+        Dimension::Sector,
+        Dimension::Industry,
+        Dimension::Country,
+        Dimension::AssetClass,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Dimension::Sector => "sector",
+            Dimension::Industry => "industry",
+            Dimension::Country => "country",
+            Dimension::AssetClass => "asset_class",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Classification {
+    pub sector: Option<String>,
+    pub industry: Option<String>,
+    pub country: Option<String>,
+    pub asset_class: Option<String>,
+}
+
+impl Classification {
+    pub fn get(&self, dimension: Dimension) -> Option<&str> {
+        let value = match dimension {
+            Dimension::Sector => &self.sector,
+            Dimension::Industry => &self.industry,
+            Dimension::Country => &self.country,
+            Dimension::AssetClass => &self.asset_class,
+        };
+        value.as_deref().map(str::trim).filter(|v| !v.is_empty())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ClassificationRow {
+    instrument: String,
+    #[serde(default)]
+    sector: Option<String>,
+    #[serde(default)]
+    industry: Option<String>,
+    #[serde(default)]
+    country: Option<String>,
+    #[serde(default)]
+    asset_class: Option<String>,
+}
+
+/// User maintained classification csv, `instrument;sector;industry;country;asset_class`.
+/// Only `instrument` is required, the same file serves as the asset classes csv of the rebalance.
+#[derive(Debug, Clone, Default)]
+pub struct Classifications {
+    instruments: BTreeMap<String, Classification>,
+}
+
+impl Classifications {
+    /// Instrument names are resolved to market data symbols.
+    pub fn load(csv: &Path, instruments: &InstrumentMaster) -> Result<Self, PortfolioError> {
+        let mut rdr = util::csv::default_reader(csv)?;
+        let mut classifications = BTreeMap::new();
+        for result in rdr.deserialize() {
+            let row: ClassificationRow = result?;
+            classifications.insert(
+                instruments.symbol(row.instrument.trim()).to_string(),
+                Classification {
+                    sector: row.sector,
+                    industry: row.industry,
+                    country: row.country,
+                    asset_class: row.asset_class,
+                },
+            );
+        }
+        Ok(Classifications {
+            instruments: classifications,
+        })
+    }
+
+    pub fn new(instruments: BTreeMap<String, Classification>) -> Self {
+        Classifications { instruments }
+    }
+
+    /// Group of the instrument in the dimension, [`UNCLASSIFIED`] when not known.
+    pub fn group(&self, instrument: &str, dimension: Dimension) -> &str {
+        self.instruments
+            .get(instrument)
+            .and_then(|c| c.get(dimension))
+            .unwrap_or(UNCLASSIFIED)
+    }
+}
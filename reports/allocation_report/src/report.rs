@@ -0,0 +1,336 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use holdings::positions::Holdings;
+use market_store::source::MarketDataSource;
+use polars::prelude::*;
+use shared_contracts::errors::PortfolioError;
+use shared_contracts::models::instrument::InstrumentMaster;
+use util::math::round;
+use util::report::{Report, ReportFormat};
+
+use crate::classification::{Classifications, Dimension};
+
+pub struct AllocationConf<'a> {
+    pub trade_orders_csv: &'a Path,
+    pub market_data: MarketDataSource<'a>,
+    /// Resolves trade, classification and market data names to one symbol.
+    pub instruments: &'a InstrumentMaster,
+    pub classifications: &'a Classifications,
+    pub limits: Limits,
+    /// Positions summed in the top-N concentration.
+    pub top_n: usize,
+    pub output_file: &'a Path,
+    /// Weights by sector, industry, country and asset class.
+    pub groups_file: &'a Path,
+    pub format: ReportFormat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limits {
+    /// Largest weight of a single position, in %.
+    pub max_position_pct: f64,
+    /// Largest weight of a sector, in %.
+    pub max_sector_pct: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PositionWeight {
+    pub instrument: String,
+    pub quantity: i64,
+    pub price: f64,
+    pub value: f64,
+    pub weight_pct: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupWeight {
+    pub dimension: Dimension,
+    pub group: String,
+    pub positions: usize,
+    pub value: f64,
+    pub weight_pct: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Allocation {
+    /// Largest position first.
+    pub positions: Vec<PositionWeight>,
+    /// Largest group of every dimension first.
+    pub groups: Vec<GroupWeight>,
+    pub total_value: f64,
+    pub top_n: usize,
+    pub top_n_pct: f64,
+    /// Sum of squared position weights (as fractions), 1 for a single position.
+    pub herfindahl: f64,
+    pub sector_herfindahl: f64,
+    pub warnings: Vec<String>,
+}
+
+pub fn calculate_and_save(conf: AllocationConf) -> Result<Allocation, PortfolioError> {
+    let holdings = Holdings::load(conf.trade_orders_csv, conf.instruments)?;
+    let prices = conf.market_data.latest_closes(conf.instruments)?;
+
+    let mut positions = Vec::new();
+    for position in holdings.open_positions() {
+        let price = prices.get(&position.instrument).ok_or_else(|| {
+            PortfolioError::InputError(format!(
+                "no market data price for held {}",
+                position.instrument
+            ))
+        })?;
+        positions.push((position.instrument.clone(), position.quantity, *price));
+    }
+
+    let allocation = allocation(&positions, conf.classifications, &conf.limits, conf.top_n);
+
+    let mut report = Report::new(
+        "Allocation",
+        positions_data_frame(&allocation, conf.classifications)?,
+    )
+    .with_summary(summary_data_frame(&allocation)?);
+    util::report::save(&mut report, conf.output_file, conf.format)?;
+    let mut report = Report::new(
+        "Allocation by group",
+        groups_data_frame(&allocation, &conf.limits)?,
+    );
+    util::report::save(&mut report, conf.groups_file, conf.format)?;
+    Ok(allocation)
+}
+
+/// Weights of the `(instrument, quantity, price)` positions overall and by every
+/// classification dimension, with the concentration measures and limit warnings.
+pub fn allocation(
+    positions: &[(String, i64, f64)],
+    classifications: &Classifications,
+    limits: &Limits,
+    top_n: usize,
+) -> Allocation {
+    let total_value: f64 = positions.iter().map(|(_, q, p)| *q as f64 * p).sum();
+    let weight_pct = |value: f64| {
+        if total_value > 0_f64 {
+            value / total_value * 100_f64
+        } else {
+            0_f64
+        }
+    };
+
+    let mut weights: Vec<PositionWeight> = positions
+        .iter()
+        .map(|(instrument, quantity, price)| {
+            let value = *quantity as f64 * price;
+            PositionWeight {
+                instrument: instrument.clone(),
+                quantity: *quantity,
+                price: *price,
+                value: round(value, 2),
+                weight_pct: round(weight_pct(value), 2),
+            }
+        })
+        .collect();
+    weights.sort_by(|a, b| b.value.total_cmp(&a.value));
+
+    let mut groups = Vec::new();
+    for dimension in Dimension::ALL {
+        let mut by_group: BTreeMap<&str, (usize, f64)> = BTreeMap::new();
+        for (instrument, quantity, price) in positions {
+            let group = by_group
+                .entry(classifications.group(instrument, dimension))
+                .or_default();
+            group.0 += 1;
+            group.1 += *quantity as f64 * price;
+        }
+        let mut dimension_groups: Vec<GroupWeight> = by_group
+            .into_iter()
+            .map(|(group, (count, value))| GroupWeight {
+                dimension,
+                group: group.to_string(),
+                positions: count,
+                value: round(value, 2),
+                weight_pct: round(weight_pct(value), 2),
+            })
+            .collect();
+        dimension_groups.sort_by(|a, b| b.value.total_cmp(&a.value));
+        groups.extend(dimension_groups);
+    }
+
+    let sectors = groups.iter().filter(|g| g.dimension == Dimension::Sector);
+
+    let mut warnings = Vec::new();
+    for position in weights
+        .iter()
+        .filter(|p| p.weight_pct > limits.max_position_pct)
+    {
+        warnings.push(format!(
+            "{} is {:.2}% of the portfolio, over the {}% position limit",
+            position.instrument, position.weight_pct, limits.max_position_pct
+        ));
+    }
+    for sector in sectors.filter(|g| g.weight_pct > limits.max_sector_pct) {
+        warnings.push(format!(
+            "sector {} is {:.2}% of the portfolio, over the {}% sector limit",
+            sector.group, sector.weight_pct, limits.max_sector_pct
+        ));
+    }
+
+    Allocation {
+        top_n,
+        top_n_pct: round(
+            weights
+                .iter()
+                .take(top_n)
+                .map(|p| weight_pct(p.value))
+                .sum(),
+            2,
+        ),
+        herfindahl: herfindahl(positions.iter().map(|(_, q, p)| weight_pct(*q as f64 * p))),
+        sector_herfindahl: herfindahl(
+            groups
+                .iter()
+                .filter(|g| g.dimension == Dimension::Sector)
+                .map(|g| weight_pct(g.value)),
+        ),
+        positions: weights,
+        groups,
+        total_value: round(total_value, 2),
+        warnings,
+    }
+}
+
+fn positions_data_frame(
+    allocation: &Allocation,
+    classifications: &Classifications,
+) -> Result<DataFrame, PortfolioError> {
+    let positions = &allocation.positions;
+    let mut columns = vec![
+        Column::new(
+            "instrument".into(),
+            positions
+                .iter()
+                .map(|p| p.instrument.as_str())
+                .collect::<Vec<_>>(),
+        ),
+        Column::new(
+            "quantity".into(),
+            positions.iter().map(|p| p.quantity).collect::<Vec<_>>(),
+        ),
+        Column::new(
+            "price".into(),
+            positions.iter().map(|p| p.price).collect::<Vec<_>>(),
+        ),
+        Column::new(
+            "value".into(),
+            positions.iter().map(|p| p.value).collect::<Vec<_>>(),
+        ),
+        Column::new(
+            "weight_pct".into(),
+            positions.iter().map(|p| p.weight_pct).collect::<Vec<_>>(),
+        ),
+    ];
+    for dimension in Dimension::ALL {
+        columns.push(Column::new(
+            dimension.as_str().into(),
+            positions
+                .iter()
+                .map(|p| classifications.group(&p.instrument, dimension))
+                .collect::<Vec<_>>(),
+        ));
+    }
+    Ok(DataFrame::new(columns)?)
+}
+
+fn groups_data_frame(
+    allocation: &Allocation,
+    limits: &Limits,
+) -> Result<DataFrame, PortfolioError> {
+    let groups = &allocation.groups;
+    let df = df!(
+        "dimension" => groups.iter().map(|g| g.dimension.as_str()).collect::<Vec<_>>(),
+        "group" => groups.iter().map(|g| g.group.as_str()).collect::<Vec<_>>(),
+        "positions" => groups.iter().map(|g| g.positions as u64).collect::<Vec<_>>(),
+        "value" => groups.iter().map(|g| g.value).collect::<Vec<_>>(),
+        "weight_pct" => groups.iter().map(|g| g.weight_pct).collect::<Vec<_>>(),
+        "over_limit" => groups
+            .iter()
+            .map(|g| g.dimension == Dimension::Sector && g.weight_pct > limits.max_sector_pct)
+            .collect::<Vec<_>>(),
+    )?;
+    Ok(df)
+}
+
+fn summary_data_frame(allocation: &Allocation) -> Result<DataFrame, PortfolioError> {
+    let effective_positions = if allocation.herfindahl > 0_f64 {
+        round(1_f64 / allocation.herfindahl, 2)
+    } else {
+        0_f64
+    };
+    let df = df!(
+        "total_value" => [allocation.total_value],
+        "positions" => [allocation.positions.len() as u64],
+        "top_n" => [allocation.top_n as u64],
+        "top_n_pct" => [allocation.top_n_pct],
+        "herfindahl" => [allocation.herfindahl],
+        "effective_positions" => [effective_positions],
+        "sector_herfindahl" => [allocation.sector_herfindahl],
+        "warnings" => [allocation.warnings.len() as u64],
+    )?;
+    Ok(df)
+}
+
+fn herfindahl(weights_pct: impl Iterator<Item = f64>) -> f64 {
+    round(weights_pct.map(|w| (w / 100_f64).powi(2)).sum(), 4)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::classification::Classification;
+
+    use super::*;
+
+    #[test]
+    fn allocation_positions_and_sectors_return_weights_concentration_and_warnings() {
+        let positions = vec![
+            ("PKOBP".to_string(), 100, 60.0),
+            ("PEKAO".to_string(), 10, 150.0),
+            ("ORANGEPL".to_string(), 300, 5.0),
+        ];
+        let classifications = Classifications::new(BTreeMap::from([
+            ("PKOBP".to_string(), _classification("banks")),
+            ("PEKAO".to_string(), _classification("banks")),
+        ]));
+        let limits = Limits {
+            max_position_pct: 50.0,
+            max_sector_pct: 70.0,
+        };
+
+        let allocation = allocation(&positions, &classifications, &limits, 2);
+
+        // 6000 + 1500 + 1500 of 9000
+        assert_eq!(allocation.total_value, 9000.0);
+        assert_eq!(allocation.positions[0].weight_pct, 66.67);
+        assert_eq!(allocation.top_n_pct, 83.33);
+        assert_eq!(
+            allocation.herfindahl,
+            round((6.0_f64 / 9.0).powi(2) + 2.0 * (1.5_f64 / 9.0).powi(2), 4)
+        );
+        let sectors: Vec<(&str, usize, f64)> = allocation
+            .groups
+            .iter()
+            .filter(|g| g.dimension == Dimension::Sector)
+            .map(|g| (g.group.as_str(), g.positions, g.weight_pct))
+            .collect();
+        assert_eq!(
+            sectors,
+            vec![("banks", 2, 83.33), ("unclassified", 1, 16.67)]
+        );
+        assert_eq!(allocation.warnings.len(), 2);
+    }
+
+    fn _classification(sector: &str) -> Classification {
+        Classification {
+            sector: Some(sector.to_string()),
+            country: Some("PL".to_string()),
+            ..Default::default()
+        }
+    }
+}
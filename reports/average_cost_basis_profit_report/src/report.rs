@@ -1,30 +1,40 @@
-use std::fs::File;
 use std::path::Path;
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use polars::prelude::*;
+use shared_contracts::models::instrument::InstrumentMaster;
 use shared_contracts::models::report::{Summary, TradePeriod};
 use shared_contracts::{errors::PortfolioError, models::trade_order::OrderSide};
-use std::io::Write;
+use trading_calendar::calendar::TradingCalendar;
+use util::report::{Report, ReportFormat};
 
-pub fn calculate_and_save(input: &Path, output: &Path) -> Result<(), PortfolioError> {
-    let df_csv = util::polars::default_lazy_reder(input)?.finish()?;
+pub fn calculate_and_save(
+    input: &Path,
+    output: &Path,
+    format: ReportFormat,
+    instruments: &InstrumentMaster,
+) -> Result<(), PortfolioError> {
+    let df_csv = util::polars::resolve_instruments(
+        util::polars::default_lazy_reder(input)?.finish()?,
+        instruments,
+    );
 
     let (aggregate_df, summary_df) = create_data_frame(df_csv)?;
 
-    let mut file = File::create(output)?;
+    let summary = map_summary(summary_df)?;
 
-    save_metadata(&mut file, summary_df)?;
-    save_aggregated_instruments(&mut file, aggregate_df)?;
+    let mut report = Report::new("Profit Report", aggregated_instruments(aggregate_df)?)
+        .with_summary(summary_data_frame(&summary)?);
+
+    util::report::save(&mut report, output, format)?;
     Ok(())
 }
-fn save_aggregated_instruments(
-    output: &mut File,
-    aggregate_df: LazyFrame,
-) -> Result<(), PortfolioError> {
-    let aggregate_res = aggregate_df.collect()?;
 
-    let mut selected_col = aggregate_res.select([
+fn aggregated_instruments(aggregate_df: LazyFrame) -> Result<DataFrame, PortfolioError> {
+    let mut aggregate_res = aggregate_df.collect()?;
+    with_settlement(&mut aggregate_res, &TradingCalendar::gpw())?;
+
+    let selected_col = aggregate_res.select([
         "instrument",
         "net_profit",
         "pct_change",
@@ -32,32 +42,61 @@ fn save_aggregated_instruments(
         "tax_amount",
         "buy_quantity",
         "sell_quantity",
+        "settlement_date",
         "days_to_settle",
     ])?;
 
-    util::polars::default_writer(output)?.finish(&mut selected_col)?;
+    Ok(selected_col)
+}
+
+/// Adds the T+2 `settlement_date` of the last order and `days_to_settle`,
+/// calendar days from the first order to that settlement.
+fn with_settlement(df: &mut DataFrame, calendar: &TradingCalendar) -> Result<(), PortfolioError> {
+    let starts = trade_dates(df, "trade_period_start")?;
+    let ends = trade_dates(df, "trade_period_end")?;
 
+    let mut settlement_dates = Vec::with_capacity(df.height());
+    let mut days_to_settle = Vec::with_capacity(df.height());
+    for (start, end) in starts.into_iter().zip(ends) {
+        let settlement = end.map(|end| calendar.settlement_date(end));
+        settlement_dates.push(settlement);
+        days_to_settle.push(
+            start
+                .zip(settlement)
+                .map(|(start, settlement)| (settlement - start).num_days()),
+        );
+    }
+
+    df.with_column(Column::new("settlement_date".into(), settlement_dates))?;
+    df.with_column(Column::new("days_to_settle".into(), days_to_settle))?;
     Ok(())
 }
 
-fn save_metadata(output: &mut File, summary_df: LazyFrame) -> Result<(), PortfolioError> {
-    let summary = map_summary(summary_df)?;
+/// Session dates of UTC submission times. GPW sessions run within one UTC day.
+fn trade_dates(df: &DataFrame, column: &str) -> Result<Vec<Option<NaiveDate>>, PortfolioError> {
+    let dates = df
+        .column(column)?
+        .datetime()?
+        .cast_time_unit(TimeUnit::Nanoseconds)
+        .cast(&DataType::Int64)?
+        .i64()?
+        .iter()
+        .map(|ns| ns.map(|ns| DateTime::<Utc>::from_timestamp_nanos(ns).date_naive()))
+        .collect();
+    Ok(dates)
+}
 
-    let metadata = format!(
-        "--- Profit Report ---\n
-        Trade from {} to {}\n
-        Commission: {}\n
-        Tax: {}\n
-        Net Profit: {}\n\n",
-        summary.trade_period.start,
-        summary.trade_period.end,
-        summary.commission_total,
-        summary.tax_amount_total,
-        summary.net_profit_total
-    );
-    writeln!(output, "{}", metadata)?;
-    Ok(())
+fn summary_data_frame(summary: &Summary) -> Result<DataFrame, PortfolioError> {
+    let df = df!(
+        "trade_period_start" => [summary.trade_period.start.to_rfc3339()],
+        "trade_period_end" => [summary.trade_period.end.to_rfc3339()],
+        "commission_total" => [summary.commission_total],
+        "tax_amount_total" => [summary.tax_amount_total],
+        "net_profit_total" => [summary.net_profit_total],
+    )?;
+    Ok(df)
 }
+
 fn create_data_frame(dataset: LazyFrame) -> Result<(LazyFrame, LazyFrame), PortfolioError> {
     let round = 2;
     let mode = RoundMode::HalfToEven;
@@ -115,12 +154,7 @@ fn create_data_frame(dataset: LazyFrame) -> Result<(LazyFrame, LazyFrame), Portf
                 .alias("total_commission"),
             (col("purchase_value") + col("buy_commission")).alias("cost_basis"),
             (col("sale_value") - col("sell_commission")).alias("net_proceeds"),
-            (col("trade_period_end") - col("trade_period_start")).alias("settlement_duration"),
         ])
-        .with_columns([col("settlement_duration")
-            .dt()
-            .total_days()
-            .alias("days_to_settle")])
         // Średnia cena zakupu
         .with_columns([(col("cost_basis") / col("buy_quantity"))
             .round(round, mode)
@@ -0,0 +1,293 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use chrono::NaiveDate;
+use holdings::positions::Holdings;
+use market_store::source::MarketDataSource;
+use polars::prelude::*;
+use shared_contracts::errors::PortfolioError;
+use shared_contracts::models::instrument::InstrumentMaster;
+use shared_contracts::models::trade_order::{OrderSide, TradeOrder};
+use util::math::{covariance, mean, round};
+use util::report::{Report, ReportFormat};
+
+const SESSIONS_PER_YEAR: f64 = 252_f64;
+
+pub struct BenchmarkConf<'a> {
+    pub trade_orders_csv: &'a Path,
+    pub market_data: MarketDataSource<'a>,
+    /// Resolves trade and market data names to one symbol.
+    pub instruments: &'a InstrumentMaster,
+    pub benchmark: &'a str,
+    pub output_file: &'a Path,
+    pub format: ReportFormat,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Session {
+    date: NaiveDate,
+    portfolio_value: f64,
+    net_flow: f64,
+    portfolio_return: f64,
+    benchmark_close: f64,
+    benchmark_return: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Statistics {
+    portfolio_return: f64,
+    benchmark_return: f64,
+    alpha: f64,
+    beta: f64,
+    tracking_error: f64,
+}
+
+type CloseHistory = HashMap<String, BTreeMap<NaiveDate, f64>>;
+
+pub fn calculate_and_save(conf: BenchmarkConf) -> Result<(), PortfolioError> {
+    let trades = holdings::trades::load_resolved(conf.trade_orders_csv, conf.instruments)?;
+    let closes = conf.market_data.closes(conf.instruments)?;
+
+    let (name, benchmark) = benchmark_closes(&closes, conf.benchmark).ok_or_else(|| {
+        PortfolioError::InputError(format!(
+            "benchmark {} not found in market data {:?}",
+            conf.benchmark, conf.market_data
+        ))
+    })?;
+
+    let sessions = sessions(&trades, &closes, benchmark)?;
+    let statistics = statistics(&sessions);
+
+    let mut report = Report::new(
+        &format!("Benchmark Report: portfolio vs {name}"),
+        series_data_frame(&sessions)?,
+    )
+    .with_summary(summary_data_frame(&sessions, &statistics, name)?);
+
+    util::report::save(&mut report, conf.output_file, conf.format)?;
+    Ok(())
+}
+
+/// Closes of the benchmark index with its market data name,
+/// matched regardless of case (`wig20` finds `WIG20`, `mwig40` finds `mWIG40`).
+pub fn benchmark_closes<'a>(
+    closes: &'a HashMap<String, BTreeMap<NaiveDate, f64>>,
+    benchmark: &str,
+) -> Option<(&'a str, &'a BTreeMap<NaiveDate, f64>)> {
+    closes
+        .iter()
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case(benchmark.trim()))
+        .map(|(name, closes)| (name.as_str(), closes))
+}
+
+fn sessions(
+    trades: &[TradeOrder],
+    closes: &CloseHistory,
+    benchmark: &BTreeMap<NaiveDate, f64>,
+) -> Result<Vec<Session>, PortfolioError> {
+    let first_trade = trades
+        .first()
+        .ok_or_else(|| PortfolioError::InputError("no executed trades".into()))?
+        .submission_time
+        .date_naive();
+
+    let mut holdings = Holdings::default();
+    let mut last_trade_price: HashMap<&str, f64> = HashMap::new();
+    let mut pending = trades.iter().peekable();
+
+    let mut sessions: Vec<Session> = Vec::new();
+    for (date, benchmark_close) in benchmark.range(first_trade..) {
+        let mut buys = 0_f64;
+        let mut sells = 0_f64;
+        while let Some(trade) = pending.next_if(|t| t.submission_time.date_naive() <= *date) {
+            let price = trade.price.unwrap_or_default();
+            let value = price * trade.filled_quantity as f64;
+            match trade.order_side {
+                OrderSide::Buy => buys += value + trade.commission,
+                OrderSide::Sell => sells += value - trade.commission,
+            }
+            last_trade_price.insert(trade.instrument.as_str(), price);
+            holdings.apply(trade)?;
+        }
+
+        let portfolio_value: f64 = holdings
+            .open_positions()
+            .map(|p| {
+                let price = closes
+                    .get(&p.instrument)
+                    .and_then(|h| h.range(..=*date).next_back().map(|(_, c)| *c))
+                    .or_else(|| last_trade_price.get(p.instrument.as_str()).copied())
+                    .unwrap_or_default();
+                price * p.quantity as f64
+            })
+            .sum();
+
+        let (previous_value, benchmark_return) = match sessions.last() {
+            Some(prev) => (
+                prev.portfolio_value,
+                benchmark_close / prev.benchmark_close - 1_f64,
+            ),
+            None => (0_f64, 0_f64),
+        };
+
+        sessions.push(Session {
+            date: *date,
+            portfolio_value,
+            net_flow: buys - sells,
+            portfolio_return: daily_return(previous_value, portfolio_value, buys, sells),
+            benchmark_close: *benchmark_close,
+            benchmark_return,
+        });
+    }
+
+    if sessions.len() < 2 {
+        return Err(PortfolioError::InputError(format!(
+            "not enough benchmark sessions since first trade {first_trade}"
+        )));
+    }
+    Ok(sessions)
+}
+
+fn daily_return(previous_value: f64, value: f64, buys: f64, sells: f64) -> f64 {
+    let invested = previous_value + buys;
+    if invested > 0_f64 {
+        (value + sells) / invested - 1_f64
+    } else {
+        0_f64
+    }
+}
+
+fn statistics(sessions: &[Session]) -> Statistics {
+    // The first session only establishes the starting point of the benchmark.
+    let portfolio: Vec<f64> = sessions[1..].iter().map(|s| s.portfolio_return).collect();
+    let benchmark: Vec<f64> = sessions[1..].iter().map(|s| s.benchmark_return).collect();
+    let active: Vec<f64> = portfolio
+        .iter()
+        .zip(&benchmark)
+        .map(|(p, b)| p - b)
+        .collect();
+
+    let benchmark_variance = covariance(&benchmark, &benchmark);
+    let beta = if benchmark_variance > 0_f64 {
+        covariance(&portfolio, &benchmark) / benchmark_variance
+    } else {
+        0_f64
+    };
+    let alpha = (mean(&portfolio) - beta * mean(&benchmark)) * SESSIONS_PER_YEAR;
+
+    Statistics {
+        portfolio_return: cumulative(sessions.iter().map(|s| s.portfolio_return)),
+        benchmark_return: cumulative(sessions.iter().map(|s| s.benchmark_return)),
+        alpha,
+        beta,
+        tracking_error: covariance(&active, &active).sqrt() * SESSIONS_PER_YEAR.sqrt(),
+    }
+}
+
+fn cumulative(returns: impl Iterator<Item = f64>) -> f64 {
+    returns.fold(1_f64, |acc, r| acc * (1_f64 + r)) - 1_f64
+}
+
+fn series_data_frame(sessions: &[Session]) -> Result<DataFrame, PortfolioError> {
+    let mut portfolio_cumulative = Vec::with_capacity(sessions.len());
+    let mut benchmark_cumulative = Vec::with_capacity(sessions.len());
+    let mut relative = Vec::with_capacity(sessions.len());
+    let (mut p, mut b) = (1_f64, 1_f64);
+    for s in sessions {
+        p *= 1_f64 + s.portfolio_return;
+        b *= 1_f64 + s.benchmark_return;
+        portfolio_cumulative.push(pct(p - 1_f64));
+        benchmark_cumulative.push(pct(b - 1_f64));
+        relative.push(pct(p / b - 1_f64));
+    }
+
+    let df = df!(
+        "date" => sessions.iter().map(|s| s.date).collect::<Vec<_>>(),
+        "portfolio_value" => sessions.iter().map(|s| round(s.portfolio_value, 2)).collect::<Vec<_>>(),
+        "net_flow" => sessions.iter().map(|s| round(s.net_flow, 2)).collect::<Vec<_>>(),
+        "portfolio_return_pct" => sessions.iter().map(|s| pct(s.portfolio_return)).collect::<Vec<_>>(),
+        "benchmark_close" => sessions.iter().map(|s| s.benchmark_close).collect::<Vec<_>>(),
+        "benchmark_return_pct" => sessions.iter().map(|s| pct(s.benchmark_return)).collect::<Vec<_>>(),
+        "portfolio_cumulative_pct" => portfolio_cumulative,
+        "benchmark_cumulative_pct" => benchmark_cumulative,
+        "relative_performance_pct" => relative,
+    )?;
+    Ok(df)
+}
+
+fn summary_data_frame(
+    sessions: &[Session],
+    statistics: &Statistics,
+    benchmark: &str,
+) -> Result<DataFrame, PortfolioError> {
+    let df = df!(
+        "period_start" => [sessions[0].date],
+        "period_end" => [sessions[sessions.len() - 1].date],
+        "benchmark" => [benchmark],
+        "portfolio_return_pct" => [pct(statistics.portfolio_return)],
+        "benchmark_return_pct" => [pct(statistics.benchmark_return)],
+        "excess_return_pct" => [pct(statistics.portfolio_return - statistics.benchmark_return)],
+        "alpha_pct" => [pct(statistics.alpha)],
+        "beta" => [round(statistics.beta, 4)],
+        "tracking_error_pct" => [pct(statistics.tracking_error)],
+    )?;
+    Ok(df)
+}
+
+fn pct(value: f64) -> f64 {
+    round(value * 100_f64, 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn daily_return_buy_counted_at_session_start() {
+        let actual = daily_return(0_f64, 1100_f64, 1000_f64, 0_f64);
+        assert!((actual - 0.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn daily_return_sell_counted_at_session_end() {
+        let actual = daily_return(1000_f64, 0_f64, 0_f64, 1050_f64);
+        assert!((actual - 0.05).abs() < 1e-9);
+    }
+
+    #[test]
+    fn benchmark_closes_any_case_return_market_data_name() {
+        let closes: CloseHistory = HashMap::from([
+            ("mWIG40".to_string(), BTreeMap::new()),
+            ("WIG20".to_string(), BTreeMap::new()),
+        ]);
+
+        let names: Vec<Option<&str>> = ["wig20", "MWIG40", "sWIG80"]
+            .iter()
+            .map(|b| benchmark_closes(&closes, b).map(|(name, _)| name))
+            .collect();
+
+        assert_eq!(names, vec![Some("WIG20"), Some("mWIG40"), None]);
+    }
+
+    #[test]
+    fn statistics_portfolio_twice_benchmark_beta_two() {
+        let returns = [0_f64, 0.01, -0.02, 0.015, 0.005];
+        let sessions: Vec<Session> = returns
+            .iter()
+            .enumerate()
+            .map(|(i, r)| Session {
+                date: NaiveDate::from_ymd_opt(2025, 1, 2 + i as u32).unwrap(),
+                portfolio_value: 0_f64,
+                net_flow: 0_f64,
+                portfolio_return: 2_f64 * r,
+                benchmark_close: 0_f64,
+                benchmark_return: *r,
+            })
+            .collect();
+
+        let actual = statistics(&sessions);
+
+        assert!((actual.beta - 2_f64).abs() < 1e-9);
+        assert!(actual.alpha.abs() < 1e-9);
+    }
+}
@@ -0,0 +1,351 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use benchmark_report::report::benchmark_closes;
+use chrono::NaiveDate;
+use holdings::positions::Holdings;
+use market_store::source::MarketDataSource;
+use polars::prelude::*;
+use shared_contracts::errors::PortfolioError;
+use shared_contracts::models::instrument::InstrumentMaster;
+use util::math::{covariance, round};
+use util::report::{Report, ReportFormat};
+
+const SESSIONS_PER_YEAR: f64 = 252_f64;
+
+pub struct RiskConf<'a> {
+    pub trade_orders_csv: &'a Path,
+    pub market_data: MarketDataSource<'a>,
+    /// Resolves trade and market data names to one symbol.
+    pub instruments: &'a InstrumentMaster,
+    pub benchmark: &'a str,
+    /// Daily returns of the last `lookback_sessions` benchmark sessions are used.
+    pub lookback_sessions: usize,
+    /// Pairs with a correlation at or above it are highly correlated.
+    pub correlation_threshold: f64,
+    pub output_file: &'a Path,
+    pub pairs_file: &'a Path,
+    pub format: ReportFormat,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CorrelatedPair {
+    pub first: String,
+    pub second: String,
+    pub correlation: f64,
+    pub highly_correlated: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Risk {
+    pub instruments: Vec<String>,
+    /// Share of the portfolio value at the last session, as fractions.
+    pub weights: Vec<f64>,
+    pub correlation: Vec<Vec<f64>>,
+    pub betas: Vec<f64>,
+    /// Annualized volatility of every instrument.
+    pub volatilities: Vec<f64>,
+    /// Annualized, from the covariance matrix and the weights.
+    pub portfolio_volatility: f64,
+    pub portfolio_beta: f64,
+    pub period_start: NaiveDate,
+    pub period_end: NaiveDate,
+    /// Daily returns used.
+    pub sessions: usize,
+    /// Every pair of instruments, most correlated first.
+    pub pairs: Vec<CorrelatedPair>,
+}
+
+type CloseHistory = HashMap<String, BTreeMap<NaiveDate, f64>>;
+
+pub fn calculate_and_save(conf: RiskConf) -> Result<Risk, PortfolioError> {
+    let holdings = Holdings::load(conf.trade_orders_csv, conf.instruments)?;
+    let positions: Vec<(String, i64)> = holdings
+        .open_positions()
+        .map(|p| (p.instrument.clone(), p.quantity))
+        .collect();
+
+    let closes = conf.market_data.closes(conf.instruments)?;
+    let (name, benchmark) = benchmark_closes(&closes, conf.benchmark).ok_or_else(|| {
+        PortfolioError::InputError(format!(
+            "benchmark {} not found in market data {:?}",
+            conf.benchmark, conf.market_data
+        ))
+    })?;
+
+    let risk = risk(
+        &positions,
+        &closes,
+        benchmark,
+        conf.lookback_sessions,
+        conf.correlation_threshold,
+    )?;
+
+    let mut report = Report::new(
+        &format!("Risk: correlation and beta to {name}"),
+        matrix_data_frame(&risk)?,
+    )
+    .with_summary(summary_data_frame(&risk, name)?);
+    util::report::save(&mut report, conf.output_file, conf.format)?;
+
+    let mut report = Report::new("Correlated pairs", pairs_data_frame(&risk)?)
+        .with_highlight("highly_correlated");
+    util::report::save(&mut report, conf.pairs_file, conf.format)?;
+    Ok(risk)
+}
+
+/// Correlation, beta and volatility of the `(instrument, quantity)` positions.
+///
+/// Sessions are the benchmark sessions, an instrument without a trade in a session
+/// keeps its previous close. The window starts when every position has a close.
+pub fn risk(
+    positions: &[(String, i64)],
+    closes: &CloseHistory,
+    benchmark: &BTreeMap<NaiveDate, f64>,
+    lookback_sessions: usize,
+    correlation_threshold: f64,
+) -> Result<Risk, PortfolioError> {
+    if positions.is_empty() {
+        return Err(PortfolioError::InputError("no open positions".into()));
+    }
+    let mut histories = Vec::with_capacity(positions.len());
+    for (instrument, _) in positions {
+        let history = closes.get(instrument).ok_or_else(|| {
+            PortfolioError::InputError(format!("no market data for held {instrument}"))
+        })?;
+        histories.push(history);
+    }
+
+    let sessions: Vec<(NaiveDate, Vec<f64>, f64)> = benchmark
+        .iter()
+        .filter_map(|(date, benchmark_close)| {
+            let prices: Option<Vec<f64>> = histories
+                .iter()
+                .map(|h| h.range(..=*date).next_back().map(|(_, c)| *c))
+                .collect();
+            prices.map(|prices| (*date, prices, *benchmark_close))
+        })
+        .collect();
+    let window = &sessions[sessions.len().saturating_sub(lookback_sessions + 1)..];
+    if window.len() < 3 {
+        return Err(PortfolioError::InputError(format!(
+            "not enough sessions with prices of all held instruments: {}",
+            window.len()
+        )));
+    }
+
+    let returns: Vec<Vec<f64>> = (0..positions.len())
+        .map(|i| {
+            window
+                .windows(2)
+                .map(|w| w[1].1[i] / w[0].1[i] - 1_f64)
+                .collect()
+        })
+        .collect();
+    let benchmark_returns: Vec<f64> = window.windows(2).map(|w| w[1].2 / w[0].2 - 1_f64).collect();
+
+    let n = positions.len();
+    let covariances: Vec<Vec<f64>> = (0..n)
+        .map(|i| {
+            (0..n)
+                .map(|j| covariance(&returns[i], &returns[j]))
+                .collect()
+        })
+        .collect();
+    let deviations: Vec<f64> = (0..n).map(|i| covariances[i][i].sqrt()).collect();
+    let correlation: Vec<Vec<f64>> = (0..n)
+        .map(|i| {
+            (0..n)
+                .map(|j| {
+                    let deviation = deviations[i] * deviations[j];
+                    if deviation > 0_f64 {
+                        covariances[i][j] / deviation
+                    } else {
+                        0_f64
+                    }
+                })
+                .collect()
+        })
+        .collect();
+
+    let benchmark_variance = covariance(&benchmark_returns, &benchmark_returns);
+    let betas: Vec<f64> = returns
+        .iter()
+        .map(|r| {
+            if benchmark_variance > 0_f64 {
+                covariance(r, &benchmark_returns) / benchmark_variance
+            } else {
+                0_f64
+            }
+        })
+        .collect();
+
+    let last_prices = &window[window.len() - 1].1;
+    let values: Vec<f64> = positions
+        .iter()
+        .zip(last_prices)
+        .map(|((_, quantity), price)| *quantity as f64 * price)
+        .collect();
+    let total: f64 = values.iter().sum();
+    let weights: Vec<f64> = values
+        .iter()
+        .map(|v| if total > 0_f64 { v / total } else { 0_f64 })
+        .collect();
+
+    let variance: f64 = (0..n)
+        .flat_map(|i| (0..n).map(move |j| (i, j)))
+        .map(|(i, j)| weights[i] * weights[j] * covariances[i][j])
+        .sum();
+
+    let mut pairs = Vec::new();
+    for i in 0..n {
+        for j in i + 1..n {
+            pairs.push(CorrelatedPair {
+                first: positions[i].0.clone(),
+                second: positions[j].0.clone(),
+                correlation: correlation[i][j],
+                highly_correlated: correlation[i][j] >= correlation_threshold,
+            });
+        }
+    }
+    pairs.sort_by(|a, b| b.correlation.total_cmp(&a.correlation));
+
+    Ok(Risk {
+        instruments: positions.iter().map(|(i, _)| i.clone()).collect(),
+        portfolio_beta: weights.iter().zip(&betas).map(|(w, b)| w * b).sum(),
+        weights,
+        correlation,
+        betas,
+        volatilities: deviations
+            .iter()
+            .map(|d| d * SESSIONS_PER_YEAR.sqrt())
+            .collect(),
+        portfolio_volatility: (variance.max(0_f64) * SESSIONS_PER_YEAR).sqrt(),
+        period_start: window[0].0,
+        period_end: window[window.len() - 1].0,
+        sessions: window.len() - 1,
+        pairs,
+    })
+}
+
+fn matrix_data_frame(risk: &Risk) -> Result<DataFrame, PortfolioError> {
+    let mut columns = vec![
+        Column::new("instrument".into(), risk.instruments.clone()),
+        Column::new(
+            "weight_pct".into(),
+            risk.weights.iter().map(|w| pct(*w)).collect::<Vec<_>>(),
+        ),
+        Column::new(
+            "volatility_pct".into(),
+            risk.volatilities
+                .iter()
+                .map(|v| pct(*v))
+                .collect::<Vec<_>>(),
+        ),
+        Column::new(
+            "beta".into(),
+            risk.betas.iter().map(|b| round(*b, 4)).collect::<Vec<_>>(),
+        ),
+    ];
+    for (j, instrument) in risk.instruments.iter().enumerate() {
+        columns.push(Column::new(
+            instrument.as_str().into(),
+            risk.correlation
+                .iter()
+                .map(|row| round(row[j], 4))
+                .collect::<Vec<_>>(),
+        ));
+    }
+    Ok(DataFrame::new(columns)?)
+}
+
+fn pairs_data_frame(risk: &Risk) -> Result<DataFrame, PortfolioError> {
+    let pairs = &risk.pairs;
+    let df = df!(
+        "first" => pairs.iter().map(|p| p.first.as_str()).collect::<Vec<_>>(),
+        "second" => pairs.iter().map(|p| p.second.as_str()).collect::<Vec<_>>(),
+        "correlation" => pairs.iter().map(|p| round(p.correlation, 4)).collect::<Vec<_>>(),
+        "highly_correlated" => pairs.iter().map(|p| p.highly_correlated).collect::<Vec<_>>(),
+    )?;
+    Ok(df)
+}
+
+fn summary_data_frame(risk: &Risk, benchmark: &str) -> Result<DataFrame, PortfolioError> {
+    let df = df!(
+        "period_start" => [risk.period_start],
+        "period_end" => [risk.period_end],
+        "sessions" => [risk.sessions as u64],
+        "benchmark" => [benchmark],
+        "portfolio_volatility_pct" => [pct(risk.portfolio_volatility)],
+        "portfolio_beta" => [round(risk.portfolio_beta, 4)],
+        "highly_correlated_pairs" => [risk.pairs.iter().filter(|p| p.highly_correlated).count() as u64],
+    )?;
+    Ok(df)
+}
+
+fn pct(value: f64) -> f64 {
+    round(value * 100_f64, 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RETURNS: [f64; 6] = [0.01, -0.02, 0.015, 0.005, -0.01, 0.02];
+
+    #[test]
+    fn risk_same_and_opposite_moves_return_correlation_and_beta() {
+        let closes = CloseHistory::from([
+            ("PKOBP".to_string(), _closes(1_f64)),
+            ("PEKAO".to_string(), _closes(1_f64)),
+            ("ORANGEPL".to_string(), _closes(-1_f64)),
+        ]);
+        let positions = _positions(&["PKOBP", "PEKAO", "ORANGEPL"]);
+
+        let risk = risk(&positions, &closes, &_closes(1_f64), 250, 0.8).unwrap();
+
+        assert_eq!(risk.sessions, RETURNS.len());
+        assert!((risk.correlation[0][1] - 1_f64).abs() < 1e-9);
+        assert!((risk.correlation[0][2] + 1_f64).abs() < 1e-9);
+        assert!((risk.betas[0] - 1_f64).abs() < 1e-9);
+        assert!((risk.betas[2] + 1_f64).abs() < 1e-9);
+        let pairs: Vec<(&str, &str, bool)> = risk
+            .pairs
+            .iter()
+            .map(|p| (p.first.as_str(), p.second.as_str(), p.highly_correlated))
+            .collect();
+        assert_eq!(pairs[0], ("PKOBP", "PEKAO", true));
+        assert!(!pairs[1].2 && !pairs[2].2);
+    }
+
+    #[test]
+    fn risk_identical_instruments_return_instrument_volatility() {
+        let closes = CloseHistory::from([
+            ("PKOBP".to_string(), _closes(1_f64)),
+            ("PEKAO".to_string(), _closes(1_f64)),
+        ]);
+        let positions = _positions(&["PKOBP", "PEKAO"]);
+
+        let risk = risk(&positions, &closes, &_closes(2_f64), 3, 0.8).unwrap();
+
+        assert_eq!(risk.sessions, 3);
+        assert!((risk.portfolio_volatility - risk.volatilities[0]).abs() < 1e-9);
+        assert!((risk.portfolio_beta - 0.5).abs() < 1e-9);
+    }
+
+    fn _positions(instruments: &[&str]) -> Vec<(String, i64)> {
+        instruments.iter().map(|i| (i.to_string(), 10)).collect()
+    }
+
+    /// Closes moving `factor` times the test returns.
+    fn _closes(factor: f64) -> BTreeMap<NaiveDate, f64> {
+        let start = NaiveDate::from_ymd_opt(2025, 3, 3).unwrap();
+        let mut close = 100_f64;
+        let mut closes = BTreeMap::from([(start, close)]);
+        for (i, r) in RETURNS.iter().enumerate() {
+            close *= 1_f64 + factor * r;
+            closes.insert(start + chrono::Days::new(i as u64 + 1), close);
+        }
+        closes
+    }
+}
@@ -0,0 +1,243 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use strum_macros::Display;
+
+use super::market_data::SeriesType;
+use crate::errors::PortfolioError;
+
+/// GPW market an instrument is listed on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Display, Serialize, Deserialize)]
+pub enum Market {
+    #[serde(alias = "main", alias = "Main Market", alias = "GPW")]
+    MainMarket,
+    #[serde(alias = "nc", alias = "NC")]
+    NewConnect,
+}
+
+/// Reference data of one instrument.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instrument {
+    /// Canonical name used in market data and reports, the GPW `Nazwa`, e.g. "ORANGEPL".
+    pub symbol: String,
+
+    pub isin: Option<String>,
+
+    /// Company or index name, e.g. "Orange Polska SA".
+    pub full_name: Option<String>,
+
+    /// `None` when not known, e.g. an override row without market.
+    pub market: Option<Market>,
+
+    pub instrument_type: Option<SeriesType>,
+
+    /// Other names of the instrument: broker symbols, former GPW names,
+    /// names used in analyst reports.
+    pub aliases: BTreeSet<String>,
+}
+
+impl Instrument {
+    pub fn new(symbol: &str) -> Self {
+        Instrument {
+            symbol: symbol.trim().to_string(),
+            isin: None,
+            full_name: None,
+            market: None,
+            instrument_type: None,
+            aliases: BTreeSet::new(),
+        }
+    }
+}
+
+/// Row of the instruments csv, aliases are separated with `|`.
+#[derive(Debug, Serialize, Deserialize)]
+struct InstrumentRow {
+    symbol: String,
+    #[serde(default)]
+    isin: Option<String>,
+    #[serde(default)]
+    full_name: Option<String>,
+    #[serde(default)]
+    market: Option<Market>,
+    #[serde(default)]
+    instrument_type: Option<SeriesType>,
+    #[serde(default)]
+    aliases: Option<String>,
+}
+
+/// Instrument master: resolves broker symbols, GPW names, ISINs and aliases
+/// to the canonical [`Instrument::symbol`].
+#[derive(Debug, Clone, Default)]
+pub struct InstrumentMaster {
+    instruments: BTreeMap<String, Instrument>,
+    /// Upper-cased name, ISIN or alias -> symbol.
+    keys: HashMap<String, String>,
+}
+
+impl InstrumentMaster {
+    /// Reads an instruments csv (`symbol;isin;full_name;market;instrument_type;aliases`).
+    /// Only `symbol` is required, so the same layout works for user overrides.
+    pub fn load(instruments_csv: &Path) -> Result<Self, PortfolioError> {
+        let mut rdr = csv::ReaderBuilder::new()
+            .delimiter(b';')
+            .has_headers(true)
+            .trim(csv::Trim::All)
+            .from_path(instruments_csv)?;
+
+        let mut master = InstrumentMaster::default();
+        for result in rdr.deserialize() {
+            let row: InstrumentRow = result?;
+            master.insert(Instrument {
+                symbol: row.symbol,
+                isin: row.isin.filter(|v| !v.is_empty()),
+                full_name: row.full_name.filter(|v| !v.is_empty()),
+                market: row.market,
+                instrument_type: row.instrument_type,
+                aliases: row
+                    .aliases
+                    .unwrap_or_default()
+                    .split('|')
+                    .map(str::trim)
+                    .filter(|a| !a.is_empty())
+                    .map(str::to_string)
+                    .collect(),
+            });
+        }
+        Ok(master)
+    }
+
+    pub fn save(&self, instruments_csv: &Path) -> Result<(), PortfolioError> {
+        let mut wtr = csv::WriterBuilder::new()
+            .delimiter(b';')
+            .from_path(instruments_csv)?;
+        for instrument in self.instruments.values() {
+            wtr.serialize(InstrumentRow {
+                symbol: instrument.symbol.clone(),
+                isin: instrument.isin.clone(),
+                full_name: instrument.full_name.clone(),
+                market: instrument.market,
+                instrument_type: instrument.instrument_type,
+                aliases: Some(
+                    instrument
+                        .aliases
+                        .iter()
+                        .cloned()
+                        .collect::<Vec<_>>()
+                        .join("|"),
+                ),
+            })?;
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.instruments.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instruments.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Instrument> {
+        self.instruments.values()
+    }
+
+    /// Adds or updates an instrument. An existing entry is found by symbol or ISIN;
+    /// values set in `instrument` win and aliases are merged. When the ISIN matches an
+    /// entry under another symbol (renamed company), the old symbol becomes an alias.
+    pub fn insert(&mut self, instrument: Instrument) {
+        let existing_symbol = self
+            .resolve(&instrument.symbol)
+            .filter(|i| i.symbol.eq_ignore_ascii_case(&instrument.symbol))
+            .or_else(|| {
+                instrument
+                    .isin
+                    .as_deref()
+                    .and_then(|isin| self.resolve(isin))
+            })
+            .map(|i| i.symbol.clone());
+
+        let merged = match existing_symbol.and_then(|s| self.instruments.remove(&s)) {
+            Some(mut existing) => {
+                if existing.symbol != instrument.symbol {
+                    existing.aliases.insert(existing.symbol.clone());
+                    existing.symbol = instrument.symbol;
+                }
+                existing.isin = instrument.isin.or(existing.isin);
+                existing.full_name = instrument.full_name.or(existing.full_name);
+                existing.market = instrument.market.or(existing.market);
+                existing.instrument_type = instrument.instrument_type.or(existing.instrument_type);
+                existing.aliases.extend(instrument.aliases);
+                existing
+            }
+            None => instrument,
+        };
+
+        for key in keys(&merged) {
+            self.keys.insert(key, merged.symbol.clone());
+        }
+        self.instruments.insert(merged.symbol.clone(), merged);
+    }
+
+    /// Applies `overrides` on top of this master.
+    pub fn merge(&mut self, overrides: InstrumentMaster) {
+        for instrument in overrides.instruments.into_values() {
+            self.insert(instrument);
+        }
+    }
+
+    /// Finds an instrument by symbol, ISIN or alias, ignoring case and surrounding spaces.
+    pub fn resolve(&self, name: &str) -> Option<&Instrument> {
+        self.keys
+            .get(&key(name))
+            .and_then(|symbol| self.instruments.get(symbol))
+    }
+
+    /// Canonical symbol of `name`, or `name` itself when it is not known.
+    pub fn symbol<'a>(&'a self, name: &'a str) -> &'a str {
+        self.resolve(name).map_or(name, |i| i.symbol.as_str())
+    }
+
+    /// Every lookup key (upper-cased) with its canonical symbol.
+    pub fn keys(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.keys.iter().map(|(k, s)| (k.as_str(), s.as_str()))
+    }
+}
+
+fn keys(instrument: &Instrument) -> Vec<String> {
+    let mut keys = vec![key(&instrument.symbol)];
+    keys.extend(instrument.isin.iter().map(|i| key(i)));
+    keys.extend(instrument.aliases.iter().map(|a| key(a)));
+    keys
+}
+
+fn key(name: &str) -> String {
+    name.trim().to_uppercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_renamed_company_same_isin_old_symbol_resolves_to_new() {
+        let mut master = InstrumentMaster::default();
+        master.insert(Instrument {
+            isin: Some("PLTPSA000011".into()),
+            ..Instrument::new("TPSA")
+        });
+        master.insert(Instrument {
+            isin: Some("PLTPSA000011".into()),
+            aliases: BTreeSet::from(["OPL".to_string()]),
+            ..Instrument::new("ORANGEPL")
+        });
+
+        assert_eq!(master.len(), 1);
+        assert_eq!(master.symbol("tpsa"), "ORANGEPL");
+        assert_eq!(master.symbol(" opl "), "ORANGEPL");
+        assert_eq!(master.symbol("PLTPSA000011"), "ORANGEPL");
+        assert_eq!(master.symbol("PKOBP"), "PKOBP");
+    }
+}
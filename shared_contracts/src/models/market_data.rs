@@ -0,0 +1,40 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use strum_macros::Display;
+
+/// One session of one instrument in the normalized market data schema,
+/// shared by every market data source.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MarketData {
+    pub date: NaiveDate,
+
+    /// Instrument name as used in trades, e.g. "ORANGEPL".
+    pub instrument: String,
+
+    pub isin: Option<String>,
+
+    pub opening_price: f64,
+
+    pub max_price: f64,
+
+    pub min_price: f64,
+
+    pub closing_price: f64,
+
+    pub volume: Option<i64>,
+
+    pub transaction_number: Option<i64>,
+
+    pub open_position_number: Option<i64>,
+
+    pub instrument_type: SeriesType,
+}
+
+/// Kind of a market data series. Indices are benchmark series only,
+/// they never appear in trade orders.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Display, Serialize, Deserialize)]
+pub enum SeriesType {
+    #[default]
+    Stock,
+    Index,
+}
@@ -1,2 +1,4 @@
+pub mod instrument;
+pub mod market_data;
 pub mod report;
 pub mod trade_order;
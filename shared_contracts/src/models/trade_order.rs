@@ -1,8 +1,8 @@
 use chrono::{DateTime, Utc};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use strum_macros::Display;
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TradeOrder {
     /// Symbol or identifier of a financial instrument (e.g. "AAPL", "EURUSD", "PLN=F").
     pub instrument: String,
@@ -43,14 +43,48 @@ pub struct TradeOrder {
     pub exchange: String,
 }
 
+#[cfg(any(test, feature = "test-utils"))]
+impl TradeOrder {
+    /// Filled GPW limit order in PLN without commission, submitted at 09:00 UTC
+    /// on `date` (`%Y-%m-%d`).
+    pub fn filled(
+        instrument: &str,
+        order_side: OrderSide,
+        quantity: i64,
+        price: f64,
+        date: &str,
+    ) -> Self {
+        let submission_time = chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .expect("fixture date")
+            .and_hms_opt(9, 0, 0)
+            .expect("fixture time")
+            .and_utc();
+        TradeOrder {
+            instrument: instrument.to_string(),
+            instrument_type: InstrumentType::Stock,
+            order_type: OrderType::Limit,
+            order_side,
+            quantity,
+            filled_quantity: quantity,
+            price: Some(price),
+            commission: 0.0,
+            status: OrderStatus::Filled,
+            submission_time,
+            currency: "PLN".to_string(),
+            exchange: "GPW".to_string(),
+        }
+    }
+}
+
 /// Types of financial instruments.
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Display, Serialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Display, Serialize, Deserialize)]
 pub enum InstrumentType {
+    #[default]
     Stock,
 }
 
 /// Stock order types.
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Display, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Display, Serialize, Deserialize)]
 pub enum OrderType {
     Market,
     Limit,
@@ -59,14 +93,14 @@ pub enum OrderType {
 
 /// Side of the order (Buy or Sell).
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Display, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Display, Serialize, Deserialize)]
 pub enum OrderSide {
     Buy,
     Sell,
 }
 
 /// Status of the order.
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Display, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Display, Serialize, Deserialize)]
 pub enum OrderStatus {
     Pending,
     PartiallyFilled,
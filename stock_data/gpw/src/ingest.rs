@@ -0,0 +1,173 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDate;
+use rayon::prelude::*;
+use shared_contracts::errors::PortfolioError;
+use shared_contracts::models::market_data::MarketData;
+
+use crate::market_data::{self, MarketDataRecord};
+use crate::validation::{self, Finding, ValidationConf};
+
+static EXTENSIONS: &[&str] = &["xls", "xlsx", "csv"];
+
+#[derive(Debug, Clone)]
+pub struct FileSummary {
+    pub file: PathBuf,
+    pub rows: usize,
+    /// Why the file was skipped, `None` when it was ingested.
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct IngestSummary {
+    pub files: Vec<FileSummary>,
+    pub sessions: usize,
+    pub instruments: usize,
+    pub rows: usize,
+    pub first_session: Option<NaiveDate>,
+    pub last_session: Option<NaiveDate>,
+    /// Validation findings of the merged series.
+    pub findings: Vec<Finding>,
+}
+
+impl IngestSummary {
+    pub fn ingested_files(&self) -> usize {
+        self.files.iter().filter(|f| f.error.is_none()).count()
+    }
+}
+
+/// Reads every GPW daily statistics file in `dir` in parallel and writes one
+/// normalized csv deduplicated by (date, ISIN). When the same session is in
+/// several files, the file that sorts last by name wins.
+/// Files without a daily statistics table are reported and skipped.
+/// Every file is checked for repeated rows and the merged series is validated,
+/// in strict mode the csv is not written when there are errors, see [`ValidationConf::enforce`].
+pub fn ingest_dir(
+    dir: &Path,
+    output: &Path,
+    validation: &ValidationConf,
+) -> Result<IngestSummary, PortfolioError> {
+    let files = find_files(dir, output)?;
+
+    let results: Vec<(PathBuf, Result<Vec<MarketDataRecord>, PortfolioError>)> = files
+        .par_iter()
+        .map(|file| (file.clone(), market_data::read(file)))
+        .collect();
+
+    let mut summary = IngestSummary::default();
+    let mut merged: BTreeMap<(NaiveDate, String), MarketDataRecord> = BTreeMap::new();
+
+    for (file, result) in results {
+        match result {
+            Ok(records) => {
+                summary.files.push(FileSummary {
+                    file,
+                    rows: records.len(),
+                    error: None,
+                });
+                // Rows repeated within one file are errors, sessions repeated across
+                // files are overlapping exports and the later file wins.
+                let file_records: Vec<MarketData> =
+                    records.iter().cloned().map(MarketData::from).collect();
+                validation::check_duplicates(&file_records, &mut summary.findings);
+                for record in records {
+                    merged.insert(series_key(&record), record);
+                }
+            }
+            Err(e) => summary.files.push(FileSummary {
+                file,
+                rows: 0,
+                error: Some(e.to_string()),
+            }),
+        }
+    }
+
+    let sessions: BTreeSet<NaiveDate> = merged.keys().map(|(date, _)| *date).collect();
+    let instruments: BTreeSet<&str> = merged.keys().map(|(_, key)| key.as_str()).collect();
+    summary.sessions = sessions.len();
+    summary.instruments = instruments.len();
+    summary.first_session = sessions.first().copied();
+    summary.last_session = sessions.last().copied();
+    summary.rows = merged.len();
+
+    let records: Vec<MarketData> = merged.into_values().map(MarketData::from).collect();
+    summary
+        .findings
+        .extend(validation::validate(&records, validation));
+    validation::sort(&mut summary.findings);
+    if validation.strict && validation::errors(&summary.findings) > 0 {
+        return Ok(summary);
+    }
+
+    let mut wtr = util::csv::default_writer(output)?;
+    for record in records {
+        wtr.serialize(record)?;
+    }
+    wtr.flush()?;
+    Ok(summary)
+}
+
+/// (date, ISIN), instruments without ISIN are keyed by name.
+fn series_key(record: &MarketDataRecord) -> (NaiveDate, String) {
+    let key = record
+        .isin
+        .as_ref()
+        .filter(|isin| !isin.trim().is_empty())
+        .cloned()
+        .unwrap_or_else(|| record.name.clone());
+    (record.date, key)
+}
+
+fn find_files(dir: &Path, output: &Path) -> Result<Vec<PathBuf>, PortfolioError> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let supported = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| EXTENSIONS.contains(&e.to_lowercase().as_str()));
+        if path.is_file() && supported && path != output {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validation::Check;
+
+    #[test]
+    fn ingest_dir_row_repeated_in_file_found_overlap_between_files_merged() {
+        let dir = _temp_dir("ingest_dir_duplicates");
+        let header = "Data;Nazwa;ISIN;Waluta;Kurs otwarcia;Kurs max;Kurs min;Kurs zamknięcia\n";
+        let row = "2025-10-17;ORANGEPL;PLTLKPL00017;PLN;8,8;9,02;8,74;8,97\n";
+        fs::write(dir.join("a.csv"), format!("{header}{row}")).unwrap();
+        fs::write(dir.join("b.csv"), format!("{header}{row}{row}")).unwrap();
+        let output = dir.join("merged.out");
+        let conf = ValidationConf {
+            strict: true,
+            ..Default::default()
+        };
+
+        let summary = ingest_dir(&dir, &output, &conf).unwrap();
+
+        let checks: Vec<Check> = summary.findings.iter().map(|f| f.check).collect();
+        assert_eq!(checks, vec![Check::DuplicateRow]);
+        assert_eq!(summary.rows, 1);
+        assert!(conf.enforce(&summary.findings).is_err());
+        assert!(!output.exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn _temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("gpw_{}_{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+}
@@ -0,0 +1,35 @@
+use std::path::PathBuf;
+
+use shared_contracts::errors::PortfolioError;
+use shared_contracts::models::instrument::{Instrument, InstrumentMaster, Market};
+use shared_contracts::models::market_data::MarketData;
+
+use crate::validation;
+
+/// Instrument master of every instrument in GPW daily statistics (or normalized
+/// market data) files. The latest ISIN of an instrument wins.
+pub fn from_files(
+    market_data_files: &[PathBuf],
+    market: Market,
+) -> Result<InstrumentMaster, PortfolioError> {
+    let mut records = Vec::new();
+    for file in market_data_files {
+        records.extend(validation::load(file)?);
+    }
+    Ok(from_market_data(records, market))
+}
+
+pub fn from_market_data(mut records: Vec<MarketData>, market: Market) -> InstrumentMaster {
+    records.sort_by_key(|r| r.date);
+
+    let mut master = InstrumentMaster::default();
+    for record in records {
+        master.insert(Instrument {
+            isin: record.isin.filter(|isin| !isin.trim().is_empty()),
+            market: Some(market),
+            instrument_type: Some(record.instrument_type),
+            ..Instrument::new(&record.instrument)
+        });
+    }
+    master
+}
@@ -1 +1,4 @@
+pub mod ingest;
+pub mod instruments;
 pub mod market_data;
+pub mod validation;
@@ -1,27 +1,107 @@
-use calamine::{RangeDeserializer, Reader, Xls, open_workbook};
-use serde::{Deserialize, Serialize};
+use calamine::{
+    Data, DataType, Range, RangeDeserializerBuilder, Reader, deserialize_as_f64_or_none,
+    deserialize_as_i64_or_none, open_workbook_auto,
+};
+use chrono::NaiveDate;
+use encoding_rs::WINDOWS_1250;
+use serde::{Deserialize, Deserializer};
 use shared_contracts::errors::PortfolioError;
-use std::path::Path;
+use shared_contracts::models::market_data::MarketData;
+use shared_contracts::models::market_data::SeriesType;
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+/// Index names published in the GPW daily statistics.
+pub static INDICES: &[&str] = &[
+    "WIG",
+    "WIG20",
+    "WIG20TR",
+    "WIG30",
+    "WIG30TR",
+    "mWIG40",
+    "mWIG40TR",
+    "sWIG80",
+    "sWIG80TR",
+    "WIG140",
+    "WIG-Poland",
+    "NCIndex",
+];
+
+/// Header cells identifying the daily statistics table.
+static REQUIRED_HEADERS: &[&str] = &["Data", "Nazwa", "Kurs zamknięcia"];
+
+/// Text columns of the csv export, the others hold numbers.
+static TEXT_HEADERS: &[&str] = &["Data", "Nazwa", "ISIN", "Waluta"];
+
+/// How many leading rows are searched for the header.
+const HEADER_SEARCH_ROWS: usize = 20;
 
 pub fn convert_xls_to_csv(market_data_file: &Path, output: &Path) -> Result<(), PortfolioError> {
-    let records = read_xls(market_data_file)?;
+    let records = read(market_data_file)?;
     write_to_csv(records, output)?;
     Ok(())
 }
 
-fn write_to_csv(records: Vec<MarketDataRecord>, output: &Path) -> Result<(), PortfolioError> {
+/// Converts several daily statistics files (shares and indices, any number of
+/// sessions) into one CSV, sorted by date and instrument.
+pub fn convert_xls_files_to_csv(
+    market_data_files: &[PathBuf],
+    output: &Path,
+) -> Result<(), PortfolioError> {
+    let mut records = Vec::new();
+    for file in market_data_files {
+        records.extend(read(file)?);
+    }
+    records.sort_by(|a, b| a.date.cmp(&b.date).then_with(|| a.name.cmp(&b.name)));
+
+    write_to_csv(records, output)?;
+    Ok(())
+}
+
+pub fn is_index(instrument: &str) -> bool {
+    INDICES
+        .iter()
+        .any(|index| index.eq_ignore_ascii_case(instrument))
+}
+
+fn write_to_csv(
+    records: impl IntoIterator<Item = MarketDataRecord>,
+    output: &Path,
+) -> Result<(), PortfolioError> {
     let mut wtr = util::csv::default_writer(output)?;
 
     for record in records {
-        wtr.serialize(record)?;
+        wtr.serialize(MarketData::from(record))?;
     }
 
     wtr.flush()?;
     Ok(())
 }
 
-fn read_xls(stocks_data_file: &Path) -> Result<Vec<MarketDataRecord>, PortfolioError> {
-    let mut workbook: Xls<_> = open_workbook(stocks_data_file).map_err(|e| {
+/// Reads a GPW daily statistics file: xls, xlsx or the csv export.
+pub(crate) fn read(market_data_file: &Path) -> Result<Vec<MarketDataRecord>, PortfolioError> {
+    let extension = market_data_file
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+
+    let mut records = match extension.as_str() {
+        "csv" | "txt" => read_csv(market_data_file)?,
+        _ => read_workbook(market_data_file)?,
+    };
+
+    for r in records.iter_mut() {
+        if is_index(&r.name) {
+            r.instrument_type = SeriesType::Index;
+        }
+    }
+    Ok(records)
+}
+
+fn read_workbook(stocks_data_file: &Path) -> Result<Vec<MarketDataRecord>, PortfolioError> {
+    let mut workbook = open_workbook_auto(stocks_data_file).map_err(|e| {
         PortfolioError::Xls(format!(
             "Invalid xls file:{}, reason:{}",
             stocks_data_file.display(),
@@ -29,21 +109,27 @@ fn read_xls(stocks_data_file: &Path) -> Result<Vec<MarketDataRecord>, PortfolioE
         ))
     })?;
 
-    let sheet_range = workbook.worksheet_range("Worksheet").map_err(|e| {
-        PortfolioError::Xls(format!(
-            "Invalid worksheet file:{}, reason:{}",
-            stocks_data_file.display(),
-            e
-        ))
-    })?;
+    let sheet_range = workbook
+        .worksheets()
+        .into_iter()
+        .find_map(|(_, range)| data_range(&range))
+        .ok_or_else(|| {
+            PortfolioError::Xls(format!(
+                "No daily statistics sheet in file:{}, expected header with {:?}",
+                stocks_data_file.display(),
+                REQUIRED_HEADERS
+            ))
+        })?;
 
-    let iter: RangeDeserializer<_, MarketDataRecord> = sheet_range.deserialize().map_err(|e| {
-        PortfolioError::Xls(format!(
-            "Deserialization error,  xls file:{}, reason:{}",
-            stocks_data_file.display(),
-            e
-        ))
-    })?;
+    let iter = RangeDeserializerBuilder::new()
+        .from_range::<_, MarketDataRecord>(&sheet_range)
+        .map_err(|e| {
+            PortfolioError::Xls(format!(
+                "Deserialization error,  xls file:{}, reason:{}",
+                stocks_data_file.display(),
+                e
+            ))
+        })?;
 
     let mut records: Vec<MarketDataRecord> = Vec::new();
 
@@ -55,20 +141,121 @@ fn read_xls(stocks_data_file: &Path) -> Result<Vec<MarketDataRecord>, PortfolioE
     Ok(records)
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct MarketDataRecord {
-    #[serde(alias = "Data", rename = "date")]
-    pub date: String,
+/// Part of the sheet starting at the daily statistics header row.
+fn data_range(range: &Range<Data>) -> Option<Range<Data>> {
+    let (start_row, start_col) = range.start()?;
+    let end = range.end()?;
+
+    let header_row = range
+        .rows()
+        .take(HEADER_SEARCH_ROWS)
+        .position(|row| is_header(row.iter().filter_map(|cell| cell.get_string())))?;
+
+    Some(range.range((start_row + header_row as u32, start_col), end))
+}
+
+fn is_header<'a>(cells: impl Iterator<Item = &'a str>) -> bool {
+    let cells: Vec<&str> = cells.map(str::trim).collect();
+    REQUIRED_HEADERS.iter().all(|h| cells.contains(h))
+}
+
+/// The csv export is either utf-8 or windows-1250, separated with `;` (decimal comma)
+/// or `,` (decimal point), and may start with a title before the header line.
+fn read_csv(stocks_data_file: &Path) -> Result<Vec<MarketDataRecord>, PortfolioError> {
+    let bytes = fs::read(stocks_data_file)?;
+    let content = match String::from_utf8(bytes) {
+        Ok(content) => content,
+        Err(e) => WINDOWS_1250.decode(e.as_bytes()).0.into_owned(),
+    };
+
+    let lines: Vec<&str> = content.trim_start_matches('\u{feff}').lines().collect();
+
+    let (header_idx, delimiter) = lines
+        .iter()
+        .take(HEADER_SEARCH_ROWS)
+        .enumerate()
+        .find_map(|(idx, line)| {
+            let delimiter = if line.contains(';') { ';' } else { ',' };
+            is_header(line.split(delimiter).map(|c| c.trim_matches('"')))
+                .then_some((idx, delimiter))
+        })
+        .ok_or_else(|| {
+            PortfolioError::Xls(format!(
+                "No daily statistics header in file:{}, expected {:?}",
+                stocks_data_file.display(),
+                REQUIRED_HEADERS
+            ))
+        })?;
+
+    let data = lines[header_idx..]
+        .iter()
+        .filter(|l| !l.trim().is_empty())
+        .fold(String::new(), |data, line| data + line + "\n");
+
+    let mut rdr = csv::ReaderBuilder::new()
+        .delimiter(delimiter as u8)
+        .has_headers(true)
+        .trim(csv::Trim::All)
+        .from_reader(Cursor::new(data));
+
+    let headers = rdr.headers()?.clone();
+    // Decimal commas are converted in the numeric columns only, names may contain commas.
+    let numeric: Vec<bool> = headers
+        .iter()
+        .map(|h| delimiter == ';' && !TEXT_HEADERS.contains(&h))
+        .collect();
+
+    let mut records = Vec::new();
+    for result in rdr.records() {
+        let record: csv::StringRecord = result?
+            .iter()
+            .zip(&numeric)
+            .map(|(field, numeric)| {
+                if *numeric {
+                    field.replace(',', ".")
+                } else {
+                    field.to_string()
+                }
+            })
+            .collect();
+        records.push(record.deserialize::<MarketDataRecord>(Some(&headers))?);
+    }
+    Ok(records)
+}
+
+/// Session date from a text cell (ISO or `DD.MM.YYYY`) or an excel date.
+fn deserialize_date<'de, D>(deserializer: D) -> Result<NaiveDate, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let data = Data::deserialize(deserializer)?;
+
+    let date = match data.get_string() {
+        Some(text) => {
+            let text = text.trim();
+            NaiveDate::parse_from_str(text.get(..10).unwrap_or(text), "%Y-%m-%d")
+                .or_else(|_| NaiveDate::parse_from_str(text, "%d.%m.%Y"))
+                .ok()
+        }
+        None => data.as_date(),
+    };
+
+    date.ok_or_else(|| serde::de::Error::custom(format!("invalid session date {data:?}")))
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct MarketDataRecord {
+    #[serde(alias = "Data", rename = "date", deserialize_with = "deserialize_date")]
+    pub date: NaiveDate,
 
     #[serde(alias = "Nazwa", rename = "instrument")]
     pub name: String,
 
-    #[allow(dead_code)]
-    #[serde(alias = "ISIN", skip_serializing)]
-    pub isin: String,
+    #[serde(alias = "ISIN", rename = "isin", default)]
+    pub isin: Option<String>,
 
     #[allow(dead_code)]
-    #[serde(alias = "Waluta", skip_serializing)]
+    #[serde(alias = "Waluta")]
     pub currency: String,
 
     #[serde(alias = "Kurs otwarcia", rename = "opening_price")]
@@ -84,27 +271,132 @@ struct MarketDataRecord {
     pub close_price: f64,
 
     #[allow(dead_code)]
-    #[serde(alias = "Zmiana", skip_serializing)]
-    pub change: f64,
+    #[serde(
+        alias = "Zmiana",
+        default,
+        deserialize_with = "deserialize_as_f64_or_none"
+    )]
+    pub change: Option<f64>,
 
-    #[serde(alias = "Wolumen", rename = "volume")]
-    pub volume: i64,
+    // Index rows leave the volume and transaction related cells empty.
+    #[serde(
+        alias = "Wolumen",
+        rename = "volume",
+        default,
+        deserialize_with = "deserialize_as_i64_or_none"
+    )]
+    pub volume: Option<i64>,
 
-    #[serde(alias = "Liczba Transakcji", rename = "transaction_number")]
-    pub num_transactions: i64,
+    #[serde(
+        alias = "Liczba Transakcji",
+        rename = "transaction_number",
+        default,
+        deserialize_with = "deserialize_as_i64_or_none"
+    )]
+    pub num_transactions: Option<i64>,
 
     #[allow(dead_code)]
-    #[serde(alias = "Obrót", skip_serializing)]
-    pub turnover: f64,
+    #[serde(
+        alias = "Obrót",
+        default,
+        deserialize_with = "deserialize_as_f64_or_none"
+    )]
+    pub turnover: Option<f64>,
 
-    #[serde(alias = "Liczba otwartych pozycji", rename = "open_position_number")]
-    pub open_positions_count: i64,
+    #[serde(
+        alias = "Liczba otwartych pozycji",
+        rename = "open_position_number",
+        default,
+        deserialize_with = "deserialize_as_i64_or_none"
+    )]
+    pub open_positions_count: Option<i64>,
 
     #[allow(dead_code)]
-    #[serde(alias = "Wartość otwartych pozycji", skip_serializing)]
-    pub open_positions_value: f64,
+    #[serde(
+        alias = "Wartość otwartych pozycji",
+        default,
+        deserialize_with = "deserialize_as_f64_or_none"
+    )]
+    pub open_positions_value: Option<f64>,
 
     #[allow(dead_code)]
-    #[serde(alias = "Cena nominalna", skip_serializing)]
-    pub nominal_price: f64,
+    #[serde(
+        alias = "Cena nominalna",
+        default,
+        deserialize_with = "deserialize_as_f64_or_none"
+    )]
+    pub nominal_price: Option<f64>,
+
+    #[serde(skip_deserializing)]
+    pub instrument_type: SeriesType,
+}
+
+impl From<MarketDataRecord> for MarketData {
+    fn from(record: MarketDataRecord) -> Self {
+        MarketData {
+            date: record.date,
+            instrument: record.name,
+            isin: record.isin,
+            opening_price: record.open_price,
+            max_price: record.high_price,
+            min_price: record.low_price,
+            closing_price: record.close_price,
+            volume: record.volume,
+            transaction_number: record.num_transactions,
+            open_position_number: record.open_positions_count,
+            instrument_type: record.instrument_type,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_xls_header_detected_records_with_isin() {
+        let file = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/stocks_data.xls");
+
+        let records = read(&file).unwrap();
+
+        let orange = records.iter().find(|r| r.name == "ORANGEPL").unwrap();
+        assert_eq!(orange.date, NaiveDate::from_ymd_opt(2025, 10, 17).unwrap());
+        assert_eq!(orange.isin.as_deref(), Some("PLTLKPL00017"));
+        assert_eq!(orange.instrument_type, SeriesType::Stock);
+    }
+
+    #[test]
+    fn read_csv_decimal_comma_converted_in_numeric_columns_only() {
+        let dir = _temp_dir("read_csv_decimal_comma");
+        let file = dir.join("stocks_data.csv");
+        fs::write(
+            &file,
+            "Notowania\n\
+             Data;Nazwa;ISIN;Waluta;Kurs otwarcia;Kurs max;Kurs min;Kurs zamknięcia;Wolumen\n\
+             2025-10-17;\"SPOLKA, S.A.\";PLXXX0000001;PLN;8,8;9,02;8,74;8,97;701712\n",
+        )
+        .unwrap();
+
+        let records = read(&file).unwrap();
+
+        assert_eq!(records[0].name, "SPOLKA, S.A.");
+        assert_eq!((records[0].open_price, records[0].close_price), (8.8, 8.97));
+        assert_eq!(records[0].volume, Some(701712));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn is_header_daily_statistics_columns_return_true() {
+        let cells = ["Data", "Nazwa", "ISIN", " Kurs zamknięcia "];
+
+        assert!(is_header(cells.into_iter()));
+        assert!(!is_header(["date", "instrument"].into_iter()));
+    }
+
+    fn _temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("gpw_{}_{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
 }
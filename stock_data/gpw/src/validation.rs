@@ -0,0 +1,401 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::Path;
+
+use chrono::NaiveDate;
+use polars::prelude::*;
+use serde::Serialize;
+use shared_contracts::errors::PortfolioError;
+use shared_contracts::models::market_data::MarketData;
+use trading_calendar::calendar::TradingCalendar;
+use util::report::{Report, ReportFormat};
+
+use crate::market_data;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub enum Severity {
+    /// The row is wrong, calculations on it are not trustworthy.
+    Error,
+    /// The row may be right, but should be looked at.
+    Warning,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Check {
+    NonPositivePrice,
+    InconsistentOhlc,
+    NegativeVolume,
+    DuplicateRow,
+    MissingSession,
+    PriceJump,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Finding {
+    pub severity: Severity,
+    pub check: Check,
+    pub date: NaiveDate,
+    /// Empty for findings about a whole session.
+    pub instrument: String,
+    pub message: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?} {:?} {} {}: {}",
+            self.severity, self.check, self.date, self.instrument, self.message
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ValidationConf {
+    /// Largest accepted close-to-close change, 0.5 = 50%.
+    pub max_daily_change: f64,
+    /// Refuse to continue when there is any error finding.
+    pub strict: bool,
+    /// Sessions expected between the first and the last date.
+    pub calendar: TradingCalendar,
+}
+
+impl Default for ValidationConf {
+    fn default() -> Self {
+        ValidationConf {
+            max_daily_change: 0.5,
+            strict: false,
+            calendar: TradingCalendar::gpw(),
+        }
+    }
+}
+
+impl ValidationConf {
+    /// Fails in strict mode when `findings` contain errors.
+    pub fn enforce(&self, findings: &[Finding]) -> Result<(), PortfolioError> {
+        let errors = errors(findings);
+        if self.strict && errors > 0 {
+            return Err(PortfolioError::InputError(format!(
+                "market data validation failed with {errors} errors, first: {}",
+                findings
+                    .iter()
+                    .find(|f| f.severity == Severity::Error)
+                    .map(|f| f.to_string())
+                    .unwrap_or_default()
+            )));
+        }
+        Ok(())
+    }
+}
+
+pub fn errors(findings: &[Finding]) -> usize {
+    findings
+        .iter()
+        .filter(|f| f.severity == Severity::Error)
+        .count()
+}
+
+pub fn warnings(findings: &[Finding]) -> usize {
+    findings
+        .iter()
+        .filter(|f| f.severity == Severity::Warning)
+        .count()
+}
+
+/// Checks OHLC consistency, volume, duplicate (date, instrument) rows,
+/// sessions missing from the calendar and day-over-day close jumps.
+/// Sessions without trades (zero volume, zero opening, max and min prices,
+/// the reference price as the close) are not price errors.
+/// Findings are sorted by date and instrument.
+pub fn validate(records: &[MarketData], conf: &ValidationConf) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    for record in records {
+        check_row(record, &mut findings);
+    }
+    check_duplicates(records, &mut findings);
+    check_missing_sessions(records, &conf.calendar, &mut findings);
+    check_jumps(records, conf.max_daily_change, &mut findings);
+
+    sort(&mut findings);
+    findings
+}
+
+/// Sorts findings by date, instrument and severity.
+pub fn sort(findings: &mut [Finding]) {
+    findings.sort_by(|a, b| {
+        a.date
+            .cmp(&b.date)
+            .then_with(|| a.instrument.cmp(&b.instrument))
+            .then_with(|| a.severity.cmp(&b.severity))
+    });
+}
+
+/// Reads a GPW daily statistics file (xls, xlsx, csv export) or a normalized market data csv.
+pub fn load(market_data_file: &Path) -> Result<Vec<MarketData>, PortfolioError> {
+    if is_normalized_csv(market_data_file)? {
+        let mut rdr = util::csv::default_reader(market_data_file)?;
+        let mut records = Vec::new();
+        for result in rdr.deserialize() {
+            let record: MarketData = result?;
+            records.push(record);
+        }
+        return Ok(records);
+    }
+    let records = market_data::read(market_data_file)?;
+    Ok(records.into_iter().map(MarketData::from).collect())
+}
+
+/// Writes the findings report in `format`.
+pub fn save_findings(
+    findings: &[Finding],
+    output: &Path,
+    format: ReportFormat,
+) -> Result<(), PortfolioError> {
+    let mut report = Report::new("Market Data Validation", findings_data_frame(findings)?);
+    util::report::save(&mut report, output, format)
+}
+
+fn findings_data_frame(findings: &[Finding]) -> Result<DataFrame, PortfolioError> {
+    let df = df!(
+        "severity" => findings.iter().map(|f| format!("{:?}", f.severity)).collect::<Vec<_>>(),
+        "check" => findings.iter().map(|f| format!("{:?}", f.check)).collect::<Vec<_>>(),
+        "date" => findings.iter().map(|f| f.date).collect::<Vec<_>>(),
+        "instrument" => findings.iter().map(|f| f.instrument.as_str()).collect::<Vec<_>>(),
+        "message" => findings.iter().map(|f| f.message.as_str()).collect::<Vec<_>>(),
+    )?;
+    Ok(df)
+}
+
+fn check_row(record: &MarketData, findings: &mut Vec<Finding>) {
+    if is_no_trade_session(record) {
+        return;
+    }
+    let mut push = |severity, check, message: String| {
+        findings.push(Finding {
+            severity,
+            check,
+            date: record.date,
+            instrument: record.instrument.clone(),
+            message,
+        })
+    };
+
+    let prices = [
+        ("opening", record.opening_price),
+        ("max", record.max_price),
+        ("min", record.min_price),
+        ("closing", record.closing_price),
+    ];
+    let non_positive: Vec<String> = prices
+        .iter()
+        .filter(|(_, price)| *price <= 0.0 || price.is_nan())
+        .map(|(name, price)| format!("{name} price {price}"))
+        .collect();
+    if !non_positive.is_empty() {
+        push(
+            Severity::Error,
+            Check::NonPositivePrice,
+            non_positive.join(", "),
+        );
+        return;
+    }
+
+    if record.max_price < record.min_price {
+        push(
+            Severity::Error,
+            Check::InconsistentOhlc,
+            format!(
+                "max price {} lower than min price {}",
+                record.max_price, record.min_price
+            ),
+        );
+    } else {
+        for (name, price) in [
+            ("opening", record.opening_price),
+            ("closing", record.closing_price),
+        ] {
+            if price < record.min_price || price > record.max_price {
+                push(
+                    Severity::Error,
+                    Check::InconsistentOhlc,
+                    format!(
+                        "{name} price {price} outside {} - {}",
+                        record.min_price, record.max_price
+                    ),
+                );
+            }
+        }
+    }
+
+    if let Some(volume) = record.volume.filter(|v| *v < 0) {
+        push(
+            Severity::Error,
+            Check::NegativeVolume,
+            format!("volume {volume}"),
+        );
+    }
+}
+
+/// GPW lists an instrument without trades in a session with zero volume and
+/// zero prices except the close, the reference price.
+fn is_no_trade_session(record: &MarketData) -> bool {
+    record.volume == Some(0)
+        && record.closing_price > 0.0
+        && [record.opening_price, record.max_price, record.min_price]
+            .iter()
+            .all(|price| *price == 0.0)
+}
+
+pub(crate) fn check_duplicates(records: &[MarketData], findings: &mut Vec<Finding>) {
+    let mut counts: BTreeMap<(NaiveDate, &str), usize> = BTreeMap::new();
+    for record in records {
+        *counts
+            .entry((record.date, record.instrument.as_str()))
+            .or_default() += 1;
+    }
+    for ((date, instrument), count) in counts.into_iter().filter(|(_, c)| *c > 1) {
+        findings.push(Finding {
+            severity: Severity::Error,
+            check: Check::DuplicateRow,
+            date,
+            instrument: instrument.to_string(),
+            message: format!("{count} rows for one session"),
+        });
+    }
+}
+
+/// Calendar sessions between the first and the last date without any row.
+fn check_missing_sessions(
+    records: &[MarketData],
+    calendar: &TradingCalendar,
+    findings: &mut Vec<Finding>,
+) {
+    let sessions: BTreeSet<NaiveDate> = records.iter().map(|r| r.date).collect();
+    let (Some(first), Some(last)) = (sessions.first(), sessions.last()) else {
+        return;
+    };
+
+    for date in calendar.sessions(*first..=*last) {
+        if !sessions.contains(&date) {
+            findings.push(Finding {
+                severity: Severity::Warning,
+                check: Check::MissingSession,
+                date,
+                instrument: String::new(),
+                message: "no data for a session".to_string(),
+            });
+        }
+    }
+}
+
+fn check_jumps(records: &[MarketData], max_daily_change: f64, findings: &mut Vec<Finding>) {
+    let mut series: BTreeMap<&str, BTreeMap<NaiveDate, f64>> = BTreeMap::new();
+    for record in records.iter().filter(|r| r.closing_price > 0.0) {
+        series
+            .entry(record.instrument.as_str())
+            .or_default()
+            .insert(record.date, record.closing_price);
+    }
+
+    for (instrument, closes) in series {
+        let closes: Vec<(&NaiveDate, &f64)> = closes.iter().collect();
+        for pair in closes.windows(2) {
+            let ((_, previous), (date, close)) = (pair[0], pair[1]);
+            let change = close / previous - 1.0;
+            if change.abs() > max_daily_change {
+                findings.push(Finding {
+                    severity: Severity::Warning,
+                    check: Check::PriceJump,
+                    date: *date,
+                    instrument: instrument.to_string(),
+                    message: format!("close {previous} -> {close} ({:+.1}%)", change * 100.0),
+                });
+            }
+        }
+    }
+}
+
+fn is_normalized_csv(file: &Path) -> Result<bool, PortfolioError> {
+    let is_csv = file
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("csv"));
+    if !is_csv {
+        return Ok(false);
+    }
+    let mut rdr = util::csv::default_reader(file)?;
+    let normalized = rdr
+        .headers()
+        .map(|h| h.iter().any(|c| c == "closing_price"))
+        .unwrap_or(false);
+    Ok(normalized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared_contracts::models::market_data::SeriesType;
+
+    #[test]
+    fn validate_bad_rows_return_findings() {
+        let records = vec![
+            _record("2025-10-13", "ORANGEPL", 9.0, 9.1, 8.9, 9.0),
+            _record("2025-10-13", "ORANGEPL", 9.0, 9.1, 8.9, 9.0),
+            _record("2025-10-14", "ORANGEPL", 9.0, 8.8, 8.9, 8.9),
+            _record("2025-10-14", "PKOBP", 0.0, 66.0, 65.0, 65.5),
+            // No trades in the session, only the reference price.
+            MarketData {
+                volume: Some(0),
+                .._record("2025-10-14", "YOLO", 0.0, 0.0, 0.0, 0.12)
+            },
+            _record("2025-10-16", "ORANGEPL", 18.0, 18.5, 17.9, 18.2),
+        ];
+
+        let findings = validate(&records, &ValidationConf::default());
+
+        let checks: Vec<Check> = findings.iter().map(|f| f.check).collect();
+        assert_eq!(
+            checks,
+            vec![
+                Check::DuplicateRow,
+                Check::InconsistentOhlc,
+                Check::NonPositivePrice,
+                Check::MissingSession,
+                Check::PriceJump,
+            ]
+        );
+        assert_eq!(errors(&findings), 3);
+        assert!(
+            ValidationConf {
+                strict: true,
+                ..Default::default()
+            }
+            .enforce(&findings)
+            .is_err()
+        );
+    }
+
+    fn _record(
+        date: &str,
+        instrument: &str,
+        open: f64,
+        max: f64,
+        min: f64,
+        close: f64,
+    ) -> MarketData {
+        MarketData {
+            date: NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap(),
+            instrument: instrument.to_string(),
+            isin: None,
+            opening_price: open,
+            max_price: max,
+            min_price: min,
+            closing_price: close,
+            volume: Some(1000),
+            transaction_number: None,
+            open_position_number: None,
+            instrument_type: SeriesType::Stock,
+        }
+    }
+}
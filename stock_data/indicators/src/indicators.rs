@@ -0,0 +1,273 @@
+use polars::prelude::*;
+use shared_contracts::errors::PortfolioError;
+
+/// Indicator periods used by [`with_indicators`].
+#[derive(Debug, Clone)]
+pub struct IndicatorsConf {
+    pub sma_windows: Vec<usize>,
+    pub ema_span: usize,
+    pub rsi_period: usize,
+    pub macd_fast: usize,
+    pub macd_slow: usize,
+    pub macd_signal: usize,
+    pub atr_period: usize,
+    pub bollinger_window: usize,
+    pub bollinger_k: f64,
+    pub volume_window: usize,
+}
+
+impl Default for IndicatorsConf {
+    fn default() -> Self {
+        IndicatorsConf {
+            sma_windows: vec![20, 50],
+            ema_span: 20,
+            rsi_period: 14,
+            macd_fast: 12,
+            macd_slow: 26,
+            macd_signal: 9,
+            atr_period: 14,
+            bollinger_window: 20,
+            bollinger_k: 2.0,
+            volume_window: 20,
+        }
+    }
+}
+
+/// Simple moving average of `column` over the last `window` sessions.
+pub fn sma(column: &str, window: usize) -> Expr {
+    per_instrument(col(column).rolling_mean(fixed_window(window)))
+}
+
+/// Exponential moving average, `alpha = 2 / (span + 1)`.
+pub fn ema(column: &str, span: usize) -> Expr {
+    per_instrument(ewm(col(column), 2.0 / (span as f64 + 1.0), span))
+}
+
+/// Relative strength index of the closing price with Wilder smoothing, 0-100.
+pub fn rsi(period: usize) -> Expr {
+    let change = col("closing_price").diff(lit(1), Default::default());
+    let gain = when(change.clone().gt(lit(0.0)))
+        .then(change.clone())
+        .otherwise(lit(0.0));
+    let loss = when(change.clone().lt(lit(0.0)))
+        .then(-change)
+        .otherwise(lit(0.0));
+
+    let alpha = 1.0 / period as f64;
+    let avg_gain = ewm(gain, alpha, period);
+    let avg_loss = ewm(loss, alpha, period);
+
+    per_instrument(
+        when(avg_loss.clone().eq(lit(0.0)))
+            .then(lit(100.0))
+            .otherwise(lit(100.0) - lit(100.0) / (lit(1.0) + avg_gain / avg_loss)),
+    )
+}
+
+/// MACD line, signal line and histogram of the closing price.
+pub fn macd(fast: usize, slow: usize, signal: usize) -> [Expr; 3] {
+    let line = ewm(col("closing_price"), 2.0 / (fast as f64 + 1.0), fast)
+        - ewm(col("closing_price"), 2.0 / (slow as f64 + 1.0), slow);
+    let signal_line = ewm(line.clone(), 2.0 / (signal as f64 + 1.0), signal);
+    let histogram = line.clone() - signal_line.clone();
+
+    [
+        per_instrument(line),
+        per_instrument(signal_line),
+        per_instrument(histogram),
+    ]
+}
+
+/// Average true range with Wilder smoothing.
+pub fn atr(period: usize) -> Expr {
+    let previous_close = col("closing_price").shift(lit(1));
+    let range = col("max_price") - col("min_price");
+    let gap_up = (col("max_price") - previous_close.clone()).abs();
+    let gap_down = (col("min_price") - previous_close.clone()).abs();
+
+    let true_range = when(previous_close.is_null())
+        .then(range.clone())
+        .otherwise(greater(greater(range, gap_up), gap_down));
+    per_instrument(ewm(true_range, 1.0 / period as f64, period))
+}
+
+/// Bollinger bands of the closing price: middle, upper and lower band,
+/// `k` population standard deviations around the simple moving average.
+pub fn bollinger(window: usize, k: f64) -> [Expr; 3] {
+    let middle = col("closing_price").rolling_mean(fixed_window(window));
+    let std = col("closing_price").rolling_std(RollingOptionsFixedWindow {
+        fn_params: Some(RollingFnParams::Var(RollingVarParams { ddof: 0 })),
+        ..fixed_window(window)
+    });
+
+    [
+        per_instrument(middle.clone()),
+        per_instrument(middle.clone() + lit(k) * std.clone()),
+        per_instrument(middle - lit(k) * std),
+    ]
+}
+
+/// Average session volume over the last `window` sessions.
+pub fn average_volume(window: usize) -> Expr {
+    per_instrument(
+        col("volume")
+            .cast(DataType::Float64)
+            .rolling_mean(fixed_window(window)),
+    )
+}
+
+/// Adds the indicator columns to market data in the store schema.
+///
+/// Values need `window` sessions of history, earlier rows are null.
+pub fn with_indicators(market_data: LazyFrame, conf: &IndicatorsConf) -> LazyFrame {
+    let mut columns: Vec<Expr> = conf
+        .sma_windows
+        .iter()
+        .map(|w| sma("closing_price", *w).alias(format!("sma_{w}")))
+        .collect();
+
+    let [macd_line, macd_signal, macd_histogram] =
+        macd(conf.macd_fast, conf.macd_slow, conf.macd_signal);
+    let [bb_middle, bb_upper, bb_lower] = bollinger(conf.bollinger_window, conf.bollinger_k);
+
+    columns.extend([
+        ema("closing_price", conf.ema_span).alias(format!("ema_{}", conf.ema_span)),
+        rsi(conf.rsi_period).alias(format!("rsi_{}", conf.rsi_period)),
+        macd_line.alias("macd"),
+        macd_signal.alias("macd_signal"),
+        macd_histogram.alias("macd_histogram"),
+        atr(conf.atr_period).alias(format!("atr_{}", conf.atr_period)),
+        bb_middle.alias("bb_middle"),
+        bb_upper.alias("bb_upper"),
+        bb_lower.alias("bb_lower"),
+        average_volume(conf.volume_window).alias(format!("avg_volume_{}", conf.volume_window)),
+    ]);
+
+    market_data
+        .sort(["instrument", "date"], Default::default())
+        .with_columns(columns)
+}
+
+/// Last session of every instrument, limited to `watchlist` unless it is empty.
+/// Keeps the date, instrument, closing price and the indicator columns.
+pub fn latest(
+    with_indicators: LazyFrame,
+    watchlist: &[String],
+) -> Result<DataFrame, PortfolioError> {
+    let mut lf =
+        with_indicators.filter(col("date").eq(col("date").max().over([col("instrument")])));
+    if !watchlist.is_empty() {
+        let names = Series::new("watchlist".into(), watchlist);
+        lf = lf.filter(col("instrument").is_in(lit(names).implode(), false));
+    }
+    let df = lf
+        .drop(cols([
+            "isin",
+            "opening_price",
+            "max_price",
+            "min_price",
+            "volume",
+            "transaction_number",
+            "open_position_number",
+            "instrument_type",
+        ]))
+        .sort(["instrument"], Default::default())
+        .collect()?;
+    Ok(df)
+}
+
+/// Indicator values of one instrument on its last session, formatted for display.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LatestValues {
+    pub instrument: String,
+    pub date: String,
+    /// Column name and value of the closing price and every indicator.
+    pub values: Vec<(String, String)>,
+}
+
+/// Rows of the frame returned by [`latest`] as displayable values.
+pub fn latest_values(latest: &DataFrame) -> Result<Vec<LatestValues>, PortfolioError> {
+    let instruments = latest.column("instrument")?;
+    let dates = latest.column("date")?;
+
+    let mut rows = Vec::with_capacity(latest.height());
+    for i in 0..latest.height() {
+        let mut values = Vec::new();
+        for column in latest.get_columns().iter().skip(2) {
+            values.push((
+                column.name().to_string(),
+                column.get(i)?.str_value().to_string(),
+            ));
+        }
+        rows.push(LatestValues {
+            instrument: instruments.get(i)?.str_value().to_string(),
+            date: dates.get(i)?.str_value().to_string(),
+            values,
+        });
+    }
+    Ok(rows)
+}
+
+fn per_instrument(expr: Expr) -> Expr {
+    expr.over([col("instrument")])
+}
+
+fn greater(a: Expr, b: Expr) -> Expr {
+    when(a.clone().gt_eq(b.clone())).then(a).otherwise(b)
+}
+
+fn fixed_window(window: usize) -> RollingOptionsFixedWindow {
+    RollingOptionsFixedWindow {
+        window_size: window,
+        min_periods: window,
+        ..Default::default()
+    }
+}
+
+fn ewm(expr: Expr, alpha: f64, min_periods: usize) -> Expr {
+    expr.ewm_mean(EWMOptions {
+        alpha,
+        adjust: false,
+        bias: false,
+        min_periods,
+        ignore_nulls: true,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn with_indicators_two_instruments_windows_do_not_mix() {
+        let conf = IndicatorsConf {
+            sma_windows: vec![3],
+            rsi_period: 3,
+            ..Default::default()
+        };
+
+        let df = with_indicators(_market_data(), &conf)
+            .filter(col("instrument").eq(lit("UP")))
+            .collect()
+            .unwrap();
+
+        let sma: Vec<Option<f64>> = df.column("sma_3").unwrap().f64().unwrap().iter().collect();
+        let rsi = df.column("rsi_3").unwrap().f64().unwrap().get(3);
+        assert_eq!(sma, vec![None, None, Some(11.0), Some(12.0)]);
+        assert_eq!(rsi, Some(100.0));
+    }
+
+    fn _market_data() -> LazyFrame {
+        df!(
+            "date" => ["2025-10-14", "2025-10-15", "2025-10-16", "2025-10-17", "2025-10-14", "2025-10-15", "2025-10-16", "2025-10-17"],
+            "instrument" => ["UP", "UP", "UP", "UP", "DOWN", "DOWN", "DOWN", "DOWN"],
+            "max_price" => [10.5, 11.5, 12.5, 13.5, 50.5, 49.5, 48.5, 47.5],
+            "min_price" => [9.5, 10.5, 11.5, 12.5, 49.5, 48.5, 47.5, 46.5],
+            "closing_price" => [10.0, 11.0, 12.0, 13.0, 50.0, 49.0, 48.0, 47.0],
+            "volume" => [100_i64, 200, 300, 400, 100, 200, 300, 400],
+        )
+        .unwrap()
+        .lazy()
+        .with_column(col("date").cast(DataType::Date))
+    }
+}
@@ -0,0 +1,95 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use chrono::NaiveDate;
+use polars::prelude::*;
+use shared_contracts::errors::PortfolioError;
+use shared_contracts::models::instrument::InstrumentMaster;
+
+use crate::store::{self, MarketDataStore};
+
+/// Where calculations read market data from.
+#[derive(Debug, Clone, Copy)]
+pub enum MarketDataSource<'a> {
+    /// Normalized market data csv, one or more sessions.
+    Csv(&'a Path),
+    /// Directory of a [`MarketDataStore`].
+    Store(&'a Path),
+}
+
+impl<'a> MarketDataSource<'a> {
+    /// A directory is treated as a store, anything else as a normalized csv.
+    pub fn from_path(path: &'a Path) -> Self {
+        if path.is_dir() {
+            MarketDataSource::Store(path)
+        } else {
+            MarketDataSource::Csv(path)
+        }
+    }
+
+    /// Full history in the store schema.
+    pub fn history(&self) -> Result<LazyFrame, PortfolioError> {
+        match self {
+            MarketDataSource::Csv(csv) => {
+                store::normalize(util::polars::default_lazy_reder(csv)?.finish()?)
+            }
+            MarketDataSource::Store(root) => MarketDataStore::open(root)?.scan(),
+        }
+    }
+
+    /// Latest session of every instrument.
+    pub fn latest(&self) -> Result<LazyFrame, PortfolioError> {
+        let lf = self
+            .history()?
+            .filter(col("date").eq(col("date").max().over([col("instrument")])));
+        Ok(lf)
+    }
+
+    /// Latest close of every instrument by symbol, sessions without trades skipped.
+    pub fn latest_closes(
+        &self,
+        instruments: &InstrumentMaster,
+    ) -> Result<BTreeMap<String, f64>, PortfolioError> {
+        let df = util::polars::resolve_instruments(self.history()?, instruments)
+            .filter(col("closing_price").gt(lit(0)))
+            .sort(["date"], Default::default())
+            .group_by([col("instrument")])
+            .agg([col("closing_price").last()])
+            .collect()?;
+
+        let names = df.column("instrument")?.str()?;
+        let closes = df.column("closing_price")?.f64()?;
+        Ok(names
+            .into_iter()
+            .zip(closes)
+            .filter_map(|(name, close)| Some((name?.to_string(), close?)))
+            .collect())
+    }
+
+    /// Closing prices of every instrument by symbol and session, sessions without trades skipped.
+    pub fn closes(
+        &self,
+        instruments: &InstrumentMaster,
+    ) -> Result<HashMap<String, BTreeMap<NaiveDate, f64>>, PortfolioError> {
+        let df = util::polars::resolve_instruments(self.history()?, instruments)
+            .filter(col("closing_price").gt(lit(0)))
+            .select([col("instrument"), col("date"), col("closing_price")])
+            .collect()?;
+
+        let names = df.column("instrument")?.str()?;
+        let dates: Vec<Option<NaiveDate>> = df.column("date")?.date()?.as_date_iter().collect();
+        let prices = df.column("closing_price")?.f64()?;
+
+        let mut closes: HashMap<String, BTreeMap<NaiveDate, f64>> = HashMap::new();
+        for ((name, date), price) in names.into_iter().zip(dates).zip(prices) {
+            let (Some(name), Some(date), Some(price)) = (name, date, price) else {
+                continue;
+            };
+            closes
+                .entry(name.to_string())
+                .or_default()
+                .insert(date, price);
+        }
+        Ok(closes)
+    }
+}
@@ -0,0 +1,331 @@
+use std::fs::{self, File};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+
+use chrono::{Datelike, NaiveDate};
+use polars::prelude::*;
+use shared_contracts::errors::PortfolioError;
+
+/// Columns of the normalized market data schema and their types.
+pub static SCHEMA: &[(&str, DataType)] = &[
+    ("date", DataType::Date),
+    ("instrument", DataType::String),
+    ("isin", DataType::String),
+    ("opening_price", DataType::Float64),
+    ("max_price", DataType::Float64),
+    ("min_price", DataType::Float64),
+    ("closing_price", DataType::Float64),
+    ("volume", DataType::Int64),
+    ("transaction_number", DataType::Int64),
+    ("open_position_number", DataType::Int64),
+    ("instrument_type", DataType::String),
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AppendSummary {
+    pub sessions: usize,
+    pub rows: usize,
+}
+
+/// Parquet store of normalized market data, one file per session.
+pub struct MarketDataStore {
+    root: PathBuf,
+}
+
+impl MarketDataStore {
+    pub fn open(root: &Path) -> Result<Self, PortfolioError> {
+        fs::create_dir_all(root)?;
+        Ok(MarketDataStore {
+            root: root.to_path_buf(),
+        })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Appends a normalized market data csv (output of the `gpw` converter).
+    pub fn append_csv(&self, market_data_csv: &Path) -> Result<AppendSummary, PortfolioError> {
+        let lf = util::polars::default_lazy_reder(market_data_csv)?.finish()?;
+        self.append(lf)
+    }
+
+    /// Appends market data. Rows of an already stored session are replaced
+    /// by (date, instrument), so appending the same data twice is a no-op.
+    pub fn append(&self, market_data: LazyFrame) -> Result<AppendSummary, PortfolioError> {
+        let df = normalize(market_data)?.collect()?;
+
+        let mut summary = AppendSummary::default();
+        for session in df.partition_by_stable(["date"], true)? {
+            let date = session_date(&session)?;
+            let path = self.partition_path(date);
+
+            let mut merged = if path.exists() {
+                concat(
+                    [read_partition(&path)?, session.lazy()],
+                    UnionArgs::default(),
+                )?
+                .unique_stable(Some(cols(["date", "instrument"])), UniqueKeepStrategy::Last)
+                .sort(["instrument"], Default::default())
+                .collect()?
+            } else {
+                session.sort(["instrument"], Default::default())?
+            };
+
+            write_partition(&path, &mut merged)?;
+            summary.sessions += 1;
+            summary.rows += merged.height();
+        }
+        Ok(summary)
+    }
+
+    /// All stored sessions, oldest first.
+    pub fn sessions(&self) -> Result<Vec<NaiveDate>, PortfolioError> {
+        let mut sessions = Vec::new();
+        for year in fs::read_dir(&self.root)? {
+            let year = year?.path();
+            if !year.is_dir() {
+                continue;
+            }
+            for file in fs::read_dir(&year)? {
+                let file = file?.path();
+                let date = file
+                    .file_stem()
+                    .and_then(|s| s.to_str())
+                    .and_then(|s| NaiveDate::parse_from_str(s, "%Y-%m-%d").ok());
+                if let (Some(date), Some("parquet")) =
+                    (date, file.extension().and_then(|e| e.to_str()))
+                {
+                    sessions.push(date);
+                }
+            }
+        }
+        sessions.sort();
+        Ok(sessions)
+    }
+
+    /// Every stored row, or an empty frame when the store is empty.
+    pub fn scan(&self) -> Result<LazyFrame, PortfolioError> {
+        self.scan_range(NaiveDate::MIN..=NaiveDate::MAX)
+    }
+
+    pub fn scan_range(
+        &self,
+        range: RangeInclusive<NaiveDate>,
+    ) -> Result<LazyFrame, PortfolioError> {
+        // Partitions are scanned one by one, those written by older versions
+        // have fewer columns and are normalized before the union.
+        let partitions = self
+            .sessions()?
+            .into_iter()
+            .filter(|d| range.contains(d))
+            .map(|d| scan_partition(&self.partition_path(d)))
+            .collect::<Result<Vec<LazyFrame>, PortfolioError>>()?;
+
+        if partitions.is_empty() {
+            return Ok(empty_frame().lazy());
+        }
+        let lf = concat(partitions, UnionArgs::default())?;
+        Ok(lf)
+    }
+
+    pub fn close_on(
+        &self,
+        instrument: &str,
+        date: NaiveDate,
+    ) -> Result<Option<f64>, PortfolioError> {
+        let df = self
+            .scan_range(date..=date)?
+            .filter(col("instrument").eq(lit(instrument)))
+            .select([col("closing_price")])
+            .collect()?;
+        Ok(df.column("closing_price")?.f64()?.iter().next().flatten())
+    }
+
+    pub fn latest_close(
+        &self,
+        instrument: &str,
+    ) -> Result<Option<(NaiveDate, f64)>, PortfolioError> {
+        let df = self
+            .scan()?
+            .filter(col("instrument").eq(lit(instrument)))
+            .sort(["date"], Default::default())
+            .select([col("date").last(), col("closing_price").last()])
+            .collect()?;
+
+        let date = df.column("date")?.date()?.as_date_iter().next().flatten();
+        let close = df.column("closing_price")?.f64()?.iter().next().flatten();
+        Ok(date.zip(close))
+    }
+
+    /// Sessions of one instrument within `range`, sorted by date.
+    pub fn history(
+        &self,
+        instrument: &str,
+        range: RangeInclusive<NaiveDate>,
+    ) -> Result<DataFrame, PortfolioError> {
+        let df = self
+            .scan_range(range)?
+            .filter(col("instrument").eq(lit(instrument)))
+            .sort(["date"], Default::default())
+            .collect()?;
+        Ok(df)
+    }
+
+    fn partition_path(&self, date: NaiveDate) -> PathBuf {
+        let mut path = self.root.clone();
+        path.push(date.year().to_string());
+        path.push(format!("{}.parquet", date.format("%Y-%m-%d")));
+        path
+    }
+}
+
+/// Casts market data to the store schema. Files written before index support
+/// have no `instrument_type`, those rows are stocks, and older files have no `isin`.
+pub fn normalize(market_data: LazyFrame) -> Result<LazyFrame, PortfolioError> {
+    let mut lf = market_data;
+    let schema = lf.collect_schema()?;
+
+    if !schema.contains("isin") {
+        lf = lf.with_column(lit(NULL).cast(DataType::String).alias("isin"));
+    }
+    if !schema.contains("instrument_type") {
+        lf = lf.with_column(lit("Stock").alias("instrument_type"));
+    }
+
+    let columns: Vec<Expr> = SCHEMA
+        .iter()
+        .map(|(name, dtype)| col(*name).cast(dtype.clone()))
+        .collect();
+    Ok(lf.select(columns))
+}
+
+fn empty_frame() -> DataFrame {
+    let columns: Vec<Column> = SCHEMA
+        .iter()
+        .map(|(name, dtype)| Column::new_empty((*name).into(), dtype))
+        .collect();
+    DataFrame::new(columns).unwrap_or_default()
+}
+
+fn session_date(session: &DataFrame) -> Result<NaiveDate, PortfolioError> {
+    session
+        .column("date")?
+        .date()?
+        .as_date_iter()
+        .next()
+        .flatten()
+        .ok_or_else(|| PortfolioError::InvalidValue("market data row without date".into()))
+}
+
+fn read_partition(path: &Path) -> Result<LazyFrame, PortfolioError> {
+    let file = File::open(path)?;
+    let df = ParquetReader::new(file).finish()?;
+    normalize(df.lazy())
+}
+
+fn scan_partition(path: &Path) -> Result<LazyFrame, PortfolioError> {
+    let path = PlPath::from_string(path.to_string_lossy().to_string());
+    normalize(LazyFrame::scan_parquet(path, ScanArgsParquet::default())?)
+}
+
+fn write_partition(path: &Path, df: &mut DataFrame) -> Result<(), PortfolioError> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let tmp = path.with_extension("parquet.tmp");
+    let mut file = File::create(&tmp)?;
+    ParquetWriter::new(&mut file).finish(df)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn append_same_session_twice_keeps_one_row_per_instrument() {
+        let root = _temp_dir("append_same_session_twice");
+        let store = MarketDataStore::open(&root).unwrap();
+
+        store.append(_session("2025-10-17", 8.97)).unwrap();
+        let summary = store.append(_session("2025-10-17", 9.01)).unwrap();
+
+        let date = NaiveDate::from_ymd_opt(2025, 10, 17).unwrap();
+        assert_eq!(
+            summary,
+            AppendSummary {
+                sessions: 1,
+                rows: 2
+            }
+        );
+        assert_eq!(store.close_on("ORANGEPL", date).unwrap(), Some(9.01));
+        assert_eq!(store.latest_close("ORANGEPL").unwrap(), Some((date, 9.01)));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn append_to_partition_without_isin_and_instrument_type_normalizes_it() {
+        let root = _temp_dir("append_to_old_partition");
+        let store = MarketDataStore::open(&root).unwrap();
+        let date = NaiveDate::from_ymd_opt(2025, 10, 16).unwrap();
+        let mut old = _session("2025-10-16", 8.9)
+            .filter(col("instrument").eq(lit("ORANGEPL")))
+            .with_column(col("date").cast(DataType::Date))
+            .drop(cols(["isin", "instrument_type"]))
+            .collect()
+            .unwrap();
+        write_partition(&store.partition_path(date), &mut old).unwrap();
+
+        store.append(_session("2025-10-17", 9.01)).unwrap();
+        let mixed = store.scan().unwrap().collect().unwrap();
+        let summary = store.append(_session("2025-10-16", 8.97)).unwrap();
+
+        let df = store.scan().unwrap().collect().unwrap();
+        assert_eq!(
+            summary,
+            AppendSummary {
+                sessions: 1,
+                rows: 2
+            }
+        );
+        assert_eq!((mixed.height(), df.height()), (3, 4));
+        assert_eq!(store.close_on("ORANGEPL", date).unwrap(), Some(8.97));
+        assert_eq!(
+            store.history("WIG20", date..=date).unwrap()["instrument_type"]
+                .str()
+                .unwrap()
+                .get(0),
+            Some("Index")
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    fn _session(date: &str, orange_close: f64) -> LazyFrame {
+        df!(
+            "date" => [date, date],
+            "instrument" => ["ORANGEPL", "WIG20"],
+            "isin" => ["PLTLKPL00017", "PL9999999987"],
+            "opening_price" => [8.8, 2890.1],
+            "max_price" => [9.02, 2911.4],
+            "min_price" => [8.74, 2871.3],
+            "closing_price" => [orange_close, 2904.77],
+            "volume" => [Some(701712_i64), None],
+            "transaction_number" => [Some(2298_i64), None],
+            "open_position_number" => [Some(0_i64), None],
+            "instrument_type" => ["Stock", "Index"],
+        )
+        .unwrap()
+        .lazy()
+    }
+
+    fn _temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("market_store_{}_{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+}
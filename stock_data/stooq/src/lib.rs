@@ -0,0 +1,2 @@
+pub mod quotes;
+pub mod tickers;
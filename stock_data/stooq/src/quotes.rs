@@ -0,0 +1,271 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDate;
+use csv::StringRecord;
+use shared_contracts::errors::PortfolioError;
+use shared_contracts::models::market_data::MarketData;
+use shared_contracts::models::market_data::SeriesType;
+
+use crate::tickers::TickerMap;
+
+static EXTENSIONS: &[&str] = &["csv", "txt"];
+
+#[derive(Debug, Clone, Default)]
+pub struct ImportSummary {
+    pub files: usize,
+    pub rows: usize,
+    /// Tickers without an entry in the tickers file, imported under the upper-cased ticker.
+    pub unmapped_tickers: BTreeSet<String>,
+}
+
+/// Column positions of one Stooq file layout.
+#[derive(Debug)]
+struct Layout {
+    ticker: Option<usize>,
+    date: usize,
+    open: usize,
+    high: usize,
+    low: usize,
+    close: usize,
+    volume: Option<usize>,
+}
+
+#[derive(Debug)]
+struct Quote {
+    ticker: String,
+    date: NaiveDate,
+    open: f64,
+    high: f64,
+    low: f64,
+    close: f64,
+    volume: Option<i64>,
+}
+
+/// Converts Stooq files (or directories of them) into one normalized market data csv,
+/// deduplicated by (date, instrument).
+pub fn convert_to_csv(
+    inputs: &[PathBuf],
+    tickers: &TickerMap,
+    output: &Path,
+) -> Result<ImportSummary, PortfolioError> {
+    let files = find_files(inputs)?;
+
+    let mut summary = ImportSummary::default();
+    let mut merged: BTreeMap<(NaiveDate, String), MarketData> = BTreeMap::new();
+
+    for file in &files {
+        for quote in read_file(file)? {
+            let instrument = match tickers.get(&quote.ticker) {
+                Some(instrument) => instrument.to_string(),
+                None => {
+                    summary.unmapped_tickers.insert(quote.ticker.clone());
+                    quote.ticker.to_uppercase()
+                }
+            };
+            let record = map(quote, instrument);
+            merged.insert((record.date, record.instrument.clone()), record);
+        }
+    }
+    summary.files = files.len();
+    summary.rows = merged.len();
+
+    let mut wtr = util::csv::default_writer(output)?;
+    for record in merged.into_values() {
+        wtr.serialize(record)?;
+    }
+    wtr.flush()?;
+
+    Ok(summary)
+}
+
+fn map(quote: Quote, instrument: String) -> MarketData {
+    let instrument_type = if gpw::market_data::is_index(&instrument) {
+        SeriesType::Index
+    } else {
+        SeriesType::Stock
+    };
+    MarketData {
+        date: quote.date,
+        instrument,
+        isin: None,
+        opening_price: quote.open,
+        max_price: quote.high,
+        min_price: quote.low,
+        closing_price: quote.close,
+        volume: quote.volume,
+        transaction_number: None,
+        open_position_number: None,
+        instrument_type,
+    }
+}
+
+fn find_files(inputs: &[PathBuf]) -> Result<Vec<PathBuf>, PortfolioError> {
+    let mut files = Vec::new();
+    for input in inputs {
+        if input.is_dir() {
+            let mut entries: Vec<PathBuf> = Vec::new();
+            for entry in fs::read_dir(input)? {
+                entries.push(entry?.path());
+            }
+            files.extend(find_files(&entries)?);
+        } else if input
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| EXTENSIONS.contains(&e.to_lowercase().as_str()))
+        {
+            files.push(input.clone());
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn read_file(file: &Path) -> Result<Vec<Quote>, PortfolioError> {
+    let mut rdr = csv::ReaderBuilder::new()
+        .delimiter(b',')
+        .has_headers(true)
+        .trim(csv::Trim::All)
+        .from_path(file)?;
+
+    let layout = layout(rdr.headers()?).ok_or_else(|| {
+        PortfolioError::InputError(format!(
+            "Unknown Stooq file layout: {}, headers: {:?}",
+            file.display(),
+            rdr.headers().ok()
+        ))
+    })?;
+
+    let file_ticker = ticker_from_file_name(file);
+
+    let mut quotes = Vec::new();
+    for result in rdr.records() {
+        let record = result?;
+        let ticker = match layout.ticker {
+            Some(idx) => field(&record, idx)?.to_string(),
+            None => file_ticker.clone(),
+        };
+        quotes.push(Quote {
+            ticker,
+            date: parse_date(field(&record, layout.date)?)?,
+            open: parse_price(field(&record, layout.open)?)?,
+            high: parse_price(field(&record, layout.high)?)?,
+            low: parse_price(field(&record, layout.low)?)?,
+            close: parse_price(field(&record, layout.close)?)?,
+            volume: match layout.volume {
+                Some(idx) => parse_volume(field(&record, idx)?)?,
+                None => None,
+            },
+        });
+    }
+    Ok(quotes)
+}
+
+fn layout(headers: &StringRecord) -> Option<Layout> {
+    let find = |names: &[&str]| {
+        headers
+            .iter()
+            .position(|h| names.iter().any(|n| n.eq_ignore_ascii_case(h)))
+    };
+
+    Some(Layout {
+        ticker: find(&["<TICKER>"]),
+        date: find(&["<DATE>", "Date", "Data"])?,
+        open: find(&["<OPEN>", "Open", "Otwarcie"])?,
+        high: find(&["<HIGH>", "High", "Najwyzszy"])?,
+        low: find(&["<LOW>", "Low", "Najnizszy"])?,
+        close: find(&["<CLOSE>", "Close", "Zamkniecie"])?,
+        volume: find(&["<VOL>", "Volume", "Wolumen"]),
+    })
+}
+
+/// `pko_d.csv` -> `pko`
+fn ticker_from_file_name(file: &Path) -> String {
+    let stem = file
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default();
+    stem.strip_suffix("_d").unwrap_or(stem).to_string()
+}
+
+fn field(record: &StringRecord, idx: usize) -> Result<&str, PortfolioError> {
+    record.get(idx).ok_or_else(|| {
+        PortfolioError::InvalidValue(format!("missing column {idx} in record {record:?}"))
+    })
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate, PortfolioError> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .or_else(|_| NaiveDate::parse_from_str(value, "%Y%m%d"))
+        .map_err(|e| PortfolioError::InvalidValue(format!("date {value:?}: {e}")))
+}
+
+fn parse_price(value: &str) -> Result<f64, PortfolioError> {
+    value
+        .parse::<f64>()
+        .map_err(|e| PortfolioError::InvalidValue(format!("price {value:?}: {e}")))
+}
+
+fn parse_volume(value: &str) -> Result<Option<i64>, PortfolioError> {
+    if value.is_empty() {
+        return Ok(None);
+    }
+    let volume = value
+        .parse::<f64>()
+        .map_err(|e| PortfolioError::InvalidValue(format!("volume {value:?}: {e}")))?;
+    Ok(Some(volume.round() as i64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layout_bulk_archive_header_ticker_column_found() {
+        let headers = StringRecord::from(vec![
+            "<TICKER>",
+            "<PER>",
+            "<DATE>",
+            "<TIME>",
+            "<OPEN>",
+            "<HIGH>",
+            "<LOW>",
+            "<CLOSE>",
+            "<VOL>",
+            "<OPENINT>",
+        ]);
+
+        let layout = layout(&headers).unwrap();
+
+        assert_eq!(layout.ticker, Some(0));
+        assert_eq!(layout.date, 2);
+        assert_eq!(layout.close, 7);
+        assert_eq!(layout.volume, Some(8));
+    }
+
+    #[test]
+    fn layout_polish_single_file_header_no_ticker_column() {
+        let headers = StringRecord::from(vec![
+            "Data",
+            "Otwarcie",
+            "Najwyzszy",
+            "Najnizszy",
+            "Zamkniecie",
+            "Wolumen",
+        ]);
+
+        let layout = layout(&headers).unwrap();
+
+        assert_eq!(layout.ticker, None);
+        assert_eq!(layout.close, 4);
+    }
+
+    #[test]
+    fn parse_date_archive_format_return_date() {
+        let expected = NaiveDate::from_ymd_opt(2025, 10, 17).unwrap();
+
+        assert_eq!(parse_date("20251017").unwrap(), expected);
+        assert_eq!(parse_date("2025-10-17").unwrap(), expected);
+    }
+}
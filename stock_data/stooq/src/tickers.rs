@@ -0,0 +1,40 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::Deserialize;
+use shared_contracts::errors::PortfolioError;
+
+/// Stooq ticker to instrument name mapping, case insensitive.
+#[derive(Debug, Clone, Default)]
+pub struct TickerMap {
+    instruments: HashMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TickerRow {
+    ticker: String,
+    instrument: String,
+}
+
+impl TickerMap {
+    /// Reads a `ticker;instrument` csv.
+    pub fn load(tickers_csv: &Path) -> Result<Self, PortfolioError> {
+        let mut rdr = util::csv::default_reader(tickers_csv)?;
+
+        let mut instruments = HashMap::new();
+        for result in rdr.deserialize() {
+            let row: TickerRow = result?;
+            instruments.insert(normalize(&row.ticker), row.instrument.trim().to_string());
+        }
+        Ok(TickerMap { instruments })
+    }
+
+    /// Instrument name for the ticker, `None` when the ticker is not mapped.
+    pub fn get(&self, ticker: &str) -> Option<&str> {
+        self.instruments.get(&normalize(ticker)).map(|s| s.as_str())
+    }
+}
+
+fn normalize(ticker: &str) -> String {
+    ticker.trim().to_lowercase()
+}
@@ -0,0 +1,173 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::Read;
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+use chrono::{Datelike, NaiveDate, Weekday};
+use serde::Deserialize;
+use shared_contracts::errors::PortfolioError;
+
+static GPW_CLOSED_DAYS: &str = include_str!("../data/gpw_closed_days.csv");
+
+/// Sessions between the trade and its settlement (T+2).
+pub const SETTLEMENT_SESSIONS: u32 = 2;
+
+#[derive(Debug, Deserialize)]
+struct ClosedDay {
+    date: NaiveDate,
+    name: String,
+}
+
+/// Exchange session calendar.
+#[derive(Debug, Clone)]
+pub struct TradingCalendar {
+    closed: BTreeMap<NaiveDate, String>,
+    years: BTreeSet<i32>,
+}
+
+impl Default for TradingCalendar {
+    fn default() -> Self {
+        TradingCalendar::gpw()
+    }
+}
+
+impl TradingCalendar {
+    /// GPW calendar from the bundled closed days file.
+    pub fn gpw() -> Self {
+        TradingCalendar::from_reader(GPW_CLOSED_DAYS.as_bytes())
+            .expect("bundled GPW closed days file is valid")
+    }
+
+    /// Calendar from a `date;name` csv of closed days.
+    pub fn load(closed_days_csv: &Path) -> Result<Self, PortfolioError> {
+        TradingCalendar::from_reader(File::open(closed_days_csv)?)
+    }
+
+    fn from_reader(reader: impl Read) -> Result<Self, PortfolioError> {
+        let mut rdr = csv::ReaderBuilder::new()
+            .delimiter(b';')
+            .has_headers(true)
+            .from_reader(reader);
+        TradingCalendar::from_records(rdr.deserialize())
+    }
+
+    fn from_records(
+        records: impl Iterator<Item = Result<ClosedDay, csv::Error>>,
+    ) -> Result<Self, PortfolioError> {
+        let mut calendar = TradingCalendar {
+            closed: BTreeMap::new(),
+            years: BTreeSet::new(),
+        };
+        for record in records {
+            let day = record?;
+            calendar.years.insert(day.date.year());
+            calendar.closed.insert(day.date, day.name);
+        }
+        Ok(calendar)
+    }
+
+    /// Whether closed days of `year` are known. Uncovered years only skip weekends.
+    pub fn covers(&self, year: i32) -> bool {
+        self.years.contains(&year)
+    }
+
+    /// Name of the holiday or closed day, `None` for sessions and weekends.
+    pub fn closed_day(&self, date: NaiveDate) -> Option<&str> {
+        self.closed.get(&date).map(String::as_str)
+    }
+
+    pub fn is_session(&self, date: NaiveDate) -> bool {
+        !matches!(date.weekday(), Weekday::Sat | Weekday::Sun) && !self.closed.contains_key(&date)
+    }
+
+    /// First session after `date`.
+    pub fn next_session(&self, date: NaiveDate) -> NaiveDate {
+        self.add_sessions(date, 1)
+    }
+
+    /// Last session before `date`.
+    pub fn previous_session(&self, date: NaiveDate) -> NaiveDate {
+        let mut day = date;
+        loop {
+            day = day.pred_opt().unwrap_or(NaiveDate::MIN);
+            if self.is_session(day) || day == NaiveDate::MIN {
+                return day;
+            }
+        }
+    }
+
+    /// The `sessions`-th session after `date`, `date` itself is not counted.
+    pub fn add_sessions(&self, date: NaiveDate, sessions: u32) -> NaiveDate {
+        let mut day = date;
+        let mut left = sessions;
+        while left > 0 {
+            day = day.succ_opt().unwrap_or(NaiveDate::MAX);
+            if self.is_session(day) || day == NaiveDate::MAX {
+                left -= 1;
+            }
+        }
+        day
+    }
+
+    /// Settlement (T+2) of a trade made on `trade_date`. A trade on a closed day,
+    /// e.g. an order submitted on a weekend, trades on the next session.
+    pub fn settlement_date(&self, trade_date: NaiveDate) -> NaiveDate {
+        let session = if self.is_session(trade_date) {
+            trade_date
+        } else {
+            self.next_session(trade_date)
+        };
+        self.add_sessions(session, SETTLEMENT_SESSIONS)
+    }
+
+    /// Sessions within `range`, oldest first.
+    pub fn sessions(&self, range: RangeInclusive<NaiveDate>) -> Vec<NaiveDate> {
+        range
+            .start()
+            .iter_days()
+            .take_while(|d| d <= range.end())
+            .filter(|d| self.is_session(*d))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settlement_date_over_easter_skips_closed_days() {
+        let calendar = TradingCalendar::gpw();
+
+        // Thursday before Good Friday, Easter Monday is closed as well.
+        assert_eq!(
+            calendar.settlement_date(_date("2025-04-17")),
+            _date("2025-04-23")
+        );
+        assert_eq!(
+            calendar.settlement_date(_date("2025-10-17")),
+            _date("2025-10-21")
+        );
+        assert!(calendar.covers(2025));
+        assert!(!calendar.is_session(_date("2025-12-24")));
+    }
+
+    #[test]
+    fn next_session_before_christmas_return_first_session_after() {
+        let calendar = TradingCalendar::gpw();
+
+        assert_eq!(
+            calendar.next_session(_date("2025-12-23")),
+            _date("2025-12-29")
+        );
+        assert_eq!(
+            calendar.previous_session(_date("2026-01-02")),
+            _date("2025-12-30")
+        );
+    }
+
+    fn _date(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+    }
+}
@@ -2,13 +2,19 @@ use crate::mapper;
 use crate::model::{Csv, HEADER};
 use encoding_rs::WINDOWS_1250;
 use shared_contracts::errors::PortfolioError;
+use shared_contracts::models::instrument::InstrumentMaster;
 use shared_contracts::models::trade_order::TradeOrder;
 use std::fs;
 use std::io::Cursor;
 use std::path::Path;
 
-pub fn normalize(input: &Path, output: &Path) -> Result<(), PortfolioError> {
-    let records = load(input)?;
+/// Writes the normalized trade orders, instrument symbols resolved through `instruments`.
+pub fn normalize(
+    input: &Path,
+    output: &Path,
+    instruments: &InstrumentMaster,
+) -> Result<(), PortfolioError> {
+    let records = load(input, instruments)?;
 
     let mut wtr = util::csv::default_writer(output)?;
 
@@ -20,7 +26,19 @@ pub fn normalize(input: &Path, output: &Path) -> Result<(), PortfolioError> {
     Ok(())
 }
 
-fn load(file_path: &Path) -> Result<Vec<TradeOrder>, PortfolioError> {
+/// Reads the trade orders of the export, instrument symbols resolved through `instruments`.
+pub fn load(
+    input: &Path,
+    instruments: &InstrumentMaster,
+) -> Result<Vec<TradeOrder>, PortfolioError> {
+    let mut records = read(input)?;
+    for record in records.iter_mut() {
+        record.instrument = instruments.symbol(&record.instrument).to_string();
+    }
+    Ok(records)
+}
+
+fn read(file_path: &Path) -> Result<Vec<TradeOrder>, PortfolioError> {
     let full_input = decode_windows1250(file_path)?;
     let csv_data_bytes = remove_metadata(full_input)?;
     let csv_model = parse(csv_data_bytes)?;
@@ -1,9 +1,17 @@
 use std::{fs::File, path::Path};
 
-use csv::{Writer, WriterBuilder};
+use csv::{Reader, ReaderBuilder, Writer, WriterBuilder};
 use shared_contracts::errors::PortfolioError;
 
 pub fn default_writer(path: &Path) -> Result<Writer<File>, PortfolioError> {
     let w = WriterBuilder::new().delimiter(b';').from_path(path)?;
     Ok(w)
 }
+
+pub fn default_reader(path: &Path) -> Result<Reader<File>, PortfolioError> {
+    let r = ReaderBuilder::new()
+        .delimiter(b';')
+        .has_headers(true)
+        .from_path(path)?;
+    Ok(r)
+}
@@ -1,3 +1,6 @@
 pub mod csv;
 pub mod file;
+pub mod math;
+pub mod money;
 pub mod polars;
+pub mod report;
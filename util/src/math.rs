@@ -0,0 +1,26 @@
+/// Rounds half away from zero to `scale` decimal places, -0 (an empty sum) to 0.
+pub fn round(value: f64, scale: i32) -> f64 {
+    let factor = 10_f64.powi(scale);
+    (value * factor).round() / factor + 0_f64
+}
+
+/// Arithmetic mean, 0 for no values.
+pub fn mean(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0_f64;
+    }
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+/// Sample covariance, 0 for fewer than two pairs.
+pub fn covariance(a: &[f64], b: &[f64]) -> f64 {
+    if a.len() < 2 {
+        return 0_f64;
+    }
+    let (mean_a, mean_b) = (mean(a), mean(b));
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| (x - mean_a) * (y - mean_b))
+        .sum::<f64>()
+        / (a.len() - 1) as f64
+}
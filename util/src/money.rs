@@ -0,0 +1,71 @@
+use polars::prelude::*;
+
+use crate::math::round;
+
+/// Tax on capital gains and dividends.
+pub const TAX_RATE: f64 = 0.19;
+
+/// mBank eMakler commission, % of the order value.
+pub const DEFAULT_COMMISSION_PCT: f64 = 0.39;
+/// mBank eMakler minimum commission of an order, PLN.
+pub const DEFAULT_COMMISSION_MIN: f64 = 5.0;
+
+/// Broker commission of an order, a percentage of its value but at least the minimum.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Commission {
+    /// Fraction of the order value.
+    pub percent: f64,
+    pub min: f64,
+}
+
+impl Default for Commission {
+    fn default() -> Self {
+        Commission::from_pct(DEFAULT_COMMISSION_PCT, DEFAULT_COMMISSION_MIN)
+    }
+}
+
+impl Commission {
+    /// Commission of `pct` % of the order value, at least `min`.
+    pub fn from_pct(pct: f64, min: f64) -> Self {
+        Commission {
+            percent: pct / 100_f64,
+            min,
+        }
+    }
+
+    pub fn amount(&self, value: f64) -> f64 {
+        round((value * self.percent).max(self.min), 2)
+    }
+
+    /// `amount` of a value column.
+    pub fn expr(&self, value: Expr) -> Expr {
+        let by_percent = value * lit(self.percent);
+        when(by_percent.clone().gt(lit(self.min)))
+            .then(by_percent)
+            .otherwise(lit(self.min))
+            .round(2, RoundMode::HalfToEven)
+    }
+}
+
+/// Tax of a tax base, negative for a loss.
+pub fn tax(base: f64) -> f64 {
+    round(base * TAX_RATE, 2)
+}
+
+/// `tax` of a tax base column.
+pub fn tax_expr(base: Expr) -> Expr {
+    (base * lit(TAX_RATE)).round(2, RoundMode::HalfToEven)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn amount_small_and_large_order_return_minimum_or_percent() {
+        let commission = Commission::default();
+
+        assert_eq!(commission.amount(1000.0), 5.0);
+        assert_eq!(commission.amount(10000.0), 39.0);
+    }
+}
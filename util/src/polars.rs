@@ -3,6 +3,7 @@ use std::{fs::File, path::Path};
 use polars::prelude::CsvWriter;
 use polars::prelude::*;
 use shared_contracts::errors::PortfolioError;
+use shared_contracts::models::instrument::InstrumentMaster;
 
 pub fn default_lazy_reder(path: &Path) -> Result<LazyCsvReader, PortfolioError> {
     let p = path.to_string_lossy().to_string();
@@ -20,3 +21,27 @@ pub fn default_writer(path: &mut File) -> Result<CsvWriter<&mut File>, Portfolio
         .with_separator(b';');
     Ok(writer)
 }
+
+/// Replaces names in the `instrument` column with canonical symbols of `instruments`,
+/// unknown names are kept.
+pub fn resolve_instruments(lf: LazyFrame, instruments: &InstrumentMaster) -> LazyFrame {
+    if instruments.is_empty() {
+        return lf;
+    }
+    let (keys, symbols): (Vec<&str>, Vec<&str>) = instruments.keys().unzip();
+
+    lf.with_column(
+        col("instrument")
+            .str()
+            .strip_chars(lit(NULL))
+            .str()
+            .to_uppercase()
+            .replace_strict(
+                lit(Series::new("key".into(), keys)),
+                lit(Series::new("symbol".into(), symbols)),
+                Some(col("instrument")),
+                Some(DataType::String),
+            )
+            .alias("instrument"),
+    )
+}
@@ -0,0 +1,24 @@
+use std::{fs::File, path::Path};
+
+use polars::prelude::SerWriter;
+use shared_contracts::errors::PortfolioError;
+
+use super::{Report, ReportWriter};
+
+/// Semicolon separated CSV. The summary is written to a sibling
+/// `<name>_summary.csv` file so the data file stays parseable.
+pub(super) struct CsvReportWriter;
+
+impl ReportWriter for CsvReportWriter {
+    fn write(&self, report: &mut Report, output: &Path) -> Result<(), PortfolioError> {
+        if let Some(summary) = report.summary.as_mut() {
+            let summary_path = crate::file::new_file_with_suffix(output, "summary.csv")?;
+            let mut file = File::create(summary_path)?;
+            crate::polars::default_writer(&mut file)?.finish(summary)?;
+        }
+
+        let mut file = File::create(output)?;
+        crate::polars::default_writer(&mut file)?.finish(&mut report.data)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,99 @@
+use std::{fs::File, io::Write, path::Path};
+
+use polars::prelude::DataFrame;
+use shared_contracts::errors::PortfolioError;
+
+use super::{Report, ReportWriter};
+
+static STYLE: &str = "body{font-family:sans-serif;margin:2em;color:#222}\
+table{border-collapse:collapse;margin-bottom:2em}\
+th,td{border:1px solid #ccc;padding:4px 8px}\
+th{background:#f0f0f0;text-align:left}\
+td.num{text-align:right}\
+tr:nth-child(even){background:#fafafa}\
+tr.highlight{background:#ffe8a8}";
+
+/// Standalone HTML page, no external resources.
+pub(super) struct HtmlReportWriter;
+
+impl ReportWriter for HtmlReportWriter {
+    fn write(&self, report: &mut Report, output: &Path) -> Result<(), PortfolioError> {
+        let mut file = File::create(output)?;
+        let title = escape(&report.title);
+
+        writeln!(file, "<!DOCTYPE html>")?;
+        writeln!(file, "<html lang=\"en\">")?;
+        writeln!(file, "<head>")?;
+        writeln!(file, "<meta charset=\"utf-8\">")?;
+        writeln!(file, "<title>{title}</title>")?;
+        writeln!(file, "<style>{STYLE}</style>")?;
+        writeln!(file, "</head>")?;
+        writeln!(file, "<body>")?;
+        writeln!(file, "<h1>{title}</h1>")?;
+
+        if let Some(summary) = report.summary.as_ref() {
+            writeln!(file, "<h2>Summary</h2>")?;
+            write_table(&mut file, summary, None)?;
+            writeln!(file, "<h2>Details</h2>")?;
+        }
+        write_table(&mut file, &report.data, report.highlight.as_deref())?;
+
+        writeln!(file, "</body>")?;
+        writeln!(file, "</html>")?;
+        Ok(())
+    }
+}
+
+fn write_table(
+    file: &mut File,
+    df: &DataFrame,
+    highlight: Option<&str>,
+) -> Result<(), PortfolioError> {
+    let highlighted: Vec<bool> = match highlight {
+        Some(column) => df
+            .column(column)?
+            .bool()?
+            .into_iter()
+            .map(|v| v.unwrap_or(false))
+            .collect(),
+        None => vec![false; df.height()],
+    };
+    let numeric: Vec<bool> = df
+        .get_columns()
+        .iter()
+        .map(|c| c.dtype().is_primitive_numeric())
+        .collect();
+
+    writeln!(file, "<table>")?;
+    write!(file, "<tr>")?;
+    for name in super::header(df) {
+        write!(file, "<th>{}</th>", escape(&name))?;
+    }
+    writeln!(file, "</tr>")?;
+
+    for (row, highlighted) in super::rows(df)?.iter().zip(highlighted) {
+        if highlighted {
+            write!(file, "<tr class=\"highlight\">")?;
+        } else {
+            write!(file, "<tr>")?;
+        }
+        for (cell, is_numeric) in row.iter().zip(&numeric) {
+            if *is_numeric {
+                write!(file, "<td class=\"num\">{}</td>", escape(cell))?;
+            } else {
+                write!(file, "<td>{}</td>", escape(cell))?;
+            }
+        }
+        writeln!(file, "</tr>")?;
+    }
+    writeln!(file, "</table>")?;
+    Ok(())
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
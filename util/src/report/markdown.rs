@@ -0,0 +1,41 @@
+use std::{fs::File, io::Write, path::Path};
+
+use polars::prelude::DataFrame;
+use shared_contracts::errors::PortfolioError;
+
+use super::{Report, ReportWriter};
+
+pub(super) struct MarkdownReportWriter;
+
+impl ReportWriter for MarkdownReportWriter {
+    fn write(&self, report: &mut Report, output: &Path) -> Result<(), PortfolioError> {
+        let mut file = File::create(output)?;
+
+        writeln!(file, "# {}\n", report.title)?;
+
+        if let Some(summary) = report.summary.as_ref() {
+            writeln!(file, "## Summary\n")?;
+            write_table(&mut file, summary)?;
+            writeln!(file)?;
+            writeln!(file, "## Details\n")?;
+        }
+
+        write_table(&mut file, &report.data)?;
+        Ok(())
+    }
+}
+
+fn write_table(file: &mut File, df: &DataFrame) -> Result<(), PortfolioError> {
+    let header = super::header(df);
+    writeln!(file, "| {} |", escape_row(&header).join(" | "))?;
+    writeln!(file, "|{}", " --- |".repeat(header.len()))?;
+
+    for row in super::rows(df)? {
+        writeln!(file, "| {} |", escape_row(&row).join(" | "))?;
+    }
+    Ok(())
+}
+
+fn escape_row(row: &[String]) -> Vec<String> {
+    row.iter().map(|cell| cell.replace('|', "\\|")).collect()
+}
@@ -0,0 +1,150 @@
+mod csv;
+mod html;
+mod markdown;
+mod parquet;
+mod xlsx;
+
+use std::{fmt, path::Path, str::FromStr};
+
+use polars::prelude::*;
+use shared_contracts::errors::PortfolioError;
+
+/// Output format of a generated report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReportFormat {
+    #[default]
+    Csv,
+    Parquet,
+    Xlsx,
+    Markdown,
+    Html,
+}
+
+impl ReportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ReportFormat::Csv => "csv",
+            ReportFormat::Parquet => "parquet",
+            ReportFormat::Xlsx => "xlsx",
+            ReportFormat::Markdown => "md",
+            ReportFormat::Html => "html",
+        }
+    }
+}
+
+impl FromStr for ReportFormat {
+    type Err = PortfolioError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "csv" => Ok(ReportFormat::Csv),
+            "parquet" => Ok(ReportFormat::Parquet),
+            "xlsx" => Ok(ReportFormat::Xlsx),
+            "md" | "markdown" => Ok(ReportFormat::Markdown),
+            "html" => Ok(ReportFormat::Html),
+            _ => Err(PortfolioError::InputError(format!(
+                "Unknown report format: {s:?}, expected one of csv, parquet, xlsx, markdown, html"
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for ReportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ReportFormat::Csv => "csv",
+            ReportFormat::Parquet => "parquet",
+            ReportFormat::Xlsx => "xlsx",
+            ReportFormat::Markdown => "markdown",
+            ReportFormat::Html => "html",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Report content handed to a [`ReportWriter`].
+///
+/// `summary` is an optional one-row (or small) frame with report totals,
+/// `data` holds the report rows. `highlight` names a boolean `data` column,
+/// the html backend highlights the rows where it is true.
+pub struct Report {
+    pub title: String,
+    pub summary: Option<DataFrame>,
+    pub data: DataFrame,
+    pub highlight: Option<String>,
+}
+
+impl Report {
+    pub fn new(title: &str, data: DataFrame) -> Self {
+        Report {
+            title: title.to_string(),
+            summary: None,
+            data,
+            highlight: None,
+        }
+    }
+
+    pub fn with_summary(mut self, summary: DataFrame) -> Self {
+        self.summary = Some(summary);
+        self
+    }
+
+    pub fn with_highlight(mut self, column: &str) -> Self {
+        self.highlight = Some(column.to_string());
+        self
+    }
+}
+
+pub trait ReportWriter {
+    fn write(&self, report: &mut Report, output: &Path) -> Result<(), PortfolioError>;
+}
+
+pub fn writer(format: ReportFormat) -> Box<dyn ReportWriter> {
+    match format {
+        ReportFormat::Csv => Box::new(csv::CsvReportWriter),
+        ReportFormat::Parquet => Box::new(parquet::ParquetReportWriter),
+        ReportFormat::Xlsx => Box::new(xlsx::XlsxReportWriter),
+        ReportFormat::Markdown => Box::new(markdown::MarkdownReportWriter),
+        ReportFormat::Html => Box::new(html::HtmlReportWriter),
+    }
+}
+
+/// Writes `report` to `output` using the backend selected by `format`.
+pub fn save(
+    report: &mut Report,
+    output: &Path,
+    format: ReportFormat,
+) -> Result<(), PortfolioError> {
+    writer(format).write(report, output)
+}
+
+/// Text representation of a single cell, shared by the text based backends.
+fn cell_to_string(value: &AnyValue) -> String {
+    match value {
+        AnyValue::Null => String::new(),
+        AnyValue::String(s) => s.to_string(),
+        AnyValue::StringOwned(s) => s.to_string(),
+        AnyValue::Float64(v) => v.to_string(),
+        AnyValue::Float32(v) => v.to_string(),
+        other => other.to_string(),
+    }
+}
+
+fn rows(df: &DataFrame) -> Result<Vec<Vec<String>>, PortfolioError> {
+    let mut rows = Vec::with_capacity(df.height());
+    for i in 0..df.height() {
+        let mut row = Vec::with_capacity(df.width());
+        for column in df.get_columns() {
+            row.push(cell_to_string(&column.get(i)?));
+        }
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+fn header(df: &DataFrame) -> Vec<String> {
+    df.get_column_names()
+        .iter()
+        .map(|name| name.to_string())
+        .collect()
+}
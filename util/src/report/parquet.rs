@@ -0,0 +1,24 @@
+use std::{fs::File, path::Path};
+
+use polars::prelude::ParquetWriter;
+use shared_contracts::errors::PortfolioError;
+
+use super::{Report, ReportWriter};
+
+/// Parquet file with the report rows. The summary goes to a sibling
+/// `<name>_summary.parquet` file.
+pub(super) struct ParquetReportWriter;
+
+impl ReportWriter for ParquetReportWriter {
+    fn write(&self, report: &mut Report, output: &Path) -> Result<(), PortfolioError> {
+        if let Some(summary) = report.summary.as_mut() {
+            let summary_path = crate::file::new_file_with_suffix(output, "summary.parquet")?;
+            let mut file = File::create(summary_path)?;
+            ParquetWriter::new(&mut file).finish(summary)?;
+        }
+
+        let mut file = File::create(output)?;
+        ParquetWriter::new(&mut file).finish(&mut report.data)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,134 @@
+use std::path::Path;
+
+use polars::prelude::*;
+use rust_xlsxwriter::{Format, Workbook, Worksheet, XlsxError};
+use shared_contracts::errors::PortfolioError;
+
+use super::{Report, ReportWriter};
+
+/// Days between 1899-12-30 (Excel epoch) and 1970-01-01.
+const EXCEL_UNIX_EPOCH_DAYS: f64 = 25569.0;
+
+/// XLSX workbook with a `Report` sheet and, when present, a `Summary` sheet.
+/// Numeric and temporal columns are written as typed cells with number formats.
+pub(super) struct XlsxReportWriter;
+
+impl ReportWriter for XlsxReportWriter {
+    fn write(&self, report: &mut Report, output: &Path) -> Result<(), PortfolioError> {
+        let mut workbook = Workbook::new();
+
+        if let Some(summary) = report.summary.as_ref() {
+            let sheet = workbook.add_worksheet();
+            sheet.set_name("Summary").map_err(xls_error)?;
+            write_sheet(sheet, summary)?;
+        }
+
+        let sheet = workbook.add_worksheet();
+        sheet.set_name("Report").map_err(xls_error)?;
+        write_sheet(sheet, &report.data)?;
+
+        workbook.save(output).map_err(xls_error)?;
+        Ok(())
+    }
+}
+
+fn write_sheet(sheet: &mut Worksheet, df: &DataFrame) -> Result<(), PortfolioError> {
+    let header_format = Format::new().set_bold();
+    let money_format = Format::new().set_num_format("#,##0.00");
+    let integer_format = Format::new().set_num_format("#,##0");
+    let date_format = Format::new().set_num_format("yyyy-mm-dd");
+    let datetime_format = Format::new().set_num_format("yyyy-mm-dd hh:mm:ss");
+
+    for (col_idx, column) in df.get_columns().iter().enumerate() {
+        let col_idx = col_idx as u16;
+        sheet
+            .write_string_with_format(0, col_idx, column.name().as_str(), &header_format)
+            .map_err(xls_error)?;
+
+        for row_idx in 0..df.height() {
+            let row = (row_idx + 1) as u32;
+            match column.get(row_idx)? {
+                AnyValue::Null => {
+                    sheet
+                        .write_blank(row, col_idx, &Format::new())
+                        .map_err(xls_error)?;
+                }
+                AnyValue::Boolean(v) => {
+                    sheet.write_boolean(row, col_idx, v).map_err(xls_error)?;
+                }
+                AnyValue::Float32(v) => {
+                    sheet
+                        .write_number_with_format(row, col_idx, v as f64, &money_format)
+                        .map_err(xls_error)?;
+                }
+                AnyValue::Float64(v) => {
+                    sheet
+                        .write_number_with_format(row, col_idx, v, &money_format)
+                        .map_err(xls_error)?;
+                }
+                AnyValue::Date(days) => {
+                    sheet
+                        .write_number_with_format(
+                            row,
+                            col_idx,
+                            days as f64 + EXCEL_UNIX_EPOCH_DAYS,
+                            &date_format,
+                        )
+                        .map_err(xls_error)?;
+                }
+                AnyValue::Datetime(v, unit, _) | AnyValue::DatetimeOwned(v, unit, _) => {
+                    let per_day = match unit {
+                        TimeUnit::Nanoseconds => 86_400_000_000_000_f64,
+                        TimeUnit::Microseconds => 86_400_000_000_f64,
+                        TimeUnit::Milliseconds => 86_400_000_f64,
+                    };
+                    sheet
+                        .write_number_with_format(
+                            row,
+                            col_idx,
+                            v as f64 / per_day + EXCEL_UNIX_EPOCH_DAYS,
+                            &datetime_format,
+                        )
+                        .map_err(xls_error)?;
+                }
+                v if v.is_integer() => {
+                    let n = v.extract::<i64>().ok_or_else(|| {
+                        PortfolioError::Xls(format!(
+                            "Can't write xlsx report, value {v} of column {} row {row} is not a number",
+                            column.name()
+                        ))
+                    })?;
+                    sheet
+                        .write_number_with_format(row, col_idx, n as f64, &integer_format)
+                        .map_err(xls_error)?;
+                }
+                v => {
+                    sheet
+                        .write_string(row, col_idx, super::cell_to_string(&v))
+                        .map_err(xls_error)?;
+                }
+            }
+        }
+    }
+    sheet.autofit();
+    Ok(())
+}
+
+fn xls_error(e: XlsxError) -> PortfolioError {
+    PortfolioError::Xls(format!("Can't write xlsx report, reason:{e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_integer_out_of_i64_range_return_error() {
+        let df = df!("quantity" => [Some(1_u64), None, Some(u64::MAX)]).unwrap();
+        let mut workbook = Workbook::new();
+
+        let result = write_sheet(workbook.add_worksheet(), &df);
+
+        assert!(matches!(result, Err(PortfolioError::Xls(_))));
+    }
+}